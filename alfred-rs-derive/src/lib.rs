@@ -0,0 +1,195 @@
+//! Derive macros for [alfred-rs].
+//!
+//! Use them through the `derive` feature of `alfred-rs` rather than depending on this crate
+//! directly.
+//!
+//! [alfred-rs]: https://docs.rs/alfred-rs/
+
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitStr, Path};
+
+/// Derive `alfred_rs::data::WorkflowConfig` and `Default` for a struct with named fields.
+///
+/// See the documentation of `WorkflowConfig` for the supported attributes.
+#[proc_macro_derive(WorkflowConfig, attributes(workflow_config))]
+pub fn derive_workflow_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Options set on the struct.
+#[derive(Default)]
+struct ConfigAttrs {
+    file: Option<LitStr>,
+    validate: Option<Path>,
+}
+
+// Options set on a field.
+#[derive(Default)]
+struct FieldAttrs {
+    default: Option<Expr>,
+    env: Option<LitStr>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "WorkflowConfig needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "WorkflowConfig can only be derived for structs",
+            ))
+        }
+    };
+
+    let config = config_attrs(input)?;
+    let file_name = match config.file {
+        Some(file) => file,
+        None => LitStr::new(&format!("{}.json", snake_case(name)), name.span()),
+    };
+
+    let mut defaults = Vec::new();
+    let mut overrides = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let attrs = field_attrs(field)?;
+        let default = match attrs.default {
+            Some(expr) => quote! { #expr },
+            None => quote! { ::std::default::Default::default() },
+        };
+        defaults.push(quote! { #ident: #default });
+        if let Some(var) = attrs.env {
+            overrides.push(quote! {
+                if let ::std::option::Option::Some(value) = ::std::env::var(#var)
+                    .ok()
+                    .and_then(|value| value.parse().ok())
+                {
+                    self.#ident = value;
+                }
+            });
+        }
+    }
+
+    let validate = config.validate.map(|validate| {
+        quote! {
+            fn validate(&self) -> ::alfred_rs::__private::Result<()> {
+                #validate(self)
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::std::default::Default for #name #ty_generics #where_clause {
+            fn default() -> Self {
+                #name {
+                    #(#defaults,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::alfred_rs::data::WorkflowConfig for #name #ty_generics #where_clause {
+            const FILE_NAME: &'static str = #file_name;
+
+            fn apply_env_overrides(&mut self) {
+                #(#overrides)*
+            }
+
+            #validate
+        }
+    })
+}
+
+fn config_attrs(input: &DeriveInput) -> syn::Result<ConfigAttrs> {
+    let mut config = ConfigAttrs::default();
+    for attr in &input.attrs {
+        if !attr.path().is_ident("workflow_config") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("file") {
+                config.file = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("validate") {
+                let path: LitStr = meta.value()?.parse()?;
+                config.validate = Some(path.parse()?);
+            } else {
+                return Err(meta.error("expected `file` or `validate`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(config)
+}
+
+fn field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in &field.attrs {
+        if !attr.path().is_ident("workflow_config") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                let expr: LitStr = meta.value()?.parse()?;
+                attrs.default = Some(expr.parse()?);
+            } else if meta.path.is_ident("env") {
+                attrs.env = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `default` or `env`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+// `MySettings` -> `my_settings`, `HTTPSettings` -> `http_settings`
+fn snake_case(ident: &Ident) -> String {
+    let chars: Vec<char> = ident.to_string().chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            // A word starts at an uppercase letter that follows a lowercase one, or that ends a
+            // run of uppercase letters (an acronym) and is followed by a lowercase one.
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |c| c.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn it_converts_names_to_snake_case() {
+        let snake = |name| snake_case(&Ident::new(name, Span::call_site()));
+        assert_eq!("my_settings", snake("MySettings"));
+        assert_eq!("http_settings", snake("HTTPSettings"));
+        assert_eq!("github_api", snake("GithubAPI"));
+        assert_eq!("oauth2_token", snake("Oauth2Token"));
+        assert_eq!("settings", snake("settings"));
+    }
+}
@@ -0,0 +1,71 @@
+#[macro_use]
+extern crate serde_derive;
+
+use alfred_rs::data::WorkflowConfig;
+use std::env;
+
+#[derive(Debug, Serialize, Deserialize, WorkflowConfig)]
+#[serde(default)]
+#[workflow_config(validate = "check_settings")]
+struct SearchSettings {
+    #[workflow_config(default = "20", env = "derive_test_page_size")]
+    page_size: u32,
+    #[workflow_config(default = "String::from(\"en\")", env = "derive_test_language")]
+    language: String,
+    api_token: Option<String>,
+}
+
+fn check_settings(settings: &SearchSettings) -> anyhow::Result<()> {
+    anyhow::ensure!(settings.page_size > 0, "page size can't be 0");
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, WorkflowConfig)]
+#[workflow_config(file = "custom.json")]
+struct Custom {
+    count: u32,
+}
+
+// Tests set the workflow's env variables, keep them in one test to avoid races.
+#[test]
+fn it_derives_workflow_config() {
+    let dir = tempfile::tempdir().unwrap();
+    env::set_var("alfred_workflow_data", dir.path());
+    env::set_var("alfred_workflow_cache", dir.path());
+
+    assert_eq!("search_settings.json", SearchSettings::FILE_NAME);
+    assert_eq!("custom.json", Custom::FILE_NAME);
+    assert_eq!(0, Custom::default().count);
+
+    let defaults = SearchSettings::default();
+    assert_eq!(20, defaults.page_size);
+    assert_eq!("en", defaults.language);
+    assert!(defaults.api_token.is_none());
+
+    // Nothing saved yet: defaults.
+    let mut settings = SearchSettings::load().unwrap();
+    assert_eq!(20, settings.page_size);
+    settings.api_token = Some("secret".to_string());
+    settings.page_size = 50;
+    settings.save().unwrap();
+
+    let settings = SearchSettings::load().unwrap();
+    assert_eq!(50, settings.page_size);
+    assert_eq!(Some("secret"), settings.api_token.as_deref());
+
+    // Workflow variables override saved values, unparsable ones are ignored.
+    env::set_var("derive_test_page_size", "10");
+    env::set_var("derive_test_language", "fr");
+    let settings = SearchSettings::load().unwrap();
+    assert_eq!(10, settings.page_size);
+    assert_eq!("fr", settings.language);
+    env::set_var("derive_test_page_size", "ten");
+    assert_eq!(50, SearchSettings::load().unwrap().page_size);
+
+    // Invalid settings can't be loaded.
+    env::set_var("derive_test_page_size", "0");
+    assert!(SearchSettings::load().is_err());
+    env::remove_var("derive_test_page_size");
+    env::remove_var("derive_test_language");
+    assert!(dir.path().join("search_settings.json").exists());
+}
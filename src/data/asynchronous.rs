@@ -0,0 +1,499 @@
+use super::{forget_read, serde_json, Data, Format, Result, Secret, TypeMismatch};
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::{to_value, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+// Numbers the temporary files written by this process, see `write_atomically()`.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Async API of [`Data`], for workflows that run on `tokio`
+///
+/// Same as `Data`, but methods that read or write files return futures, so that async
+/// workflows don't block their runtime on disk I/O. Getters, which only read the data in
+/// memory, are the same as `Data`'s. Available with the `async` feature.
+///
+/// Data files are locked (with `flock`) while they are loaded and saved, which can't be done
+/// with async I/O: loads and saves run on tokio's blocking thread pool, the same way as
+/// `tokio::fs` does, and behave exactly like `Data`'s. The file helpers ([`save_to_file()`],
+/// [`load_from_file()`], ...) use `tokio::fs`.
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::AsyncData;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let mut wf_data = AsyncData::load("settings.json").await?;
+/// wf_data.set("page_size", &20).await?;
+///
+/// let page_size: u32 = wf_data.get("page_size").unwrap_or(10);
+///
+/// AsyncData::save_to_file("all_my_tweets.cache", &vec!["chirp1", "chirp2"]).await?;
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// [`Data`]: struct.Data.html
+/// [`save_to_file()`]: struct.AsyncData.html#method.save_to_file
+/// [`load_from_file()`]: struct.AsyncData.html#method.load_from_file
+#[derive(Debug)]
+pub struct AsyncData {
+    data: Arc<Mutex<Data>>,
+}
+
+impl From<Data> for AsyncData {
+    fn from(data: Data) -> Self {
+        AsyncData {
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+}
+
+impl AsyncData {
+    /// Loads the workflow data or creates a new one, see [`Data::load()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::load()`].
+    ///
+    /// [`Data::load()`]: struct.Data.html#method.load
+    pub async fn load<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let p = p.as_ref().to_path_buf();
+        let data = tokio::task::spawn_blocking(move || Data::load(p)).await??;
+        Ok(data.into())
+    }
+
+    /// Same as [`load()`] but the data file is in the given [`Format`], see
+    /// [`Data::load_with_format()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::load()`].
+    ///
+    /// [`load()`]: struct.AsyncData.html#method.load
+    /// [`Format`]: format/trait.Format.html
+    /// [`Data::load_with_format()`]: struct.Data.html#method.load_with_format
+    /// [`Data::load()`]: struct.Data.html#method.load
+    pub async fn load_with_format<P, F>(p: P, format: F) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: Format + 'static,
+    {
+        let p = p.as_ref().to_path_buf();
+        let data = tokio::task::spawn_blocking(move || Data::load_with_format(p, format)).await??;
+        Ok(data.into())
+    }
+
+    /// Set the value of key `k` to `v`, and persist it to disk, see [`Data::set()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::set()`].
+    ///
+    /// [`Data::set()`]: struct.Data.html#method.set
+    pub async fn set<K, V>(&mut self, k: K, v: &V) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        let k = k.into();
+        let v = to_value(v)?;
+        self.with_data(move |data| data.set(k, &v)).await
+    }
+
+    /// Set the value of key `k` to `v` for `ttl`, and persist it to disk, see
+    /// [`Data::set_with_ttl()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::set_with_ttl()`].
+    ///
+    /// [`Data::set_with_ttl()`]: struct.Data.html#method.set_with_ttl
+    pub async fn set_with_ttl<K, V>(&mut self, k: K, v: &V, ttl: Duration) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        let k = k.into();
+        let v = to_value(v)?;
+        self.with_data(move |data| data.set_with_ttl(k, &v, ttl))
+            .await
+    }
+
+    /// Set multiple key/value pairs and persist them to disk with a single write, see
+    /// [`Data::set_many()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::set_many()`].
+    ///
+    /// [`Data::set_many()`]: struct.Data.html#method.set_many
+    pub async fn set_many<I, K, V>(&mut self, pairs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Serialize,
+    {
+        let pairs = pairs
+            .into_iter()
+            .map(|(k, v)| Ok((k.into(), to_value(v)?)))
+            .collect::<Result<Vec<(String, Value)>>>()?;
+        self.with_data(move |data| data.set_many(pairs)).await
+    }
+
+    /// Remove key `k` and persist the change to disk, see [`Data::remove()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::remove()`].
+    ///
+    /// [`Data::remove()`]: struct.Data.html#method.remove
+    pub async fn remove<K>(&mut self, k: K) -> Result<Option<Value>>
+    where
+        K: AsRef<str>,
+    {
+        let k = k.as_ref().to_string();
+        self.with_data(move |data| data.remove(k)).await
+    }
+
+    /// Apply a JSON merge patch to the data, and persist it to disk, see
+    /// [`Data::apply_patch()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::apply_patch()`].
+    ///
+    /// [`Data::apply_patch()`]: struct.Data.html#method.apply_patch
+    pub async fn apply_patch(&mut self, patch: Value) -> Result<()> {
+        self.with_data(move |data| data.apply_patch(patch)).await
+    }
+
+    /// Add `delta` to the integer value of key `k`, and persist it to disk, see
+    /// [`Data::increment_by()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::increment_by()`].
+    ///
+    /// [`Data::increment_by()`]: struct.Data.html#method.increment_by
+    pub async fn increment_by<K>(&mut self, k: K, delta: i64) -> Result<i64>
+    where
+        K: Into<String>,
+    {
+        let k = k.into();
+        self.with_data(move |data| data.increment_by(k, delta))
+            .await
+    }
+
+    /// Write pending changes to disk, see [`Data::save()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::save()`].
+    ///
+    /// [`Data::save()`]: struct.Data.html#method.save
+    pub async fn save(&mut self) -> Result<()> {
+        self.with_data(Data::save).await
+    }
+
+    /// Read the data file again, see [`Data::reload()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::reload()`].
+    ///
+    /// [`Data::reload()`]: struct.Data.html#method.reload
+    pub async fn reload(&mut self) -> Result<()> {
+        self.with_data(Data::reload).await
+    }
+
+    /// Only write changes to disk when [`save()`] is called, see [`Data::defer_writes()`]
+    ///
+    /// Pending changes are saved when the `AsyncData` is dropped, but this blocks the
+    /// thread it is dropped on: better call [`save()`].
+    ///
+    /// [`save()`]: struct.AsyncData.html#method.save
+    /// [`Data::defer_writes()`]: struct.Data.html#method.defer_writes
+    pub fn defer_writes(&mut self, defer: bool) {
+        self.lock().defer_writes(defer);
+    }
+
+    /// Write the JSON data file indented, see [`Data::pretty_print()`]
+    ///
+    /// [`Data::pretty_print()`]: struct.Data.html#method.pretty_print
+    pub fn pretty_print(&mut self, pretty: bool) {
+        self.lock().pretty_print(pretty);
+    }
+
+    /// Clear all key-value pairs. Does not affect data on disk.
+    pub fn clear(&mut self) {
+        self.lock().clear();
+    }
+
+    /// Get (possible) value of key `k`, see [`Data::get()`]
+    ///
+    /// [`Data::get()`]: struct.Data.html#method.get
+    pub fn get<K, V>(&self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        self.lock().get(k)
+    }
+
+    /// Get (possible) value of key `k`, reporting values of another type, see
+    /// [`Data::try_get()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::try_get()`].
+    ///
+    /// [`Data::try_get()`]: struct.Data.html#method.try_get
+    pub fn try_get<K, V>(&self, k: K) -> std::result::Result<Option<V>, TypeMismatch>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        self.lock().try_get(k)
+    }
+
+    /// Get the raw JSON value of key `k`, see [`Data::get_value()`]
+    ///
+    /// [`Data::get_value()`]: struct.Data.html#method.get_value
+    pub fn get_value<K>(&self, k: K) -> Option<Value>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_value(k).cloned()
+    }
+
+    /// Get the value of key `k` as a string, see [`Data::get_str()`]
+    ///
+    /// [`Data::get_str()`]: struct.Data.html#method.get_str
+    pub fn get_str<K>(&self, k: K) -> Option<String>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_str(k)
+    }
+
+    /// Get the value of key `k` as a [`Secret`] string, see [`Data::get_secret()`]
+    ///
+    /// [`Secret`]: struct.Secret.html
+    /// [`Data::get_secret()`]: struct.Data.html#method.get_secret
+    pub fn get_secret<K>(&self, k: K) -> Option<Secret<String>>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_secret(k)
+    }
+
+    /// Get the value of key `k` as a boolean, see [`Data::get_bool()`]
+    ///
+    /// [`Data::get_bool()`]: struct.Data.html#method.get_bool
+    pub fn get_bool<K>(&self, k: K) -> Option<bool>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_bool(k)
+    }
+
+    /// Get the value of key `k` as an integer, see [`Data::get_i64()`]
+    ///
+    /// [`Data::get_i64()`]: struct.Data.html#method.get_i64
+    pub fn get_i64<K>(&self, k: K) -> Option<i64>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_i64(k)
+    }
+
+    /// Get the value of key `k` as a float, see [`Data::get_f64()`]
+    ///
+    /// [`Data::get_f64()`]: struct.Data.html#method.get_f64
+    pub fn get_f64<K>(&self, k: K) -> Option<f64>
+    where
+        K: AsRef<str>,
+    {
+        self.lock().get_f64(k)
+    }
+
+    /// Returns `true` if key `k` is set, see [`Data::contains_key()`]
+    ///
+    /// [`Data::contains_key()`]: struct.Data.html#method.contains_key
+    pub fn contains_key<K>(&self, k: K) -> bool
+    where
+        K: AsRef<str>,
+    {
+        self.lock().contains_key(k)
+    }
+
+    /// Number of keys in workflow's data
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no key is set in workflow's data
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// The keys of workflow's data, in arbitrary order
+    pub fn keys(&self) -> Vec<String> {
+        self.lock().keys().map(str::to_string).collect()
+    }
+
+    /// Full path of the data file
+    pub fn path(&self) -> PathBuf {
+        self.lock().path().to_path_buf()
+    }
+
+    /// Async version of [`Data::save_to_file()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::save_to_file()`].
+    ///
+    /// [`Data::save_to_file()`]: struct.Data.html#method.save_to_file
+    pub async fn save_to_file<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        let p = Data::cache_file_path(p.as_ref())?;
+        write_atomically(&p, serde_json::to_vec(data)?).await
+    }
+
+    /// Async version of [`Data::load_from_file()`]
+    ///
+    /// [`Data::load_from_file()`]: struct.Data.html#method.load_from_file
+    pub async fn load_from_file<P, V>(p: P) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Data::cache_file_path(p.as_ref()).ok()?;
+        read(&p).await
+    }
+
+    /// Async version of [`Data::save_to_data_file()`]
+    ///
+    /// # Errors
+    /// Same as [`Data::save_to_data_file()`].
+    ///
+    /// [`Data::save_to_data_file()`]: struct.Data.html#method.save_to_data_file
+    pub async fn save_to_data_file<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        let p = Data::data_file_path(p.as_ref())?;
+        write_atomically(&p, serde_json::to_vec(data)?).await
+    }
+
+    /// Async version of [`Data::load_from_data_file()`]
+    ///
+    /// [`Data::load_from_data_file()`]: struct.Data.html#method.load_from_data_file
+    pub async fn load_from_data_file<P, V>(p: P) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Data::data_file_path(p.as_ref()).ok()?;
+        read(&p).await
+    }
+
+    // Run `f` on the data on tokio's blocking thread pool.
+    async fn with_data<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Data) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let data = Arc::clone(&self.data);
+        tokio::task::spawn_blocking(move || f(&mut data.lock().unwrap_or_else(|e| e.into_inner())))
+            .await?
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Data> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// Async version of `Data::write_atomically()`.
+async fn write_atomically(p: &Path, bytes: Vec<u8>) -> Result<()> {
+    let dir = match p.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    tokio::fs::create_dir_all(dir).await?;
+    let temp = dir.join(format!(
+        ".alfred_rs_temp{}-{}.json",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    debug!("writing to: {:?}", p);
+    if let Err(e) = tokio::fs::write(&temp, bytes).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e.into());
+    }
+    forget_read(p);
+    if let Err(e) = tokio::fs::rename(&temp, p).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+async fn read<V>(p: &Path) -> Option<V>
+where
+    V: for<'d> Deserialize<'d>,
+{
+    debug!("loading from: {:?}", p);
+    let bytes = tokio::fs::read(p).await.ok()?;
+    Data::parse_file_bytes(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::setup_workflow_env_vars;
+    use super::*;
+
+    #[test]
+    fn it_loads_and_saves_asynchronously() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = std::fs::remove_file(wfd.join("async_test.json"));
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let mut wf_data = AsyncData::load("async_test.json").await.unwrap();
+            wf_data.set("page_size", &20).await.unwrap();
+            wf_data
+                .set_many(vec![("theme", "dark"), ("lang", "en")])
+                .await
+                .unwrap();
+            assert_eq!(
+                Some(Value::from("en")),
+                wf_data.remove("lang").await.unwrap()
+            );
+            assert_eq!(1, wf_data.increment_by("launches", 1).await.unwrap());
+            assert_eq!(Some(20), wf_data.get::<_, u32>("page_size"));
+            assert_eq!(3, wf_data.len());
+
+            let saved = AsyncData::load("async_test.json").await.unwrap();
+            assert_eq!(Some("dark".to_string()), saved.get_str("theme"));
+            assert!(!saved.contains_key("lang"));
+            assert_eq!(wfd.join("async_test.json"), saved.path());
+
+            AsyncData::save_to_file("async_cache/items.json", &vec![1, 2, 3])
+                .await
+                .unwrap();
+            let items: Vec<u32> = AsyncData::load_from_file("async_cache/items.json")
+                .await
+                .unwrap();
+            assert_eq!(vec![1, 2, 3], items);
+            assert_eq!(
+                Some(vec![1, 2, 3]),
+                Data::load_from_file::<_, Vec<u32>>("async_cache/items.json")
+            );
+            assert!(
+                AsyncData::load_from_file::<_, Vec<u32>>("async_cache/missing.json")
+                    .await
+                    .is_none()
+            );
+        });
+    }
+}
@@ -0,0 +1,218 @@
+use super::{bail, Result};
+
+/// Ask the user to authenticate with Touch ID (or their password) before a sensitive action
+///
+/// Shows macOS' authentication dialog, which reads "Alfred is trying to `reason`.", and blocks
+/// until the user authenticates or cancels. Touch ID is used when available, with the user's
+/// password as fallback, so this also works on Macs without Touch ID. Use it before releasing a
+/// secret (see [`Keychain::get_authenticated()`]) or before running destructive commands.
+///
+/// Returns `false` if the user cancels or fails to authenticate.
+///
+/// # Errors
+///
+/// If `reason` is empty, if the user can't be authenticated (such as when the Mac has no
+/// password), or on other systems than macOS.
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::authenticate;
+///
+/// if authenticate("delete the production database").unwrap() {
+///     // drop tables
+/// }
+/// ```
+///
+/// [`Keychain::get_authenticated()`]: struct.Keychain.html#method.get_authenticated
+pub fn authenticate(reason: &str) -> Result<bool> {
+    // LocalAuthentication throws an exception for empty reasons.
+    if reason.is_empty() {
+        bail!("reason for authentication can't be empty");
+    }
+    debug!("asking user to authenticate to {}", reason);
+    imp::authenticate(reason)
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use super::super::{anyhow, Result};
+    use std::ffi::{CStr, CString};
+    use std::mem;
+    use std::os::raw::{c_char, c_int, c_ulong, c_void};
+    use std::sync::mpsc::{channel, Sender};
+
+    type Id = *mut c_void;
+    type Sel = *const c_void;
+
+    // `LAPolicyDeviceOwnerAuthentication`: Touch ID, Apple Watch or the user's password.
+    const POLICY_DEVICE_OWNER_AUTHENTICATION: isize = 2;
+    // `LAError` codes of authentications that the user cancelled or failed.
+    const ERROR_AUTHENTICATION_FAILED: isize = -1;
+    const ERROR_USER_CANCEL: isize = -2;
+    const ERROR_USER_FALLBACK: isize = -3;
+    const ERROR_SYSTEM_CANCEL: isize = -4;
+
+    #[link(name = "objc")]
+    extern "C" {
+        fn objc_getClass(name: *const c_char) -> Id;
+        fn sel_registerName(name: *const c_char) -> Sel;
+        fn objc_msgSend();
+    }
+
+    #[link(name = "Foundation", kind = "framework")]
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {
+        static _NSConcreteStackBlock: c_void;
+    }
+
+    // Result of the authentication sent by the reply block.
+    type Reply = std::result::Result<bool, String>;
+
+    // Layout of an Objective-C block without captured objects, see
+    // https://clang.llvm.org/docs/Block-ABI-Apple.html
+    #[repr(C)]
+    struct ReplyBlock {
+        isa: *const c_void,
+        flags: c_int,
+        reserved: c_int,
+        invoke: unsafe extern "C" fn(*mut ReplyBlock, i8, Id),
+        descriptor: *const BlockDescriptor,
+        sender: *mut Sender<Reply>,
+    }
+
+    #[repr(C)]
+    struct BlockDescriptor {
+        reserved: c_ulong,
+        size: c_ulong,
+    }
+
+    static REPLY_BLOCK_DESCRIPTOR: BlockDescriptor = BlockDescriptor {
+        reserved: 0,
+        size: mem::size_of::<ReplyBlock>() as c_ulong,
+    };
+
+    pub(super) fn authenticate(reason: &str) -> Result<bool> {
+        let reason = CString::new(reason)?;
+        unsafe {
+            let context = send(class(b"LAContext\0"), b"alloc\0");
+            let context = send(context, b"init\0");
+            let reason = send_ptr(
+                send(class(b"NSString\0"), b"alloc\0"),
+                b"initWithUTF8String:\0",
+                reason.as_ptr() as *const c_void,
+            );
+            let authenticated = evaluate(context, reason);
+            send(reason, b"release\0");
+            send(context, b"release\0");
+            authenticated
+        }
+    }
+
+    unsafe fn evaluate(context: Id, reason: Id) -> Result<bool> {
+        let can_evaluate: unsafe extern "C" fn(Id, Sel, isize, *mut Id) -> i8 =
+            mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        let mut error: Id = std::ptr::null_mut();
+        if can_evaluate(
+            context,
+            sel(b"canEvaluatePolicy:error:\0"),
+            POLICY_DEVICE_OWNER_AUTHENTICATION,
+            &mut error,
+        ) == 0
+        {
+            return Err(anyhow!("can't authenticate user: {}", description(error)));
+        }
+
+        // The reply block is called once, on another thread, and owns the sender.
+        let (sender, receiver) = channel();
+        let mut block = ReplyBlock {
+            isa: &_NSConcreteStackBlock as *const c_void,
+            flags: 0,
+            reserved: 0,
+            invoke: reply,
+            descriptor: &REPLY_BLOCK_DESCRIPTOR,
+            sender: Box::into_raw(Box::new(sender)),
+        };
+        let evaluate_policy: unsafe extern "C" fn(Id, Sel, isize, Id, *mut ReplyBlock) =
+            mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        evaluate_policy(
+            context,
+            sel(b"evaluatePolicy:localizedReason:reply:\0"),
+            POLICY_DEVICE_OWNER_AUTHENTICATION,
+            reason,
+            &mut block,
+        );
+        receiver
+            .recv()?
+            .map_err(|e| anyhow!("can't authenticate user: {}", e))
+    }
+
+    unsafe extern "C" fn reply(block: *mut ReplyBlock, success: i8, error: Id) {
+        let sender = Box::from_raw((*block).sender);
+        let authenticated = if success != 0 {
+            Ok(true)
+        } else {
+            let code: unsafe extern "C" fn(Id, Sel) -> isize =
+                mem::transmute(objc_msgSend as unsafe extern "C" fn());
+            match code(error, sel(b"code\0")) {
+                ERROR_AUTHENTICATION_FAILED
+                | ERROR_USER_CANCEL
+                | ERROR_USER_FALLBACK
+                | ERROR_SYSTEM_CANCEL => Ok(false),
+                _ => Err(description(error)),
+            }
+        };
+        let _ = sender.send(authenticated);
+    }
+
+    // Localized description of `NSError` `error`.
+    unsafe fn description(error: Id) -> String {
+        if error.is_null() {
+            return "unknown error".to_string();
+        }
+        let description = send(error, b"localizedDescription\0");
+        let utf8: unsafe extern "C" fn(Id, Sel) -> *const c_char =
+            mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        CStr::from_ptr(utf8(description, sel(b"UTF8String\0")))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    unsafe fn class(name: &[u8]) -> Id {
+        objc_getClass(name.as_ptr() as *const c_char)
+    }
+
+    unsafe fn sel(name: &[u8]) -> Sel {
+        sel_registerName(name.as_ptr() as *const c_char)
+    }
+
+    unsafe fn send(receiver: Id, name: &[u8]) -> Id {
+        let send: unsafe extern "C" fn(Id, Sel) -> Id =
+            mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        send(receiver, sel(name))
+    }
+
+    unsafe fn send_ptr(receiver: Id, name: &[u8], arg: *const c_void) -> Id {
+        let send: unsafe extern "C" fn(Id, Sel, *const c_void) -> Id =
+            mem::transmute(objc_msgSend as unsafe extern "C" fn());
+        send(receiver, sel(name), arg)
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod imp {
+    use super::super::{bail, Result};
+
+    pub(super) fn authenticate(_reason: &str) -> Result<bool> {
+        bail!("authentication with Touch ID is only available on macOS")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_needs_a_reason_to_authenticate() {
+        assert!(authenticate("").is_err());
+    }
+}
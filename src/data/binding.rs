@@ -0,0 +1,57 @@
+use super::{Data, Result};
+use serde::Serialize;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+/// A value of type `T` bound to a whole data file, see [`Data::bind()`]
+///
+/// `Binding` dereferences to the bound value, changes are written to disk by [`save()`].
+///
+/// [`Data::bind()`]: struct.Data.html#method.bind
+/// [`save()`]: struct.Binding.html#method.save
+#[derive(Debug)]
+pub struct Binding<T> {
+    value: T,
+    file_name: PathBuf,
+}
+
+impl<T> Binding<T> {
+    pub(super) fn new(value: T, file_name: PathBuf) -> Self {
+        Binding { value, file_name }
+    }
+
+    /// Full path of the data file the value is bound to
+    pub fn path(&self) -> &Path {
+        &self.file_name
+    }
+
+    /// Unbind the value from its file
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Serialize> Binding<T> {
+    /// Write the value to its data file
+    ///
+    /// # Errors
+    ///
+    /// If the value cannot be serialized or there are file IO issues an error is returned.
+    pub fn save(&self) -> Result<()> {
+        Data::write_data_to_disk(&self.file_name, &self.value)
+    }
+}
+
+impl<T> Deref for Binding<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Binding<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,67 @@
+use super::Result;
+use std::io::{BufRead, Read};
+
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of files written by the [`data`] module
+///
+/// Compressed files are recognized when they are read, whatever function reads them, so
+/// compression can be turned on (or off) without migrating existing files. Compression is
+/// selected per file with [`Data::save_to_file_compressed()`], or per `Data` with
+/// [`Data::set_compression()`].
+///
+/// [`data`]: index.html
+/// [`Data::save_to_file_compressed()`]: struct.Data.html#method.save_to_file_compressed
+/// [`Data::set_compression()`]: struct.Data.html#method.set_compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, enabled by the `gzip` feature
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, enabled by the `zstd` feature. Usually both smaller and faster than gzip.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    pub(super) fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                use std::io::Write;
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(zstd::encode_all(bytes, 0)?),
+        }
+    }
+}
+
+// Reader of the decompressed content of `reader` if it starts with the magic number of a
+// supported compression, or of `reader` as it is otherwise.
+pub(super) fn decoder<'a, R>(mut reader: R) -> Result<Box<dyn Read + 'a>>
+where
+    R: BufRead + 'a,
+{
+    let head = reader.fill_buf()?;
+    #[cfg(feature = "gzip")]
+    {
+        if head.starts_with(GZIP_MAGIC) {
+            return Ok(Box::new(flate2::bufread::GzDecoder::new(reader)));
+        }
+    }
+    #[cfg(feature = "zstd")]
+    {
+        if head.starts_with(ZSTD_MAGIC) {
+            return Ok(Box::new(zstd::stream::read::Decoder::with_buffer(reader)?));
+        }
+    }
+    let _ = head;
+    Ok(Box::new(reader))
+}
@@ -0,0 +1,85 @@
+use super::{Binding, Data, Result};
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Workflow settings stored as a whole in their own data file
+///
+/// Usually implemented with `#[derive(WorkflowConfig)]` (available with the `derive`
+/// feature), which also implements `Default` for the struct:
+/// - `#[workflow_config(file = "settings.json")]` on the struct sets the data file name
+///   (the struct's name in `snake_case` with a `.json` extension by default).
+/// - `#[workflow_config(validate = "path::to::fn")]` on the struct checks loaded settings with
+///   a `fn(&Self) -> anyhow::Result<()>`.
+/// - `#[workflow_config(default = "expr")]` on a field sets its default value (the field
+///   type's `Default` otherwise).
+/// - `#[workflow_config(env = "VAR")]` on a field lets users override it with the `VAR`
+///   workflow variable. Its value is parsed with the field type's `FromStr`, unparsable values
+///   are ignored.
+///
+/// Settings are loaded with [`load()`] and saved with [`Binding::save()`].
+///
+/// # Example
+#[cfg_attr(feature = "derive", doc = "```rust,no_run")]
+#[cfg_attr(not(feature = "derive"), doc = "```rust,ignore")]
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// use alfred_rs::data::WorkflowConfig;
+///
+/// #[derive(Serialize, Deserialize, WorkflowConfig)]
+/// #[serde(default)]
+/// #[workflow_config(file = "settings.json", validate = "check_settings")]
+/// struct Settings {
+///     #[workflow_config(default = "20", env = "page_size")]
+///     page_size: u32,
+///     api_token: Option<String>,
+/// }
+///
+/// fn check_settings(settings: &Settings) -> anyhow::Result<()> {
+///     anyhow::ensure!(settings.page_size > 0, "page size can't be 0");
+///     Ok(())
+/// }
+///
+/// # fn main() {
+/// let mut settings = Settings::load().unwrap();
+/// settings.api_token = Some("secret".to_string());
+/// settings.save().unwrap();
+/// # }
+/// ```
+///
+/// [`load()`]: trait.WorkflowConfig.html#method.load
+/// [`Binding::save()`]: struct.Binding.html#method.save
+pub trait WorkflowConfig: Serialize + for<'d> Deserialize<'d> + Default {
+    /// Name of the data file the settings are saved to
+    const FILE_NAME: &'static str;
+
+    /// Override settings with values that users set as workflow variables
+    fn apply_env_overrides(&mut self) {}
+
+    /// Check that the settings are valid
+    ///
+    /// # Errors
+    ///
+    /// Describes why the settings are invalid.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Load the settings from their data file (or their defaults if there's none), apply the
+    /// overrides from workflow variables and validate them
+    ///
+    /// Overridden values are saved along with the rest of the settings by
+    /// [`Binding::save()`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Data::bind()`], and if the settings are not valid.
+    ///
+    /// [`Binding::save()`]: struct.Binding.html#method.save
+    /// [`Data::bind()`]: struct.Data.html#method.bind
+    fn load() -> Result<Binding<Self>> {
+        let mut settings: Binding<Self> = Data::bind(Self::FILE_NAME)?;
+        settings.apply_env_overrides();
+        settings.validate()?;
+        Ok(settings)
+    }
+}
@@ -0,0 +1,229 @@
+use super::{Data, Result};
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs::create_dir_all;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// Extension of the files that values are stored in.
+const VALUE_EXT: &str = "json";
+
+/// A key/value store where each key is saved to its own file in a directory
+///
+/// [`Data`] rewrites its whole file whenever a key is set. When some values are large (such as
+/// a cached index or a list of thousands of items), this means rewriting megabytes to change a
+/// small setting. `DirStore` only writes the file of the key that is set, and only reads the
+/// files of the keys it is asked for.
+///
+/// Values are stored as JSON in `<key>.json`, with characters of the key that can't be in file
+/// names (such as `/`) escaped. Files are replaced atomically, readers never see a partially
+/// written value. The file of a key is available through [`path()`], e.g. to memory-map a large
+/// value instead of reading it.
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::DirStore;
+///
+/// let store = DirStore::open("index").unwrap();
+///
+/// store.set("page_size", &20).unwrap();
+/// store.set("all_repos", &vec!["alfred-workflow", "alfred-rs"]).unwrap();
+///
+/// let repos: Vec<String> = store.get("all_repos").unwrap_or_default();
+/// ```
+///
+/// [`Data`]: struct.Data.html
+/// [`path()`]: struct.DirStore.html#method.path
+#[derive(Debug, Clone)]
+pub struct DirStore {
+    dir: PathBuf,
+}
+
+impl DirStore {
+    /// Opens (or creates) the directory `p` in workflow's data dir
+    ///
+    /// Same as [`Data::load()`], `p` is relative to the data dir.
+    ///
+    /// # Errors
+    ///
+    /// If the data dir is not set, or the directory can't be created.
+    ///
+    /// [`Data::load()`]: struct.Data.html#method.load
+    pub fn open<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let dir = Data::data_file_path(p.as_ref())?;
+        create_dir_all(&dir)?;
+        debug!("opening store: {:?}", dir);
+        Ok(DirStore { dir })
+    }
+
+    /// Directory the values are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the file that stores the value of key `k`, whether it is set or not
+    pub fn path<K: AsRef<str>>(&self, k: K) -> PathBuf {
+        let mut file_name = escape_key(k.as_ref());
+        file_name.push('.');
+        file_name.push_str(VALUE_EXT);
+        self.dir.join(file_name)
+    }
+
+    /// Set the value of key `k` to `v`, writing only the file of `k`
+    ///
+    /// # Errors
+    ///
+    /// If `v` cannot be serialized or the file can't be written.
+    pub fn set<K, V>(&self, k: K, v: &V) -> Result<()>
+    where
+        K: AsRef<str>,
+        V: Serialize,
+    {
+        Data::write_data_to_disk(self.path(k), v)
+    }
+
+    /// Get the value of key `k`
+    ///
+    /// Same as [`Data::get()`], `None` is returned if the key is not set or its value cannot be
+    /// deserialized to type `V`.
+    ///
+    /// [`Data::get()`]: struct.Data.html#method.get
+    pub fn get<K, V>(&self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = self.path(k);
+        Data::read_data_from_disk(&p)
+            .map_err(|e| debug!("couldn't read {:?}: {}", p, e))
+            .ok()
+    }
+
+    /// Returns `true` if key `k` is set
+    pub fn contains_key<K: AsRef<str>>(&self, k: K) -> bool {
+        self.path(k).is_file()
+    }
+
+    /// Remove key `k`, returning whether it was set
+    ///
+    /// # Errors
+    ///
+    /// If the file of `k` can't be deleted.
+    pub fn remove<K: AsRef<str>>(&self, k: K) -> Result<bool> {
+        match std::fs::remove_file(self.path(k)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The keys that are set, in arbitrary order
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read.
+    pub fn keys(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let p = entry?.path();
+            if p.extension().map_or(true, |ext| ext != VALUE_EXT) {
+                continue;
+            }
+            if let Some(k) = p
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(unescape_key)
+            {
+                keys.push(k);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Number of keys that are set
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read.
+    pub fn len(&self) -> Result<usize> {
+        self.keys().map(|keys| keys.len())
+    }
+
+    /// Returns `true` if no key is set
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+}
+
+// File name (without extension) for key `k`: bytes other than ASCII letters, digits, `-` and
+// `_` are escaped as `%XX`, so that any key maps to a distinct, valid file name. Dots are
+// escaped too, keys can't be `..` or end up as hidden files.
+fn escape_key(k: &str) -> String {
+    let mut escaped = String::with_capacity(k.len());
+    for b in k.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            escaped.push(b as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", b));
+        }
+    }
+    escaped
+}
+
+// Key of the file named `escaped`, `None` for files that `escape_key()` doesn't name.
+fn unescape_key(escaped: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut chars = escaped.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            bytes.push(b);
+        } else {
+            return None;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::setup_workflow_env_vars;
+    use super::*;
+
+    #[test]
+    fn it_stores_keys_in_files() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = std::fs::remove_dir_all(wfd.join("dir_store_test"));
+
+        let store = DirStore::open("dir_store_test").unwrap();
+        assert!(store.is_empty().unwrap());
+
+        store.set("page_size", &20).unwrap();
+        store.set("repos/../all", &vec!["a", "b"]).unwrap();
+        store.set("ünïcode key", &true).unwrap();
+        assert_eq!(Some(20), store.get("page_size"));
+        assert_eq!(None, store.get::<_, String>("page_size"));
+        assert_eq!(
+            Some(vec!["a".to_string(), "b".to_string()]),
+            store.get("repos/../all")
+        );
+        assert_eq!(store.dir(), store.path("repos/../all").parent().unwrap());
+        assert!(wfd.join("dir_store_test/page_size.json").is_file());
+
+        let mut keys = store.keys().unwrap();
+        keys.sort();
+        assert_eq!(vec!["page_size", "repos/../all", "ünïcode key"], keys);
+
+        assert!(store.remove("page_size").unwrap());
+        assert!(!store.remove("page_size").unwrap());
+        assert!(!store.contains_key("page_size"));
+        assert!(store.contains_key("ünïcode key"));
+        assert_eq!(2, store.len().unwrap());
+    }
+}
@@ -0,0 +1,66 @@
+use super::serde_json;
+use std::fmt;
+
+/// Error of [`Data::try_get()`] when the value of a key can't be deserialized to the type
+/// asked for
+///
+/// The stored value itself can be inspected with [`Data::get_value()`].
+///
+/// [`Data::try_get()`]: struct.Data.html#method.try_get
+/// [`Data::get_value()`]: struct.Data.html#method.get_value
+#[derive(Debug)]
+pub struct TypeMismatch {
+    key: String,
+    source: serde_json::Error,
+}
+
+impl TypeMismatch {
+    pub(super) fn new(key: &str, source: serde_json::Error) -> Self {
+        TypeMismatch {
+            key: key.to_string(),
+            source,
+        }
+    }
+
+    /// Key whose value has an unexpected type
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value of key `{}` has an unexpected type: {}",
+            self.key, self.source
+        )
+    }
+}
+
+impl std::error::Error for TypeMismatch {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Error of loading or saving an [encrypted] data file that can't be decrypted with the key it
+/// was loaded with
+///
+/// Unlike corrupt files, these files are left in place: the key may have been replaced or
+/// deleted from the Keychain, and the file can still be decrypted with the right one.
+///
+/// [encrypted]: format/struct.Encrypted.html
+#[cfg(feature = "encryption")]
+#[derive(Debug)]
+pub struct DecryptionFailed;
+
+#[cfg(feature = "encryption")]
+impl fmt::Display for DecryptionFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "couldn't decrypt data, wrong key or tampered data")
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl std::error::Error for DecryptionFailed {}
@@ -0,0 +1,384 @@
+//! Formats of the files that [`Data`] reads and writes
+//!
+//! Data files are JSON by default. A file in another format is used with
+//! [`Data::load_with_format()`] for data files, or with [`Data::save_to_file_with_format()`]
+//! and [`Data::load_from_file_with_format()`] for cache files.
+//!
+//! Besides the formats of this module, which are enabled with the crate feature of the same
+//! name (such as `toml`), any type that implements [`Format`] can be used.
+//!
+//! [`Data`]: ../struct.Data.html
+//! [`Data::load_with_format()`]: ../struct.Data.html#method.load_with_format
+//! [`Data::save_to_file_with_format()`]: ../struct.Data.html#method.save_to_file_with_format
+//! [`Data::load_from_file_with_format()`]: ../struct.Data.html#method.load_from_file_with_format
+//! [`Format`]: trait.Format.html
+#[cfg(feature = "encryption")]
+use super::{anyhow, bail, Keychain};
+use super::{serde_json, Result};
+use serde_json::Value;
+use std::fmt::Debug;
+
+/// Serialization of data to the bytes of a file, and back
+///
+/// Data is handed to formats as a [`Value`], so a format only has to map JSON-like values
+/// to bytes and back to be usable for data files as well as cache files.
+///
+/// # Example
+/// A format for files of `key=value` lines:
+/// ```rust
+/// use alfred_rs::data::Format;
+/// use serde_json::{Map, Value};
+///
+/// #[derive(Debug)]
+/// struct KeyValueLines;
+///
+/// impl Format for KeyValueLines {
+///     fn serialize(&self, value: &Value) -> anyhow::Result<Vec<u8>> {
+///         let mut lines = String::new();
+///         for (k, v) in value.as_object().into_iter().flatten() {
+///             lines.push_str(&format!("{}={}\n", k, v));
+///         }
+///         Ok(lines.into_bytes())
+///     }
+///
+///     fn deserialize(&self, bytes: &[u8]) -> anyhow::Result<Value> {
+///         let mut map = Map::new();
+///         for line in std::str::from_utf8(bytes)?.lines() {
+///             if let Some((k, v)) = line.split_once('=') {
+///                 map.insert(k.to_string(), serde_json::from_str(v)?);
+///             }
+///         }
+///         Ok(Value::Object(map))
+///     }
+/// }
+///
+/// let bytes = KeyValueLines.serialize(&serde_json::json!({"page_size": 20})).unwrap();
+/// assert_eq!(b"page_size=20\n", bytes.as_slice());
+/// ```
+/// Then `Data::load_with_format("settings.conf", KeyValueLines)` reads and writes such files.
+///
+/// [`Value`]: https://docs.rs/serde_json/1/serde_json/enum.Value.html
+pub trait Format: Debug + Send + Sync {
+    /// Serialize `value` to the content of a file
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>>;
+
+    /// Deserialize the content of a file
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value>;
+
+    /// Same as [`serialize()`], but as an update of the `previous` content of the file
+    ///
+    /// Formats that users edit by hand can keep what they can of the previous content (such as
+    /// comments). By default the previous content is ignored.
+    ///
+    /// [`serialize()`]: trait.Format.html#tymethod.serialize
+    fn serialize_over(&self, value: &Value, previous: &[u8]) -> Result<Vec<u8>> {
+        let _ = previous;
+        self.serialize(value)
+    }
+}
+
+/// JSON, the default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl Format for Json {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// TOML, friendlier to users who edit settings by hand. When a file is saved, the comments
+/// and layout of the values it already had are kept.
+///
+/// TOML has no `null`, values that serialize to `null` (such as `None`) can't be saved.
+#[cfg(feature = "toml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Toml;
+
+#[cfg(feature = "toml")]
+impl Format for Toml {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(toml_edit::ser::to_document(value)?.to_string().into_bytes())
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(toml_edit::de::from_slice(bytes)?)
+    }
+
+    fn serialize_over(&self, value: &Value, previous: &[u8]) -> Result<Vec<u8>> {
+        let new = toml_edit::ser::to_document(value)?;
+        let mut doc = match std::str::from_utf8(previous)
+            .ok()
+            .and_then(|previous| previous.parse::<toml_edit::DocumentMut>().ok())
+        {
+            Some(doc) => doc,
+            None => return Ok(new.to_string().into_bytes()),
+        };
+        merge_toml_table(doc.as_table_mut(), new.into_table());
+        Ok(doc.to_string().into_bytes())
+    }
+}
+
+/// YAML, for workflows whose users already have YAML config files
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaml;
+
+#[cfg(feature = "yaml")]
+impl Format for Yaml {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(serde_yaml::to_string(value)?.into_bytes())
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+}
+
+/// MessagePack, a binary format that is smaller and faster to parse than JSON, for caches of
+/// many items
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "msgpack")]
+impl Format for MessagePack {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(value)?)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+/// Apple's XML property list, to share files with Alfred and other macOS tools
+///
+/// Property lists have no `null`, values that serialize to `null` (such as `None`) can't be
+/// saved. Files in either plist format are read whichever of the two is used.
+#[cfg(feature = "plist")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plist;
+
+#[cfg(feature = "plist")]
+impl Format for Plist {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        plist::to_writer_xml(&mut bytes, value)?;
+        Ok(bytes)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(plist::from_bytes(bytes)?)
+    }
+}
+
+/// Apple's binary property list, same as [`Plist`] but more compact
+///
+/// [`Plist`]: struct.Plist.html
+#[cfg(feature = "plist")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryPlist;
+
+#[cfg(feature = "plist")]
+impl Format for BinaryPlist {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        plist::to_writer_binary(&mut bytes, value)?;
+        Ok(bytes)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        Ok(plist::from_bytes(bytes)?)
+    }
+}
+
+/// JSON encrypted with ChaCha20-Poly1305, for data files that hold tokens, cookies or other
+/// personal data
+///
+/// Files are encrypted with a 256-bit key, usually generated once and kept in the user's
+/// Keychain by [`from_keychain()`], so that the files are useless wherever they are copied
+/// (backups, Alfred's synced preferences, ...). Each write uses a new random nonce. A file that
+/// can't be decrypted (e.g. after the key was deleted) fails to load with [`DecryptionFailed`]
+/// and is left as is, while a file that isn't encrypted at all is treated as corrupt.
+///
+/// The cipher is the IETF ChaCha20-Poly1305 with its 96-bit nonce, not XChaCha20-Poly1305 and
+/// its 192-bit nonce. Random nonces of 96 bits are only safe for a limited number of writes
+/// with the same key: keep it under 2<sup>32</sup> writes (about one write per second for 136
+/// years), past which a repeated nonce becomes likely enough to reveal data. Files start with a
+/// header naming the cipher and the format version, so the format can change without breaking
+/// existing files.
+///
+/// # Example
+/// ```rust,ignore
+/// use alfred_rs::data::{format, Data, Keychain};
+///
+/// // Needs the `encryption` feature
+/// let key = format::Encrypted::from_keychain(&Keychain::for_workflow().unwrap()).unwrap();
+/// let mut secrets = Data::load_with_format("secrets.bin", key).unwrap();
+/// secrets.set("session_cookie", &"a4c9...").unwrap();
+/// ```
+///
+/// [`from_keychain()`]: struct.Encrypted.html#method.from_keychain
+/// [`DecryptionFailed`]: ../struct.DecryptionFailed.html
+#[cfg(feature = "encryption")]
+pub struct Encrypted {
+    key: ring::aead::LessSafeKey,
+}
+
+#[cfg(feature = "encryption")]
+impl Encrypted {
+    // Start of encrypted files, naming the cipher and format version. Also authenticated with
+    // their content.
+    const MAGIC: &'static [u8] = b"alfred-rs chacha20poly1305 1\n";
+
+    // Keychain account of the key created by `from_keychain()`.
+    const KEYCHAIN_ACCOUNT: &'static str = "alfred-rs data key";
+
+    /// Encrypt with `key`
+    pub fn new(key: &[u8; 32]) -> Self {
+        use ring::aead::{LessSafeKey, UnboundKey, CHACHA20_POLY1305};
+        let key = UnboundKey::new(&CHACHA20_POLY1305, key)
+            .expect("32 bytes is the key length of ChaCha20-Poly1305");
+        Encrypted {
+            key: LessSafeKey::new(key),
+        }
+    }
+
+    /// A new random key
+    ///
+    /// # Errors
+    ///
+    /// If the system's random number generator fails.
+    pub fn generate_key() -> Result<[u8; 32]> {
+        use ring::rand::{SecureRandom, SystemRandom};
+        let mut key = [0; 32];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| anyhow!("couldn't generate key"))?;
+        Ok(key)
+    }
+
+    /// Encrypt with the key kept in `keychain`, which is generated the first time
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be read or written.
+    pub fn from_keychain(keychain: &Keychain) -> Result<Self> {
+        let key = match keychain.get(Self::KEYCHAIN_ACCOUNT)? {
+            Some(hex) => decode_hex(hex.expose())
+                .ok_or_else(|| anyhow!("invalid key in keychain: {}", keychain.service()))?,
+            None => {
+                let key = Self::generate_key()?;
+                keychain.set(Self::KEYCHAIN_ACCOUNT, &encode_hex(&key))?;
+                key
+            }
+        };
+        Ok(Self::new(&key))
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl Debug for Encrypted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Encrypted").finish()
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl Format for Encrypted {
+    fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+        use ring::aead::{Aad, Nonce, NONCE_LEN};
+        use ring::rand::{SecureRandom, SystemRandom};
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("couldn't generate nonce"))?;
+        let mut sealed = serde_json::to_vec(value)?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(Self::MAGIC),
+                &mut sealed,
+            )
+            .map_err(|_| anyhow!("couldn't encrypt data"))?;
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&sealed);
+        Ok(bytes)
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+        use ring::aead::{Aad, Nonce, NONCE_LEN};
+        if !bytes.starts_with(Self::MAGIC) || bytes.len() < Self::MAGIC.len() + NONCE_LEN {
+            bail!("data is not encrypted");
+        }
+        let (nonce, sealed) = bytes[Self::MAGIC.len()..].split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("invalid nonce of encrypted data"))?;
+        let mut sealed = sealed.to_vec();
+        let json = self
+            .key
+            .open_in_place(nonce, Aad::from(Self::MAGIC), &mut sealed)
+            .map_err(|_| super::DecryptionFailed)?;
+        Ok(serde_json::from_slice(json)?)
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "encryption")]
+fn decode_hex(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// Update `old` to the values of `new`, keeping the comments (decor) of values and tables that
+// `old` already had.
+#[cfg(feature = "toml")]
+fn merge_toml_table(old: &mut toml_edit::Table, new: toml_edit::Table) {
+    use toml_edit::Item;
+
+    let stale: Vec<String> = old
+        .iter()
+        .map(|(k, _)| k.to_string())
+        .filter(|k| !new.contains_key(k))
+        .collect();
+    for k in stale {
+        old.remove(&k);
+    }
+    for (k, item) in new {
+        match (old.get_mut(&k), item) {
+            (Some(Item::Table(old_table)), Item::Table(new_table)) => {
+                merge_toml_table(old_table, new_table)
+            }
+            // Nested maps are serialized as inline tables, keep them as the tables users wrote.
+            (
+                Some(Item::Table(old_table)),
+                Item::Value(toml_edit::Value::InlineTable(new_table)),
+            ) => merge_toml_table(old_table, new_table.into_table()),
+            (Some(Item::Value(old_value)), Item::Value(mut new_value)) => {
+                *new_value.decor_mut() = old_value.decor().clone();
+                *old_value = new_value;
+            }
+            (Some(old_item), item) => *old_item = item,
+            (None, item) => {
+                old.insert(&k, item);
+            }
+        }
+    }
+}
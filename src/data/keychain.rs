@@ -0,0 +1,214 @@
+#[cfg(feature = "touch-id")]
+use super::authenticate;
+use super::{anyhow, env, Result, Secret};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Exit status of `security` when the item asked for doesn't exist.
+const ITEM_NOT_FOUND: i32 = 44;
+
+/// Secrets (such as passwords or API tokens) stored in the user's macOS Keychain
+///
+/// Unlike the files of the data dir, which end up in backups and in Alfred's synced
+/// preferences, Keychain items are encrypted and stay on the Mac. Each secret is a generic
+/// password item of the Keychain, identified by a service (the workflow's bundle id by default)
+/// and an account name.
+///
+/// Items are read and written with macOS' `security` tool. Secrets are passed to it through
+/// its standard input, so they never show up in the list of running processes.
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::Keychain;
+///
+/// let keychain = Keychain::for_workflow().unwrap();
+/// keychain.set("api_token", "b3f1...").unwrap();
+///
+/// if let Some(token) = keychain.get("api_token").unwrap() {
+///     // call the API with `token.expose()`
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keychain {
+    service: String,
+}
+
+impl Keychain {
+    /// Keychain items of service `service`
+    pub fn new<S: Into<String>>(service: S) -> Self {
+        Keychain {
+            service: service.into(),
+        }
+    }
+
+    /// Keychain items of the workflow, whose service is the workflow's bundle id
+    ///
+    /// # Errors
+    ///
+    /// If the workflow has no bundle id.
+    pub fn for_workflow() -> Result<Self> {
+        env::workflow_bundle_id()
+            .filter(|id| !id.is_empty())
+            .map(Self::new)
+            .ok_or_else(|| anyhow!("missing env variable for bundle id"))
+    }
+
+    /// Service of the items
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Get the secret of `account`, or `None` if there is none
+    ///
+    /// The secret is returned as a [`Secret`], which is never displayed in logs.
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be read (such as when the user denies access to it).
+    ///
+    /// [`Secret`]: struct.Secret.html
+    pub fn get(&self, account: &str) -> Result<Option<Secret<String>>> {
+        let output = Command::new("security")
+            .args([
+                "find-generic-password",
+                "-s",
+                &self.service,
+                "-a",
+                account,
+                "-w",
+            ])
+            .output()?;
+        if output.status.code() == Some(ITEM_NOT_FOUND) {
+            return Ok(None);
+        }
+        check_status("read", &output)?;
+        let secret = String::from_utf8(output.stdout)?;
+        Ok(Some(Secret::new(secret.trim_end_matches('\n').to_string())))
+    }
+
+    /// Get the secret of `account` once the user authenticates with Touch ID (or their password)
+    ///
+    /// Same as [`get()`], after asking the user to authenticate with [`authenticate()`] for
+    /// `reason`. For secrets that shouldn't be used without the user's consent, such as the
+    /// passwords of a password manager.
+    ///
+    /// # Errors
+    ///
+    /// If the user doesn't authenticate, or the Keychain can't be read.
+    ///
+    /// [`get()`]: struct.Keychain.html#method.get
+    /// [`authenticate()`]: fn.authenticate.html
+    #[cfg(feature = "touch-id")]
+    pub fn get_authenticated(&self, account: &str, reason: &str) -> Result<Option<Secret<String>>> {
+        if !authenticate(reason)? {
+            return Err(anyhow!("user didn't authenticate to read {}", account));
+        }
+        self.get(account)
+    }
+
+    /// Set the secret of `account`, replacing the one it had
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be written, or the service, `account` or `secret` contain line
+    /// breaks or NUL characters (which `security` can't be given through its standard input).
+    pub fn set(&self, account: &str, secret: &str) -> Result<()> {
+        debug!("saving {} of {} to keychain", account, self.service);
+        let command = format!(
+            "add-generic-password -U -s {} -a {} -w {}\n",
+            quote(&self.service)?,
+            quote(account)?,
+            quote(secret)?
+        );
+        // Commands read in interactive mode don't show up in the list of processes.
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(command.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        check_status("write", &output)?;
+        // `security -i` reports failed commands on stderr, with a successful exit status.
+        if !output.stderr.is_empty() {
+            return Err(anyhow!(
+                "couldn't write to keychain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Remove the secret of `account`, returning whether there was one
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be written.
+    pub fn remove(&self, account: &str) -> Result<bool> {
+        let output = Command::new("security")
+            .args([
+                "delete-generic-password",
+                "-s",
+                &self.service,
+                "-a",
+                account,
+            ])
+            .output()?;
+        if output.status.code() == Some(ITEM_NOT_FOUND) {
+            return Ok(false);
+        }
+        check_status("write", &output)?;
+        Ok(true)
+    }
+}
+
+fn check_status(action: &str, output: &Output) -> Result<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "couldn't {} keychain ({}): {}",
+            action,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// `s` as a single argument of a command of `security -i`. Line breaks end commands (even
+// quoted) and NUL ends the input, so they can't be passed.
+fn quote(s: &str) -> Result<String> {
+    if s.contains(['\n', '\r', '\0']) {
+        return Err(anyhow!("keychain items can't contain line breaks nor NUL"));
+    }
+    Ok(format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_quotes_arguments() {
+        assert_eq!("\"abc\"", quote("abc").unwrap());
+        assert_eq!(r#""a \"b\" \\c""#, quote(r#"a "b" \c"#).unwrap());
+        assert!(quote("token\ndelete-generic-password -s other").is_err());
+        assert!(quote("token\r").is_err());
+        assert!(quote("token\0").is_err());
+    }
+
+    #[test]
+    fn it_rejects_secrets_with_line_breaks() {
+        let keychain = Keychain::new("alfred-rs.test");
+        assert!(keychain
+            .set("account", "secret\nadd-generic-password")
+            .is_err());
+        assert!(keychain.set("account\n", "secret").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use super::serde_json;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::marker::PhantomData;
+
+/// Iterator over the entries of a log written by [`Data::append()`]
+///
+/// Entries are read from disk one line at a time, in the order they were appended, so even
+/// long logs are never loaded in memory as a whole. Lines that cannot be deserialized to `V`
+/// (such as a line cut short by a crash) are skipped.
+///
+/// Created by [`Data::read_log()`].
+///
+/// [`Data::append()`]: struct.Data.html#method.append
+/// [`Data::read_log()`]: struct.Data.html#method.read_log
+#[derive(Debug)]
+pub struct LogReader<V> {
+    lines: Option<std::io::Lines<BufReader<File>>>,
+    entry: PhantomData<fn() -> V>,
+}
+
+impl<V> LogReader<V> {
+    // `None` for a log that doesn't exist yet.
+    pub(super) fn new(file: Option<File>) -> Self {
+        LogReader {
+            lines: file.map(|file| BufReader::new(file).lines()),
+            entry: PhantomData,
+        }
+    }
+}
+
+impl<V> Iterator for LogReader<V>
+where
+    V: for<'d> Deserialize<'d>,
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        let lines = self.lines.as_mut()?;
+        loop {
+            let line = match lines.next()? {
+                Ok(line) => line,
+                Err(e) => {
+                    debug!("couldn't read log: {}", e);
+                    return None;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => return Some(entry),
+                Err(e) => debug!("skipping log entry: {}", e),
+            }
+        }
+    }
+}
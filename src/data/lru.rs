@@ -0,0 +1,314 @@
+use super::{Data, Result};
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// File in cache's dir that tracks its entries and when they were last used.
+const INDEX_FN: &str = "index.json";
+
+/// A keyed cache in workflow's cache dir that evicts its least recently used entries
+///
+/// Each entry is saved to its own file in a dedicated directory of the cache dir. When the
+/// cache holds more than [`max_entries()`] entries, or its files take more than
+/// [`max_bytes()`] bytes, the entries that were least recently inserted or read are deleted.
+///
+/// Reading entries only updates their recency in memory, it is written to disk with the next
+/// [`insert()`] or [`remove()`], or when the cache is dropped.
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::LruCache;
+///
+/// # fn search(_: &str) -> Vec<String> { vec![] }
+/// let mut results = LruCache::open("queries").unwrap().max_entries(100);
+///
+/// let query = "rust";
+/// let items: Vec<String> = match results.get(query) {
+///     Some(items) => items,
+///     None => {
+///         let items = search(query);
+///         results.insert(query, &items).unwrap();
+///         items
+///     }
+/// };
+/// ```
+///
+/// [`max_entries()`]: struct.LruCache.html#method.max_entries
+/// [`max_bytes()`]: struct.LruCache.html#method.max_bytes
+/// [`insert()`]: struct.LruCache.html#method.insert
+/// [`remove()`]: struct.LruCache.html#method.remove
+#[derive(Debug)]
+pub struct LruCache {
+    dir: PathBuf,
+    max_entries: Option<usize>,
+    max_bytes: Option<u64>,
+    index: Index,
+    // Recency of entries changed since the index was last written.
+    dirty: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    // Incremented on every use of an entry, orders entries by recency.
+    clock: u64,
+    next_id: u64,
+    entries: HashMap<String, Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    id: u64,
+    size: u64,
+    used: u64,
+}
+
+impl LruCache {
+    /// Opens (or creates) the cache named `name`, a directory in workflow's cache dir
+    ///
+    /// Same as the files of [`Data::save_to_file()`], `name` is relative to workflow's cache dir
+    /// and can include sub-directories (e.g. `avatars/large`). The cache has no limits until
+    /// they are set with [`max_entries()`] or [`max_bytes()`].
+    ///
+    /// # Errors
+    ///
+    /// If the cache dir is not set, `name` is out of the cache dir (an absolute path elsewhere,
+    /// or one that goes up with `..`) or the cache's directory can't be created.
+    ///
+    /// [`Data::save_to_file()`]: struct.Data.html#method.save_to_file
+    /// [`max_entries()`]: struct.LruCache.html#method.max_entries
+    /// [`max_bytes()`]: struct.LruCache.html#method.max_bytes
+    pub fn open<P: AsRef<Path>>(name: P) -> Result<Self> {
+        let dir = Data::cache_file_path(name.as_ref())?;
+        fs::create_dir_all(&dir)?;
+        let index = Data::read_data_from_disk(&dir.join(INDEX_FN)).unwrap_or_default();
+        Ok(LruCache {
+            dir,
+            max_entries: None,
+            max_bytes: None,
+            index,
+            dirty: false,
+        })
+    }
+
+    /// Keep at most `max_entries` entries in the cache
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Keep the files of cache's entries under `max_bytes` bytes in total
+    ///
+    /// An entry bigger than `max_bytes` on its own is not kept at all.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Get the entry of `key`, marking it as the most recently used one
+    ///
+    /// `None` is returned if there is no such entry or it cannot be deserialized to `V`.
+    pub fn get<K, V>(&mut self, key: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = {
+            let entry = self.index.entries.get_mut(key.as_ref())?;
+            self.index.clock += 1;
+            entry.used = self.index.clock;
+            self.dir.join(entry_fn(entry.id))
+        };
+        self.dirty = true;
+        Data::read_data_from_disk(&p).ok()
+    }
+
+    /// Returns `true` if there is an entry for `key`, without marking it as used
+    pub fn contains_key<K: AsRef<str>>(&self, key: K) -> bool {
+        self.index.entries.contains_key(key.as_ref())
+    }
+
+    /// Set the entry of `key` to `v`, evicting least recently used entries beyond cache's
+    /// limits
+    ///
+    /// # Errors
+    ///
+    /// If `v` cannot be serialized or there are file IO issues an error is returned.
+    pub fn insert<K, V>(&mut self, key: K, v: &V) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        let key = key.into();
+        let id = match self.index.entries.get(&key) {
+            Some(entry) => entry.id,
+            None => {
+                self.index.next_id += 1;
+                self.index.next_id
+            }
+        };
+        let p = self.dir.join(entry_fn(id));
+        Data::write_data_to_disk(&p, v)?;
+        let size = p.metadata()?.len();
+        self.index.clock += 1;
+        let used = self.index.clock;
+        self.index.entries.insert(key, Entry { id, size, used });
+        self.evict()?;
+        self.save_index()
+    }
+
+    /// Remove the entry of `key`, returning whether there was one
+    ///
+    /// # Errors
+    ///
+    /// If there are file IO issues an error is returned.
+    pub fn remove<K: AsRef<str>>(&mut self, key: K) -> Result<bool> {
+        match self.index.entries.remove(key.as_ref()) {
+            Some(entry) => {
+                self.remove_entry_file(entry.id)?;
+                self.save_index()?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Remove all entries of the cache
+    ///
+    /// # Errors
+    ///
+    /// If there are file IO issues an error is returned.
+    pub fn clear(&mut self) -> Result<()> {
+        for (_, entry) in self.index.entries.drain() {
+            let p = self.dir.join(entry_fn(entry.id));
+            if let Err(e) = fs::remove_file(&p) {
+                debug!("couldn't remove cache entry {:?}: {}", p, e);
+            }
+        }
+        self.save_index()
+    }
+
+    /// Number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.index.entries.len()
+    }
+
+    /// Returns `true` if the cache has no entries
+    pub fn is_empty(&self) -> bool {
+        self.index.entries.is_empty()
+    }
+
+    /// Total size in bytes of the files of cache's entries
+    pub fn total_bytes(&self) -> u64 {
+        self.index.entries.values().map(|entry| entry.size).sum()
+    }
+
+    // Remove least recently used entries until the cache is within its limits.
+    fn evict(&mut self) -> Result<()> {
+        loop {
+            let too_many = self
+                .max_entries
+                .map_or(false, |max| self.index.entries.len() > max);
+            let too_big = self.max_bytes.map_or(false, |max| self.total_bytes() > max);
+            if !too_many && !too_big {
+                return Ok(());
+            }
+            let lru = self
+                .index
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone());
+            let entry = match lru.and_then(|key| self.index.entries.remove(&key)) {
+                Some(entry) => entry,
+                None => return Ok(()),
+            };
+            debug!("evicting cache entry {}", entry.id);
+            self.remove_entry_file(entry.id)?;
+        }
+    }
+
+    fn remove_entry_file(&self, id: u64) -> Result<()> {
+        match fs::remove_file(self.dir.join(entry_fn(id))) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn save_index(&mut self) -> Result<()> {
+        Data::write_data_to_disk(self.dir.join(INDEX_FN), &self.index)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for LruCache {
+    fn drop(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Err(e) = self.save_index() {
+            debug!("couldn't save index of cache {:?}: {}", self.dir, e);
+        }
+    }
+}
+
+fn entry_fn(id: u64) -> String {
+    format!("{}.json", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::setup_workflow_env_vars;
+    use super::*;
+
+    #[test]
+    fn it_evicts_least_recently_used_entries() {
+        setup_workflow_env_vars(true);
+
+        let mut cache = LruCache::open("lru_test").unwrap().max_entries(2);
+        cache.insert("a", &1).unwrap();
+        cache.insert("b", &2).unwrap();
+        assert_eq!(Some(1), cache.get("a"));
+        cache.insert("c", &3).unwrap();
+        assert_eq!(2, cache.len());
+        assert!(!cache.contains_key("b"));
+        drop(cache);
+
+        // Recency survives reopening the cache.
+        let mut cache = LruCache::open("lru_test").unwrap().max_entries(2);
+        assert_eq!(Some(3), cache.get::<_, u32>("c"));
+        cache.insert("d", &4).unwrap();
+        assert!(!cache.contains_key("a"));
+        assert!(cache.remove("c").unwrap());
+        assert!(!cache.remove("c").unwrap());
+        assert_eq!(1, cache.len());
+
+        let mut cache = LruCache::open("lru_test").unwrap().max_bytes(5);
+        cache.insert("long", &"abcdefgh").unwrap();
+        assert!(!cache.contains_key("long"));
+        assert!(!cache.contains_key("d"));
+        cache.insert("e", &5).unwrap();
+        cache.insert("f", &6).unwrap();
+        assert_eq!(2, cache.len());
+        assert_eq!(2, cache.total_bytes());
+        cache.clear().unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn it_opens_caches_in_sub_dirs() {
+        setup_workflow_env_vars(true);
+
+        let mut cache = LruCache::open("lru_dirs_test/small").unwrap();
+        cache.insert("a", &1).unwrap();
+        assert!(Data::cache_dir()
+            .unwrap()
+            .join("lru_dirs_test/small")
+            .join(INDEX_FN)
+            .exists());
+        cache.clear().unwrap();
+        assert!(LruCache::open("../lru_test").is_err());
+    }
+}
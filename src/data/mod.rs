@@ -11,6 +11,12 @@
 //! To save/load temporary data, use [`save_to_file()`] and [`load_from_file()`] functions.
 //! Example of such data are cached list of items related to workflow or a downloaded file to be used later.
 //!
+//! Files that must survive cleanups of the cache dir can be saved to workflow's data dir with
+//! [`save_to_data_file()`] and [`load_from_data_file()`]. Data that only ever grows, such as
+//! usage history, can be [`append()`]ed to a log instead. Large values that change
+//! independently of each other can be kept in a [`DirStore`], which saves each key to its own
+//! file.
+//!
 //! # Example
 //! ```rust,no_run
 //! extern crate chrono;
@@ -43,295 +49,4289 @@
 //! [`get()`]: struct.Data.html#method.get
 //! [`save_to_file()`]: struct.Data.html#method.save_to_file
 //! [`load_from_file()`]: struct.Data.html#method.load_from_file
+//! [`save_to_data_file()`]: struct.Data.html#method.save_to_data_file
+//! [`load_from_data_file()`]: struct.Data.html#method.load_from_data_file
+//! [`append()`]: struct.Data.html#method.append
+//! [`DirStore`]: struct.DirStore.html
 //! [documentation]: struct.Data.html
 use super::{anyhow, bail, env, serde, serde_json, tempfile, Result};
+use crate::lock::FileLock;
 
+use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::{from_value, to_value, Value};
-use std::collections::HashMap;
+use serde_json::{to_value, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "touch-id")]
+mod auth;
+mod binding;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
+mod config;
+mod dir_store;
+mod error;
+pub mod format;
+mod keychain;
+mod log;
+mod lru;
+mod secret;
+#[cfg(feature = "sqlite")]
+mod store;
+mod stream;
+mod token;
+mod transaction;
+
+#[cfg(feature = "async")]
+pub use self::asynchronous::AsyncData;
+#[cfg(feature = "touch-id")]
+pub use self::auth::authenticate;
+pub use self::binding::Binding;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use self::compression::Compression;
+pub use self::config::WorkflowConfig;
+pub use self::dir_store::DirStore;
+#[cfg(feature = "encryption")]
+pub use self::error::DecryptionFailed;
+pub use self::error::TypeMismatch;
+pub use self::format::Format;
+pub use self::keychain::Keychain;
+pub use self::log::LogReader;
+pub use self::lru::LruCache;
+pub use self::secret::Secret;
+#[cfg(feature = "sqlite")]
+pub use self::store::Store;
+pub use self::stream::ItemReader;
+pub use self::token::StoredToken;
+pub use self::transaction::Transaction;
+#[cfg(feature = "derive")]
+pub use alfred_rs_derive::WorkflowConfig;
+
+// Directories set with `Data::set_data_dir()` and `Data::set_cache_dir()`, used instead of the
+// ones Alfred sets in the environment.
+static DATA_DIR: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+static CACHE_DIR: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+
+// Content of the data files read by this process along with the version of the file that was
+// read, so that loading a file again doesn't re-read and re-parse it unless it changed. Files
+// of `load_from_file()` and friends, which can be large, are read every time.
+static READ_CACHE: Lazy<Mutex<HashMap<PathBuf, (FileVersion, Value)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Variable that Alfred sets to identify a usage session.
+const SESSION_ID_VAR: &str = "alfred_session_id";
+
+// Sub-directory of workflow's cache dir where the updater keeps its state and downloads, left
+// alone by `clear_cache()` and the other functions that delete cache files.
+pub(crate) const UPDATER_CACHE_DIR: &str = "alfred_rs_updater";
+
+// Number of corrupt versions of a data file that are kept, see `set_aside_corrupt_file()`.
+const MAX_CORRUPT_FILES: usize = 3;
+
+// Prefix of the temporary files that are written and then renamed over files being saved.
+const TEMP_FN_PREFIX: &str = ".alfred_rs_temp";
+
+// Prefix of the files in workflow's cache dir that store data of a session.
+const SESSION_FN_PREFIX: &str = "alfred_rs_session_";
+
+// Start of the first line of files saved with `save_to_file_with_checksum()`, followed by
+// the checksum of the rest of the file.
+const CHECKSUM_HEADER: &str = "alfred-rs fnv1a64 ";
+
+// Prefix of the workflow variables that override values of keys, see `allow_env_overrides()`.
+const ENV_OVERRIDE_PREFIX: &str = "wf_setting_";
+
+// Key under which expiry times of values set by `set_with_ttl()` are saved in data files.
+const EXPIRES_KEY: &str = "alfred_rs_expires";
+
+// Key under which times of last change of keys are saved in data files, see
+// `track_modifications()`.
+const MODIFIED_KEY: &str = "alfred_rs_modified";
 
 /// Workflow data that will be persisted to disk
 #[derive(Debug)]
 pub struct Data {
     inner: HashMap<String, Value>,
+    // Expiry time (milliseconds since Unix epoch) of keys set with `set_with_ttl()`.
+    expires: HashMap<String, u64>,
+    // Time of last change (milliseconds since Unix epoch) of keys, see `track_modifications()`.
+    modified: HashMap<String, u64>,
+    // Values of keys that are not set, see `with_defaults()`. Never written to disk.
+    defaults: HashMap<String, Value>,
+    // Values of keys set by users as workflow variables, see `allow_env_overrides()`.
+    env_overrides: HashMap<String, Value>,
     file_name: PathBuf,
+    // `None` for JSON, written straight from the map.
+    format: Option<Box<dyn Format>>,
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compression: Option<Compression>,
+    // Changes are only written by `save()` (or when dropped), see `defer_writes()`.
+    deferred: bool,
+    // JSON data file is indented, see `pretty_print()`.
+    pretty: bool,
+    // Record when keys are changed, see `track_modifications()`.
+    track_modified: bool,
+    // Number of previous versions of the data file to keep, see `keep_versions()`.
+    versions: usize,
+    // There are changes that haven't been written to disk yet.
+    dirty: bool,
+    // Keys set or removed since the data file was last written, see `save()`.
+    changed: HashSet<String>,
+    // Amounts that changed keys were only incremented by, see `increment_by()`.
+    increments: HashMap<String, i64>,
+    // All keys were cleared since the data file was last written.
+    cleared: bool,
+    // Where the data file was moved to because it was corrupt, see `corrupt_file()`.
+    corrupt_file: Option<PathBuf>,
+    // Version of the data file when it was last read or written, see `is_stale()`.
+    version: Option<FileVersion>,
+}
+
+// Values of the keys of a data file and their metadata, as saved in the file.
+#[derive(Debug, Default)]
+struct Entries {
+    inner: HashMap<String, Value>,
+    expires: HashMap<String, u64>,
+    modified: HashMap<String, u64>,
+}
+
+// Modification time and size of a file, and on unix its inode and status change time, which
+// change whenever it is replaced. A file rewritten in place with the same size within the
+// granularity of timestamps keeps its inode too, which is why `save()` doesn't trust it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileVersion {
+    modified: SystemTime,
+    len: u64,
+    #[cfg(unix)]
+    inode: u64,
+    #[cfg(unix)]
+    changed: (i64, i64),
+}
+
+impl FileVersion {
+    fn of(p: &Path) -> Option<Self> {
+        #[cfg(unix)]
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(p).ok()?;
+        Some(FileVersion {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+            #[cfg(unix)]
+            inode: metadata.ino(),
+            #[cfg(unix)]
+            changed: (metadata.ctime(), metadata.ctime_nsec()),
+        })
+    }
 }
 
 impl Data {
     /// Loads the workflow data or creates a new one.
     ///
     /// Reads the data stored in `p` file.
-    /// Only file name section of `p` is used as data will be always saved
-    /// in workflow's default data dir.
+    /// `p` is relative to workflow's default data dir, where data will always be saved. It can
+    /// include sub-directories (e.g. `accounts/work.json`), which are created when saving.
     /// If the file is missing or corrupt a new (empty) Data instance will be returned.
     ///
+    /// The content of files is kept in memory once read, loading the same file again in the
+    /// same process only reads it again if it changed.
+    ///
     /// # Errors
-    /// This method can fail if any disk/IO error happens.
+    /// This method can fail if any disk/IO error happens, or if `p` is out of the data dir
+    /// (an absolute path elsewhere, or one that goes up with `..`).
     pub fn load<P: AsRef<Path>>(p: P) -> Result<Self> {
-        if p.as_ref().as_os_str().is_empty() {
-            bail!("File name to load data from cannot be empty");
-        }
+        let wf_data_fn = Self::data_file_path(p.as_ref())?;
+        Self::from_file(wf_data_fn, None)
+    }
 
-        // Only use the file name section of input parameter. We will always save to Workflow's
-        // data dir
-        let filename = p
-            .as_ref()
-            .file_name()
-            .ok_or_else(|| anyhow!("invalid file name"))?;
-        let wf_data_path = env::workflow_data().ok_or_else(|| {
-            anyhow!("missing env variable for data dir. forgot to set workflow bundle id?")
-        })?;
+    /// Same as [`load()`] but the data file is in the given [`Format`] instead of JSON
+    ///
+    /// The data is saved back in the same format. Besides the formats of the [`format`] module,
+    /// any implementation of [`Format`] can be used.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use alfred_rs::data::{format, Data};
+    ///
+    /// // Needs the `toml` feature
+    /// let mut wf_data = Data::load_with_format("settings.toml", format::Toml).unwrap();
+    /// wf_data.set("page_size", &20).unwrap();
+    /// ```
+    /// # Errors
+    /// Same as [`load()`], and a [`DecryptionFailed`] error if the file is [encrypted] with
+    /// another key (the file is then left as is, not treated as corrupt).
+    ///
+    /// [`load()`]: struct.Data.html#method.load
+    /// [`Format`]: format/trait.Format.html
+    /// [`format`]: format/index.html
+    /// [`DecryptionFailed`]: struct.DecryptionFailed.html
+    /// [encrypted]: format/struct.Encrypted.html
+    pub fn load_with_format<P, F>(p: P, format: F) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: Format + 'static,
+    {
+        let wf_data_fn = Self::data_file_path(p.as_ref())?;
+        Self::from_file(wf_data_fn, Some(Box::new(format)))
+    }
 
-        let wf_data_fn = wf_data_path.join(filename);
+    /// Use the key/value pairs of `defaults` as values of the keys that are not set
+    ///
+    /// `defaults` is a map or a struct (whose fields are the keys). [`get()`] and the other
+    /// getters return the default value of a key that was never set (or was removed), which
+    /// spares an `unwrap_or()` at every call site and documents all defaults in one place.
+    /// Defaults are never written to the data file, so changing them in a new version of the
+    /// workflow applies to users who didn't set the keys. `null` values (such as `None`
+    /// fields) are no defaults.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # #[macro_use]
+    /// # extern crate serde_derive;
+    /// use alfred_rs::data::Data;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Settings {
+    ///     page_size: u32,
+    ///     show_icons: bool,
+    /// }
+    ///
+    /// # fn main() {
+    /// let defaults = Settings {
+    ///     page_size: 20,
+    ///     show_icons: true,
+    /// };
+    /// let settings = Data::load("settings.json")
+    ///     .and_then(|data| data.with_defaults(&defaults))
+    ///     .unwrap();
+    ///
+    /// let page_size: u32 = settings.get("page_size").unwrap();
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// If `defaults` can't be serialized, or isn't serialized to a map.
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    pub fn with_defaults<V: Serialize>(mut self, defaults: &V) -> Result<Self> {
+        let defaults = match to_value(defaults)? {
+            Value::Object(defaults) => defaults,
+            _ => bail!("defaults must be a map or a struct"),
+        };
+        self.defaults = defaults.into_iter().filter(|(_, v)| !v.is_null()).collect();
+        Ok(self)
+    }
 
-        let inner = Self::read_data_from_disk(&wf_data_fn)
-            .or_else(|_| -> Result<_> { Ok(HashMap::new()) })?;
+    // Data stored in `file_name`, without the values that have expired.
+    fn from_file(file_name: PathBuf, format: Option<Box<dyn Format>>) -> Result<Self> {
+        let (entries, mut version) = {
+            let _lock = Self::read_lock(&file_name);
+            (
+                Self::read_entries(&file_name, format.as_deref()),
+                FileVersion::of(&file_name),
+            )
+        };
+        let mut corrupt_file = None;
+        let Entries {
+            inner,
+            expires,
+            modified,
+        } = match entries {
+            Ok(entries) => entries,
+            Err(e) if is_corrupt(&e) => {
+                debug!("data file {:?} is corrupt: {}", file_name, e);
+                corrupt_file = Self::write_lock(&file_name)
+                    .and_then(|_lock| Self::set_aside_corrupt_file(&file_name))
+                    .map_err(|e| debug!("couldn't move corrupt data file aside: {}", e))
+                    .ok();
+                version = FileVersion::of(&file_name);
+                Default::default()
+            }
+            Err(e) if is_undecryptable(&e) => return Err(e),
+            Err(_) => Default::default(),
+        };
         Ok(Data {
             inner,
-            file_name: wf_data_fn,
+            expires,
+            modified,
+            defaults: HashMap::new(),
+            env_overrides: env_overrides(),
+            file_name,
+            format,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression: None,
+            deferred: false,
+            pretty: false,
+            track_modified: false,
+            versions: 0,
+            dirty: false,
+            changed: HashSet::new(),
+            increments: HashMap::new(),
+            cleared: false,
+            corrupt_file,
+            version,
         })
     }
 
-    /// Set the value of key `k` to `v` and persist it to disk
+    // Rename the corrupt data file `p` to `<p>.corrupt-<timestamp>`, so that it isn't overwritten.
+    // Only the `MAX_CORRUPT_FILES` most recent corrupt files are kept.
+    fn set_aside_corrupt_file(p: &Path) -> Result<PathBuf> {
+        // A file corrupt again within the same millisecond doesn't replace the previous one.
+        let mut timestamp = now_millis();
+        let mut corrupt = sibling_path(p, &format!("corrupt-{}", timestamp));
+        while corrupt.exists() {
+            timestamp += 1;
+            corrupt = sibling_path(p, &format!("corrupt-{}", timestamp));
+        }
+        std::fs::rename(p, &corrupt)?;
+        debug!("moved corrupt data file to {:?}", corrupt);
+        if let Err(e) = Self::remove_old_corrupt_files(p) {
+            debug!("couldn't remove old corrupt data files: {}", e);
+        }
+        Ok(corrupt)
+    }
+
+    // Delete the corrupt versions of data file `p` but the `MAX_CORRUPT_FILES` most recent.
+    fn remove_old_corrupt_files(p: &Path) -> Result<()> {
+        let prefix = sibling_path(p, "corrupt-");
+        let prefix = prefix.file_name().unwrap_or_default().to_string_lossy();
+        let dir = match p.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut corrupt = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let timestamp = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix.as_ref()))
+                .and_then(|timestamp| timestamp.parse::<u64>().ok());
+            if let Some(timestamp) = timestamp {
+                corrupt.push((timestamp, entry.path()));
+            }
+        }
+        // Most recent first
+        corrupt.sort_by(|a, b| b.cmp(a));
+        for (_, old) in corrupt.into_iter().skip(MAX_CORRUPT_FILES) {
+            std::fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the data file was changed on disk since this `Data` last read or wrote
+    /// it, e.g. by a background job of the workflow
     ///
-    /// `k` is a type that implements `Into<String>`. `v` can be any type as long as it
-    /// implements `Serialize`.
+    /// See [`reload()`] to get the changes.
     ///
-    /// This method overwrites values of any existing keys, otherwise adds
-    /// the key/value pair to the workflow's standard data file
+    /// [`reload()`]: struct.Data.html#method.reload
+    pub fn is_stale(&self) -> bool {
+        FileVersion::of(&self.file_name) != self.version
+    }
+
+    /// Read the data file again, to get the changes other invocations of the workflow saved
+    ///
+    /// Changes made through this `Data` that are not saved yet (see [`defer_writes()`]) are
+    /// kept over the values of the file.
     ///
     /// # Example
     /// ```rust,no_run
-    /// # extern crate chrono;
-    /// # use chrono::prelude::*;
     /// use alfred_rs::data::Data;
     ///
-    /// let mut workflow_data = Data::load("settings.json").unwrap();
-    ///
-    /// workflow_data.set("user_id", &0xFF);
-    /// workflow_data.set("last_log_date", &Utc::now());
+    /// let mut wf_data = Data::load("status.json").unwrap();
+    /// // ... while a background job updates `status.json`
+    /// if wf_data.is_stale() {
+    ///     wf_data.reload().unwrap();
+    /// }
+    /// let progress: u32 = wf_data.get("progress").unwrap_or(0);
     /// ```
     /// # Errors
     ///
-    /// If `v` cannot be serialized or there are file IO issues an error is returned.
-    pub fn set<K, V>(&mut self, k: K, v: &V) -> Result<()>
-    where
-        K: Into<String>,
-        V: Serialize,
-    {
-        let v = to_value(v)?;
-        self.inner.insert(k.into(), v);
-        Self::write_data_to_disk(&self.file_name, &self.inner)
+    /// If the data file exists but can't be read.
+    ///
+    /// [`defer_writes()`]: struct.Data.html#method.defer_writes
+    pub fn reload(&mut self) -> Result<()> {
+        let _lock = Self::read_lock(&self.file_name);
+        let entries = if self.file_name.exists() {
+            Self::read_entries(&self.file_name, self.format.as_deref())?
+        } else {
+            Default::default()
+        };
+        self.version = FileVersion::of(&self.file_name);
+        if !self.cleared {
+            self.apply_changes_to(entries);
+        }
+        Ok(())
     }
 
-    /// Get (possible) value of key `k` from workflow's data
+    /// Path the data file was moved to because it couldn't be read when it was loaded
     ///
-    /// If key `k` has not been set before `None` will be returned.
+    /// Instead of being overwritten by the next change, a data file that can't be parsed (e.g.
+    /// after a bad hand edit) is renamed to `<file>.corrupt-<timestamp>` and the `Data` starts
+    /// empty. Only the 3 most recent corrupt files are kept. Workflows can tell users about it,
+    /// or recover the previous version of the file with [`restore_backup()`] if they keep
+    /// versions.
     ///
-    /// Since the data can be of arbitrary type, you should annotate the type you are expecting
-    /// to get back from data file.
-    /// If the stored value cannot be deserialized back to the desired type `None` is returned.
+    /// [`restore_backup()`]: struct.Data.html#method.restore_backup
+    pub fn corrupt_file(&self) -> Option<&Path> {
+        self.corrupt_file.as_deref()
+    }
+
+    /// Full path of the data file
     ///
     /// # Example
     /// ```rust,no_run
-    /// # extern crate chrono;
-    /// # use chrono::prelude::*;
     /// use alfred_rs::data::Data;
     ///
-    /// let wf_data = Data::load("settings.json").unwrap();
-    ///
-    /// let id: i32 = wf_data.get("user_id").expect("user id was not set");
-    /// let last_log: DateTime<Utc> = wf_data.get("last_log_date").expect("log date was not set");
+    /// let settings = Data::load("settings.json").unwrap();
+    /// println!("settings are saved in {:?}", settings.path());
     /// ```
-    pub fn get<K, V>(&self, k: K) -> Option<V>
-    where
-        K: AsRef<str>,
-        V: for<'d> Deserialize<'d>,
-    {
-        self.inner
-            .get(k.as_ref())
-            .and_then(|v| from_value(v.clone()).ok())
+    pub fn path(&self) -> &Path {
+        &self.file_name
     }
 
-    /// Clear all key-value pairs. Does not affect data on disk.
-    pub fn clear(&mut self) {
-        self.inner.clear();
+    /// Returns `true` if the data file `p` exists in workflow's data dir
+    ///
+    /// Unlike [`load()`], which returns an empty `Data` for a missing file, this tells a
+    /// workflow that was never configured apart from one whose settings are all unset.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// if !Data::exists("settings.json") {
+    ///     println!("run the setup command first");
+    /// }
+    /// ```
+    ///
+    /// [`load()`]: struct.Data.html#method.load
+    pub fn exists<P: AsRef<Path>>(p: P) -> bool {
+        Self::data_file_path(p.as_ref()).map_or(false, |p| p.is_file())
     }
 
-    /// Function to save (temporary) `data` to file named `p` in workflow's cache dir
+    /// Replace the data with the backup of the data file, and save it
     ///
-    /// This function is provided so that workflow authors can temporarily save information
-    /// to workflow's cache dir. The saved data is considered to be irrelevant to workflow's
-    /// actual data (for which you should use [`set`] and [`get`])
+    /// When previous versions are kept (see [`keep_versions()`]), every time the data file is
+    /// saved its previous version is kept as `<file>.bak` (as long as it could be read). Returns
+    /// `false`, without changing anything, if there is no backup.
     ///
     /// # Example
     /// ```rust,no_run
     /// use alfred_rs::data::Data;
     ///
-    /// Data::save_to_file("cached_tags.dat", &vec!["rust", "alfred"]).unwrap();
+    /// let mut settings = Data::load("settings.json").unwrap();
+    /// settings.keep_versions(1);
+    /// if let Some(corrupt) = settings.corrupt_file() {
+    ///     eprintln!("settings were corrupt, moved to {:?}", corrupt);
+    ///     settings.restore_backup().unwrap();
+    /// }
     /// ```
-    /// ## Note
-    /// Only the [`file_name`] portion of `p` will be used to name the file that'll be stored in
-    /// workflow's cache directory.
     /// # Errors
-    /// File IO related issues as well as serializing problems will cause an error to be returned.
     ///
-    /// [`set`]: struct.Data.html#method.set
-    /// [`get`]: struct.Data.html#method.get
-    /// [`file_name`]: https://doc.rust-lang.org/std/path/struct.Path.html#method.file_name
-    pub fn save_to_file<P, V>(p: P, data: &V) -> Result<()>
-    where
-        P: AsRef<Path>,
-        V: Serialize,
-    {
-        let filename = p
-            .as_ref()
-            .file_name()
-            .ok_or_else(|| anyhow!("invalid file name"))?;
-        let p = env::workflow_cache()
-            .map(|wfc| wfc.join(filename))
-            .ok_or_else(|| {
-                anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
-            })?;
-        debug!("saving to: {}", p.to_str().expect(""));
-        Self::write_data_to_disk(p, data)
+    /// If the backup can't be read or the data file can't be written.
+    ///
+    /// [`keep_versions()`]: struct.Data.html#method.keep_versions
+    pub fn restore_backup(&mut self) -> Result<bool> {
+        let backup = sibling_path(&self.file_name, "bak");
+        if !backup.exists() {
+            return Ok(false);
+        }
+        let entries = Self::read_entries(&backup, self.format.as_deref())?;
+        self.inner = entries.inner;
+        self.expires = entries.expires;
+        self.modified = entries.modified;
+        self.changed.clear();
+        self.increments.clear();
+        self.cleared = true;
+        self.dirty = true;
+        self.save()?;
+        Ok(true)
     }
 
-    fn write_data_to_disk<P, V>(p: P, data: &V) -> Result<()>
-    where
-        P: AsRef<Path> + std::fmt::Debug,
-        V: Serialize,
-    {
-        use std::fs;
-        use tempfile::Builder;
-        let wfc = env::workflow_cache().ok_or_else(|| {
-            anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
-        })?;
-        let named_tempfile = Builder::new()
-            .prefix("alfred_rs_temp")
-            .suffix(".json")
-            .rand_bytes(5)
-            .tempfile_in(wfc)?;
-
-        let fn_temp = named_tempfile.as_ref();
-        File::create(&fn_temp).and_then(|fp| {
-            let buf_writer = BufWriter::with_capacity(0x1000, fp);
-            serde_json::to_writer(buf_writer, data)?;
-            Ok(())
-        })?;
+    /// Keep the `n` previous versions of the data file, none by default
+    ///
+    /// Every time the data file is saved, the version it replaces is kept as `<file>.bak`, and
+    /// older ones as `<file>.bak.2`, `<file>.bak.3`, ... up to `n` versions. They can be
+    /// restored with [`rollback()`], e.g. to offer an *undo* command or to recover users from
+    /// a bad automated edit. Keeping versions costs a copy of the file on every save. With 0,
+    /// versions that were kept are left as they are.
+    ///
+    /// [`rollback()`]: struct.Data.html#method.rollback
+    pub fn keep_versions(&mut self, n: usize) {
+        self.versions = n;
+    }
 
-        // Rename over to main file name
-        fs::rename(fn_temp, p)?;
-        Ok(())
+    /// Modification times of the previous versions of the data file that are kept, most
+    /// recent first
+    ///
+    /// The `n`-th time is the one of the version restored by `rollback(n)`, see
+    /// [`keep_versions()`].
+    ///
+    /// [`keep_versions()`]: struct.Data.html#method.keep_versions
+    pub fn versions(&self) -> Vec<SystemTime> {
+        let mut times = Vec::new();
+        while let Ok(modified) = std::fs::metadata(self.version_path(times.len() + 1))
+            .and_then(|metadata| metadata.modified())
+        {
+            times.push(modified);
+        }
+        times
     }
 
-    /// Function to load some (temporary) data from file named `p` in workflow's cache dir
+    /// Go back `n` versions of the data file, see [`keep_versions()`]
     ///
-    /// This function is provided so that workflow authors can retrieve temporarily information
-    /// saved to workflow's cache dir. The saved data is considered to be irrelevant to workflow's
-    /// actual data (for which you should use [`set`] and [`get`])
+    /// `rollback(1)` undoes the last change that was saved. The versions more recent than the
+    /// one restored are dropped, so that calling `rollback(1)` again undoes the change before.
+    /// Changes that are not saved yet are dropped too. Returns `false`, without changing
+    /// anything, if there is no such version.
     ///
     /// # Example
-    ///
     /// ```rust,no_run
     /// use alfred_rs::data::Data;
     ///
-    /// let cached_tags: Vec<String> = Data::load_from_file("cached_tags.dat").unwrap();
+    /// let mut settings = Data::load("settings.json").unwrap();
+    /// settings.keep_versions(10);
+    /// settings.set("page_size", &50).unwrap();
+    ///
+    /// // "Undo" command
+    /// if !settings.rollback(1).unwrap() {
+    ///     println!("nothing to undo");
+    /// }
     /// ```
+    /// # Errors
     ///
-    /// ## Note
-    /// Only the [`file_name`] portion of `p` will be used to name the file, which will then be
-    /// looked up in workflow's cache directory.
+    /// If `n` is 0, or there are file IO issues.
     ///
-    /// [`set`]: struct.Data.html#method.set
-    /// [`get`]: struct.Data.html#method.get
-    /// [`file_name`]: https://doc.rust-lang.org/std/path/struct.Path.html#method.file_name
-    pub fn load_from_file<P, V>(p: P) -> Option<V>
-    where
-        P: AsRef<Path>,
-        V: for<'d> Deserialize<'d>,
-    {
-        let p = env::workflow_cache()
-            .and_then(|wfc| p.as_ref().file_name().map(|name| wfc.join(name)))?;
-        debug!("loading from: {}", p.to_str().expect(""));
-        Self::read_data_from_disk(&p).ok()
+    /// [`keep_versions()`]: struct.Data.html#method.keep_versions
+    pub fn rollback(&mut self, n: usize) -> Result<bool> {
+        if n == 0 {
+            bail!("can't roll back 0 versions");
+        }
+        let _lock = Self::write_lock(&self.file_name)?;
+        let version = self.version_path(n);
+        if !version.exists() {
+            return Ok(false);
+        }
+        let entries = Self::read_entries(&version, self.format.as_deref())?;
+        let kept = self.versions().len().max(n);
+        std::fs::rename(&version, &self.file_name)?;
+        // Versions more recent than the restored one are dropped, older ones move up.
+        for i in 1..=kept {
+            let newer = self.version_path(i);
+            if i + n <= kept {
+                std::fs::rename(self.version_path(i + n), newer)?;
+            } else if newer.exists() {
+                std::fs::remove_file(newer)?;
+            }
+        }
+        self.inner = entries.inner;
+        self.expires = entries.expires;
+        self.modified = entries.modified;
+        self.changed.clear();
+        self.increments.clear();
+        self.cleared = false;
+        self.dirty = false;
+        self.version = FileVersion::of(&self.file_name);
+        Ok(true)
     }
 
-    fn read_data_from_disk<V>(p: &Path) -> Result<V>
-    where
-        V: for<'d> Deserialize<'d>,
-    {
-        File::open(p).map_err(Into::into).and_then(|fp| {
-            let buf_reader = BufReader::with_capacity(0x1000, fp);
-            let d: V = serde_json::from_reader(buf_reader)?;
-            Ok(d)
-        })
+    // Path of the `n`-th previous version of the data file.
+    fn version_path(&self, n: usize) -> PathBuf {
+        match n {
+            1 => sibling_path(&self.file_name, "bak"),
+            n => sibling_path(&self.file_name, &format!("bak.{}", n)),
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::prelude::*;
-    use std::env as StdEnv;
-    use std::ffi::OsStr;
-    use std::fs::remove_file;
-    use std::{thread, time};
-    use tempfile::Builder;
 
-    #[test]
-    fn it_sets_gets_data() {
-        #[derive(Serialize, Deserialize)]
-        struct User {
-            name: String,
-            age: usize,
+    // Keep the data file, before it's replaced, as the most recent of its `versions` previous
+    // versions.
+    fn keep_version(&self) -> Result<()> {
+        let mut n = self.versions;
+        // Drop the versions that are no longer kept.
+        while self.version_path(n).exists() {
+            std::fs::remove_file(self.version_path(n))?;
+            n += 1;
         }
+        for n in (1..self.versions).rev() {
+            let version = self.version_path(n);
+            if version.exists() {
+                std::fs::rename(version, self.version_path(n + 1))?;
+            }
+        }
+        std::fs::copy(&self.file_name, self.version_path(1))?;
+        Ok(())
+    }
 
-        setup_workflow_env_vars(true);
-
-        let user = User {
-            name: "Hamid".to_string(),
-            age: 42,
+    // Values and metadata of the keys stored in `file_name` that haven't expired.
+    fn read_entries(file_name: &Path, format: Option<&dyn Format>) -> Result<Entries> {
+        // Files in other formats are read every time, what was read of a file depends on the
+        // format (and key, for encrypted files) it was read with.
+        let mut inner: HashMap<String, Value> = match format {
+            Some(format) => Self::read_with_format(file_name, format)?,
+            None => serde_json::from_value(Self::read_cached(file_name, |p| {
+                Self::read_data_from_disk(p)
+            })?)?,
         };
+        let mut expires: HashMap<String, u64> = inner
+            .remove(EXPIRES_KEY)
+            .and_then(|expires| serde_json::from_value(expires).ok())
+            .unwrap_or_default();
+        let mut modified: HashMap<String, u64> = inner
+            .remove(MODIFIED_KEY)
+            .and_then(|modified| serde_json::from_value(modified).ok())
+            .unwrap_or_default();
+        let now = now_millis();
+        expires.retain(|k, expires_at| {
+            if *expires_at > now {
+                true
+            } else {
+                inner.remove(k);
+                false
+            }
+        });
+        modified.retain(|k, _| inner.contains_key(k));
+        Ok(Entries {
+            inner,
+            expires,
+            modified,
+        })
+    }
 
-        {
-            let mut wf_data: Data = Data::load("settings_test.json").unwrap();
-            wf_data.set("key1", &8).unwrap();
-            wf_data.set("key2", &user).unwrap();
-            wf_data.set("date", &Utc::now()).unwrap();
-            println!("{:?}", wf_data);
+    // Shared lock for reading `p`. Reading goes on without it if the lock can't be taken (e.g.
+    // the directory of `p` doesn't exist yet).
+    fn read_lock(p: &Path) -> Option<FileLock> {
+        FileLock::shared(p)
+            .map_err(|e| debug!("couldn't lock {:?} for reading: {}", p, e))
+            .ok()
+    }
+
+    // Exclusive lock for writing `p`, creating its directory if needed.
+    fn write_lock(p: &Path) -> Result<FileLock> {
+        if let Some(dir) = p.parent() {
+            std::fs::create_dir_all(dir)?;
         }
+        FileLock::exclusive(p)
+    }
 
-        {
-            let wf_data = Data::load("settings_test.json").unwrap();
+    /// Use `dir` as workflow's data dir instead of the one Alfred sets
+    ///
+    /// Outside of Alfred (e.g. when the workflow's binary runs as a command line tool, from a
+    /// `launchd` job or in tests) there is no `alfred_workflow_data` variable to locate the
+    /// data dir. The dir set here is used, for the whole process, by all `Data` loaded
+    /// afterwards, by the functions of this module that use the data dir and by the updater.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// if Data::data_dir().is_none() {
+    ///     Data::set_data_dir("/Users/me/.config/my-workflow");
+    /// }
+    /// let settings = Data::load("settings.json").unwrap();
+    /// ```
+    pub fn set_data_dir<P: Into<PathBuf>>(dir: P) {
+        *DATA_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+    }
 
-            assert_eq!(3, wf_data.inner.len());
-            let user: User = wf_data.get("key2").unwrap();
-            assert_eq!(42, user.age);
+    /// Use `dir` as workflow's cache dir instead of the one Alfred sets
+    ///
+    /// Same as [`set_data_dir()`], for the cache dir (`alfred_workflow_cache`).
+    ///
+    /// [`set_data_dir()`]: struct.Data.html#method.set_data_dir
+    pub fn set_cache_dir<P: Into<PathBuf>>(dir: P) {
+        *CACHE_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(dir.into());
+    }
 
-            let x: i8 = wf_data.get("key1").unwrap();
-            assert_eq!(8, x);
-            let _last_log: DateTime<Utc> = wf_data.get("date").expect("log date was not set");
-        }
+    /// Workflow's data dir: the one set with [`set_data_dir()`], or else the one Alfred sets
+    ///
+    /// [`set_data_dir()`]: struct.Data.html#method.set_data_dir
+    pub fn data_dir() -> Option<PathBuf> {
+        DATA_DIR
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .or_else(env::workflow_data)
     }
 
-    #[test]
-    fn it_saves_loads_from_file() {
-        let wfc = setup_workflow_env_vars(true);
-        let path = wfc.join("_test_saves_loads_from_file");
-        let _r = remove_file(&path);
+    /// Workflow's cache dir: the one set with [`set_cache_dir()`], or else the one Alfred sets
+    ///
+    /// [`set_cache_dir()`]: struct.Data.html#method.set_cache_dir
+    pub fn cache_dir() -> Option<PathBuf> {
+        CACHE_DIR
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .or_else(env::workflow_cache)
+    }
 
-        let now = Utc::now();
-        Data::save_to_file(&path, &now).expect("couldn't write to file");
+    // Path of data file `p` in workflow's data dir.
+    fn data_file_path(p: &Path) -> Result<PathBuf> {
+        if p.as_os_str().is_empty() {
+            bail!("File name to load data from cannot be empty");
+        }
+
+        // We will always save to Workflow's data dir
+        let wf_data_path = Self::data_dir().ok_or_else(|| {
+            anyhow!("missing env variable for data dir. forgot to set workflow bundle id?")
+        })?;
+        let p = Self::relative_path(&wf_data_path, p)?;
+        Ok(wf_data_path.join(p))
+    }
+
+    // `p` relative to `dir`, as long as it stays within `dir`. Absolute paths are accepted if
+    // they are in `dir`.
+    fn relative_path(dir: &Path, p: &Path) -> Result<PathBuf> {
+        use std::path::Component;
+        let p = if p.is_absolute() {
+            p.strip_prefix(dir)
+                .map_err(|_| anyhow!("path is not in workflow's directory: {:?}", p))?
+        } else {
+            p
+        };
+        let mut relative = PathBuf::new();
+        for component in p.components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    bail!("path can't contain `..`: {:?}", p)
+                }
+            }
+        }
+        if relative.as_os_str().is_empty() {
+            bail!("invalid file name: {:?}", p);
+        }
+        Ok(relative)
+    }
+
+    /// Loads the data of Alfred's current usage session
+    ///
+    /// Alfred sets `alfred_session_id` for as long as its window stays open, so values set in
+    /// a session survive reruns of a script filter but are gone once the user opens Alfred
+    /// again. Session data is stored in workflow's cache dir, and the data of previous
+    /// sessions is deleted when a new session starts.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut session = Data::session().unwrap();
+    /// let page: u32 = session.get("page").unwrap_or(0);
+    /// session.set("page", &(page + 1)).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If `alfred_session_id` or the cache dir are not set, or on disk/IO errors.
+    pub fn session() -> Result<Self> {
+        let session_id = std::env::var(SESSION_ID_VAR)
+            .map_err(|_| anyhow!("missing env variable for session id: {}", SESSION_ID_VAR))?;
+        let session_id: String = session_id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        if session_id.is_empty() {
+            bail!("invalid session id");
+        }
+        let file_name =
+            Self::cache_file_path(format!("{}{}.json", SESSION_FN_PREFIX, session_id).as_ref())?;
+
+        if !file_name.exists() {
+            let previous = Self::clear_cache_matching(|name| name.starts_with(SESSION_FN_PREFIX))?;
+            debug!("new session, removed data of {} previous ones", previous);
+        }
+        Self::from_file(file_name, None)
+    }
+
+    /// Loads a whole data file into a value of type `T`
+    ///
+    /// Instead of storing values under string keys, the file holds a single `T` (typically a
+    /// settings struct), so its fields are checked at compile time. The returned [`Binding`]
+    /// dereferences to `T` and saves it back with [`Binding::save()`].
+    ///
+    /// Same as [`load()`], `p` is relative to workflow's data dir. If the file is missing,
+    /// `T::default()` is returned. Use serde's `default` attribute on new fields so that files
+    /// saved by older versions of the workflow can still be read.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # #[macro_use]
+    /// # extern crate serde_derive;
+    /// use alfred_rs::data::{Binding, Data};
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct Settings {
+    ///     page_size: u32,
+    ///     #[serde(default)]
+    ///     dark_mode: bool,
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut settings: Binding<Settings> = Data::bind("settings.json").unwrap();
+    /// settings.page_size = 50;
+    /// settings.save().unwrap();
+    /// # }
+    /// ```
+    /// # Errors
+    ///
+    /// An error is returned if the file exists but cannot be read as a `T`, or on disk/IO
+    /// errors.
+    ///
+    /// [`Binding`]: struct.Binding.html
+    /// [`Binding::save()`]: struct.Binding.html#method.save
+    /// [`load()`]: struct.Data.html#method.load
+    pub fn bind<T, P>(p: P) -> Result<Binding<T>>
+    where
+        T: Serialize + for<'d> Deserialize<'d> + Default,
+        P: AsRef<Path>,
+    {
+        let file_name = Self::data_file_path(p.as_ref())?;
+        let value = if file_name.exists() {
+            Self::read_data_from_disk(&file_name)?
+        } else {
+            T::default()
+        };
+        Ok(Binding::new(value, file_name))
+    }
+
+    /// Set the value of key `k` to `v` and persist it to disk
+    ///
+    /// `k` is a type that implements `Into<String>`. `v` can be any type as long as it
+    /// implements `Serialize`.
+    ///
+    /// This method overwrites values of any existing keys, otherwise adds
+    /// the key/value pair to the workflow's standard data file. Keys `alfred_rs_expires` and
+    /// `alfred_rs_modified` are reserved, the expiry times of [`set_with_ttl()`] and the
+    /// times of [`track_modifications()`] are saved under them.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # extern crate chrono;
+    /// # use chrono::prelude::*;
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut workflow_data = Data::load("settings.json").unwrap();
+    ///
+    /// workflow_data.set("user_id", &0xFF);
+    /// workflow_data.set("last_log_date", &Utc::now());
+    /// ```
+    /// # Errors
+    ///
+    /// If `k` is reserved, `v` cannot be serialized or there are file IO issues an error is
+    /// returned.
+    ///
+    /// [`set_with_ttl()`]: struct.Data.html#method.set_with_ttl
+    /// [`track_modifications()`]: struct.Data.html#method.track_modifications
+    pub fn set<K, V>(&mut self, k: K, v: &V) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        let v = to_value(v)?;
+        let k = k.into();
+        check_key(&k)?;
+        self.expires.remove(&k);
+        self.touch(&k);
+        self.inner.insert(k, v);
+        self.persist()
+    }
+
+    /// Set the value of key `k` to `v` for `ttl`, and persist it to disk
+    ///
+    /// Same as [`set()`], but once `ttl` has passed the key behaves as if it was never set:
+    /// [`get()`] returns `None`, it is left out of [`keys()`], ... and it is removed from the
+    /// data file next time the file is written. Setting the key again with [`set()`] makes it
+    /// permanent.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::time::Duration;
+    ///
+    /// let mut wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// wf_data
+    ///     .set_with_ttl("weather", &"sunny", Duration::from_secs(10 * 60))
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If `k` is reserved (see [`set()`]), `v` cannot be serialized or there are file IO issues
+    /// an error is returned.
+    ///
+    /// [`set()`]: struct.Data.html#method.set
+    /// [`get()`]: struct.Data.html#method.get
+    /// [`keys()`]: struct.Data.html#method.keys
+    pub fn set_with_ttl<K, V>(&mut self, k: K, v: &V, ttl: Duration) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        let v = to_value(v)?;
+        let k = k.into();
+        check_key(&k)?;
+        let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        self.expires
+            .insert(k.clone(), now_millis().saturating_add(ttl));
+        self.touch(&k);
+        self.inner.insert(k, v);
+        self.persist()
+    }
+
+    /// Set multiple key/value pairs and persist them to disk with a single write
+    ///
+    /// Same as calling [`set()`] for each pair, but the data file is rewritten only once. If
+    /// any value cannot be serialized, none of the pairs is set. To set values of different
+    /// types, pass them as [`Value`]s (e.g. using `serde_json::json!`).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut workflow_data = Data::load("settings.json").unwrap();
+    ///
+    /// workflow_data
+    ///     .set_many(vec![("page_size", 20), ("max_results", 100), ("timeout", 5)])
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If a key is reserved (see [`set()`]), a value cannot be serialized or there are file IO
+    /// issues an error is returned.
+    ///
+    /// [`set()`]: struct.Data.html#method.set
+    /// [`Value`]: https://docs.rs/serde_json/1/serde_json/enum.Value.html
+    pub fn set_many<I, K, V>(&mut self, pairs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Serialize,
+    {
+        let pairs = pairs
+            .into_iter()
+            .map(|(k, v)| {
+                let k = k.into();
+                check_key(&k)?;
+                Ok((k, to_value(v)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (k, _) in &pairs {
+            self.expires.remove(k);
+            self.touch(k);
+        }
+        self.inner.extend(pairs);
+        self.persist()
+    }
+
+    /// Get (possible) value of key `k` from workflow's data
+    ///
+    /// If key `k` has not been set before, its default value (see [`with_defaults()`]) or
+    /// `None` will be returned. Users can override the value with a workflow variable, see
+    /// [`allow_env_overrides()`].
+    ///
+    /// Since the data can be of arbitrary type, you should annotate the type you are expecting
+    /// to get back from data file.
+    /// If the stored value cannot be deserialized back to the desired type `None` is returned.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # extern crate chrono;
+    /// # use chrono::prelude::*;
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// let id: i32 = wf_data.get("user_id").expect("user id was not set");
+    /// let last_log: DateTime<Utc> = wf_data.get("last_log_date").expect("log date was not set");
+    /// ```
+    ///
+    /// [`with_defaults()`]: struct.Data.html#method.with_defaults
+    /// [`allow_env_overrides()`]: struct.Data.html#method.allow_env_overrides
+    pub fn get<K, V>(&self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        self.get_ref(k)
+    }
+
+    /// Get (possible) value of key `k` from workflow's data, reporting values of another type
+    ///
+    /// Unlike [`get()`], which returns `None` both when the key is not set and when its value
+    /// can't be deserialized to type `V`, the latter is an error, so that bugs such as reading a
+    /// key with the wrong type don't go unnoticed. The raw value is available with
+    /// [`get_value()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// match wf_data.try_get::<_, u32>("page_size") {
+    ///     Ok(page_size) => println!("page size: {}", page_size.unwrap_or(20)),
+    ///     Err(e) => eprintln!("invalid setting {}: {:?}", e.key(), wf_data.get_value(e.key())),
+    /// }
+    /// ```
+    /// # Errors
+    ///
+    /// If the value of `k` can't be deserialized to type `V`.
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    /// [`get_value()`]: struct.Data.html#method.get_value
+    pub fn try_get<K, V>(&self, k: K) -> std::result::Result<Option<V>, TypeMismatch>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let k = k.as_ref();
+        self.get_value(k)
+            .map(|v| V::deserialize(v).map_err(|e| TypeMismatch::new(k, e)))
+            .transpose()
+    }
+
+    /// Get (possible) value of key `k` from workflow's data, borrowing from it where possible
+    ///
+    /// Same as [`get()`] (which never copies the stored data either), but the returned value
+    /// can borrow from the data: strings can be read as `&str` and, with serde's `borrow`
+    /// attribute, structs can hold `&str` fields, without allocating.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// let name: &str = wf_data.get_ref("user_name").expect("user name was not set");
+    /// ```
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    pub fn get_ref<'a, K, V>(&'a self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: Deserialize<'a>,
+    {
+        self.get_value(k).and_then(|v| V::deserialize(v).ok())
+    }
+
+    /// Get the raw JSON [`Value`] of key `k`, without deserializing it
+    ///
+    /// Same as [`get()`], values set as workflow variables take precedence and the default
+    /// value of `k` is returned if it is not set.
+    ///
+    /// [`Value`]: https://docs.rs/serde_json/1/serde_json/enum.Value.html
+    /// [`get()`]: struct.Data.html#method.get
+    pub fn get_value<K>(&self, k: K) -> Option<&Value>
+    where
+        K: AsRef<str>,
+    {
+        let k = k.as_ref();
+        self.env_overrides
+            .get(k)
+            .or_else(|| self.stored_value(k))
+            .or_else(|| self.defaults.get(k))
+    }
+
+    // Value set for key `k`, unless it has expired.
+    fn stored_value(&self, k: &str) -> Option<&Value> {
+        self.inner.get(k).filter(|_| !self.is_expired(k))
+    }
+
+    /// Get the value of key `k` as a string
+    ///
+    /// Unlike [`get()`], numbers and booleans are also converted to a string.
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    pub fn get_str<K>(&self, k: K) -> Option<String>
+    where
+        K: AsRef<str>,
+    {
+        match self.get_value(k)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Get the value of key `k` as a [`Secret`] string, which is never displayed in logs
+    ///
+    /// Same as `get::<_, Secret<String>>(k)`.
+    ///
+    /// [`Secret`]: struct.Secret.html
+    pub fn get_secret<K>(&self, k: K) -> Option<Secret<String>>
+    where
+        K: AsRef<str>,
+    {
+        self.get(k)
+    }
+
+    /// Get the value of key `k` as a boolean
+    ///
+    /// Besides JSON booleans, values that users are likely to type when editing the data file
+    /// by hand are understood: `"true"`/`"false"`, `"yes"`/`"no"`, `"on"`/`"off"`
+    /// (case-insensitive), `"1"`/`"0"` and the numbers `1`/`0`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// let show_icons = wf_data.get_bool("show_icons").unwrap_or(true);
+    /// ```
+    pub fn get_bool<K>(&self, k: K) -> Option<bool>
+    where
+        K: AsRef<str>,
+    {
+        match self.get_value(k)? {
+            Value::Bool(b) => Some(*b),
+            Value::Number(n) => match n.as_u64() {
+                Some(0) => Some(false),
+                Some(1) => Some(true),
+                _ => None,
+            },
+            Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(true),
+                "false" | "no" | "off" | "0" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the value of key `k` as an integer
+    ///
+    /// Strings holding an integer (such as `"20"`) and floats without a fractional part are
+    /// converted too.
+    pub fn get_i64<K>(&self, k: K) -> Option<i64>
+    where
+        K: AsRef<str>,
+    {
+        match self.get_value(k)? {
+            Value::Number(n) => n.as_i64().or_else(|| {
+                n.as_f64()
+                    .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                    .map(|f| f as i64)
+            }),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the value of key `k` as a float
+    ///
+    /// Strings holding a number (such as `"0.5"`) are converted too.
+    pub fn get_f64<K>(&self, k: K) -> Option<f64>
+    where
+        K: AsRef<str>,
+    {
+        match self.get_value(k)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the value of key `k`, or set it to the value returned by `f` and persist it to disk
+    ///
+    /// `f` is only called if key `k` has not been set before. This makes first-run
+    /// initialization a single call.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// let page_size: u32 = wf_data.get_or_insert_with("page_size", || 20).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If the value of `k` can't be deserialized to type `V` (a [`TypeMismatch`], the value is
+    /// left as is), if the value returned by `f` cannot be serialized or there are file IO
+    /// issues an error is returned.
+    ///
+    /// [`TypeMismatch`]: struct.TypeMismatch.html
+    pub fn get_or_insert_with<K, V, F>(&mut self, k: K, f: F) -> Result<V>
+    where
+        K: Into<String>,
+        V: Serialize + for<'d> Deserialize<'d>,
+        F: FnOnce() -> V,
+    {
+        let k = k.into();
+        if let Some(v) = self.try_get(&k)? {
+            return Ok(v);
+        }
+        let v = f();
+        self.set(k, &v)?;
+        Ok(v)
+    }
+
+    /// Returns `true` if key `k` is set in workflow's data
+    ///
+    /// Unlike [`get()`], this tells a key that was never set apart from one whose value
+    /// cannot be deserialized to the expected type. Keys that only have a default value (see
+    /// [`with_defaults()`]) are not set.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// if !wf_data.contains_key("api_token") {
+    ///     println!("please log in first");
+    /// }
+    /// ```
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    /// [`with_defaults()`]: struct.Data.html#method.with_defaults
+    pub fn contains_key<K>(&self, k: K) -> bool
+    where
+        K: AsRef<str>,
+    {
+        self.stored_value(k.as_ref()).is_some()
+    }
+
+    /// Number of keys in workflow's data
+    pub fn len(&self) -> usize {
+        if self.expires.is_empty() {
+            self.inner.len()
+        } else {
+            self.keys().count()
+        }
+    }
+
+    /// Returns `true` if no key is set in workflow's data, e.g. when the workflow has never
+    /// been configured
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An iterator over the keys of workflow's data, in arbitrary order
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let wf_data = Data::load("accounts.json").unwrap();
+    ///
+    /// for account in wf_data.keys() {
+    ///     println!("{}", account);
+    /// }
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// An iterator over the key/value pairs of workflow's data, in arbitrary order
+    ///
+    /// Values are returned as raw JSON [`Value`]s, see [`iter_as()`] to get them as a given
+    /// type.
+    ///
+    /// [`Value`]: https://docs.rs/serde_json/1/serde_json/enum.Value.html
+    /// [`iter_as()`]: struct.Data.html#method.iter_as
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.inner
+            .iter()
+            .filter(move |(k, _)| !self.is_expired(k))
+            .map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// An iterator over the key/value pairs of workflow's data whose values can be
+    /// deserialized to type `V`, in arbitrary order
+    ///
+    /// Same as [`get()`], pairs whose value cannot be deserialized to `V` are skipped. This
+    /// makes it easy to enumerate saved accounts, profiles, ... stored under their own keys.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # #[macro_use]
+    /// # extern crate serde_derive;
+    /// use alfred_rs::data::Data;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Account {
+    ///     user: String,
+    /// }
+    ///
+    /// # fn main() {
+    /// let wf_data = Data::load("accounts.json").unwrap();
+    ///
+    /// for (name, account) in wf_data.iter_as::<Account>() {
+    ///     println!("{}: {}", name, account.user);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    pub fn iter_as<V>(&self) -> impl Iterator<Item = (&str, V)>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        self.iter()
+            .filter_map(|(k, v)| Some((k, V::deserialize(v).ok()?)))
+    }
+
+    /// Add 1 to the integer value of key `k`, and persist it to disk
+    ///
+    /// Same as [`increment_by()`] with a `delta` of 1.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`increment_by()`].
+    ///
+    /// [`increment_by()`]: struct.Data.html#method.increment_by
+    pub fn increment<K>(&mut self, k: K) -> Result<i64>
+    where
+        K: Into<String>,
+    {
+        self.increment_by(k, 1)
+    }
+
+    /// Add `delta` (which can be negative) to the integer value of key `k`, and persist it to
+    /// disk
+    ///
+    /// A key that is not set counts as 0. Returns the new value.
+    ///
+    /// Unlike getting the value and setting it back, increments of concurrent invocations of
+    /// the workflow are never lost: when the data file is written, `delta` is added to the
+    /// value in the file (while the file is locked), whatever other invocations saved since it
+    /// was loaded. This makes counters of launches, API calls, ... reliable, including with
+    /// [`defer_writes()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut stats = Data::load("stats.json").unwrap();
+    ///
+    /// let launches = stats.increment("launch_count").unwrap();
+    /// if launches == 1 {
+    ///     println!("welcome!");
+    /// }
+    /// stats.increment_by("api_quota", -1).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If `k` is reserved (see [`set()`]), the value of `k` is not an integer or would overflow,
+    /// or there are file IO issues.
+    ///
+    /// [`set()`]: struct.Data.html#method.set
+    /// [`defer_writes()`]: struct.Data.html#method.defer_writes
+    pub fn increment_by<K>(&mut self, k: K, delta: i64) -> Result<i64>
+    where
+        K: Into<String>,
+    {
+        let k = k.into();
+        check_key(&k)?;
+        let v = match self.stored_value(&k) {
+            Some(v) => v
+                .as_i64()
+                .ok_or_else(|| anyhow!("value of key `{}` is not an integer: {}", k, v))?,
+            None => 0,
+        };
+        let v = v
+            .checked_add(delta)
+            .ok_or_else(|| anyhow!("value of key `{}` would overflow", k))?;
+        // Only keys that were not set otherwise since the last write can be incremented on disk.
+        let pending = if self.changed.contains(&k) {
+            self.increments.get(&k).copied()
+        } else {
+            Some(0)
+        };
+        self.expires.remove(&k);
+        self.touch(&k);
+        if let Some(pending) = pending {
+            self.increments
+                .insert(k.clone(), pending.saturating_add(delta));
+        }
+        self.inner.insert(k.clone(), Value::from(v));
+        self.persist()?;
+        Ok(self.stored_value(&k).and_then(Value::as_i64).unwrap_or(v))
+    }
+
+    // Remember that key `k` was set, and when if modifications are tracked.
+    fn touch(&mut self, k: &str) {
+        self.changed.insert(k.to_string());
+        self.increments.remove(k);
+        if self.track_modified {
+            self.modified.insert(k.to_string(), now_millis());
+        } else {
+            self.modified.remove(k);
+        }
+    }
+
+    /// Time key `k` was last set, if modifications are tracked
+    ///
+    /// See [`track_modifications()`].
+    ///
+    /// [`track_modifications()`]: struct.Data.html#method.track_modifications
+    pub fn modified_at<K>(&self, k: K) -> Option<SystemTime>
+    where
+        K: AsRef<str>,
+    {
+        let k = k.as_ref();
+        self.stored_value(k)?;
+        let modified = self.modified.get(k)?;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_millis(*modified))
+    }
+
+    // Whether key `k` was set with a ttl that has passed.
+    fn is_expired(&self, k: &str) -> bool {
+        self.expires
+            .get(k)
+            .map_or(false, |expires_at| *expires_at <= now_millis())
+    }
+
+    /// Remove key `k` from workflow's data and persist the change to disk
+    ///
+    /// Returns the value that was stored for `k`, or `None` (without touching the disk) if the
+    /// key was not set. Useful for features such as *forget token* or *remove account*.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// if wf_data.remove("api_token").unwrap().is_some() {
+    ///     println!("token removed");
+    /// }
+    /// ```
+    /// # Errors
+    ///
+    /// If there are file IO issues an error is returned.
+    pub fn remove<K>(&mut self, k: K) -> Result<Option<Value>>
+    where
+        K: AsRef<str>,
+    {
+        let k = k.as_ref();
+        let expired = self.is_expired(k);
+        self.expires.remove(k);
+        self.changed.insert(k.to_string());
+        self.increments.remove(k);
+        self.modified.remove(k);
+        let removed = self.inner.remove(k).filter(|_| !expired);
+        if removed.is_some() {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
+
+    /// Apply a [JSON merge patch] to the data, and persist it to disk with a single write
+    ///
+    /// Each key of `patch` is set to its value, except that `null` removes the key and objects
+    /// are merged into the objects already stored, recursively. Handy to bulk-edit settings
+    /// from a configuration command, or to apply settings sent by a server.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use serde_json::json;
+    ///
+    /// let mut wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// // Sets `page_size`, removes `api_token` and only changes `theme` in `ui`
+    /// wf_data
+    ///     .apply_patch(json!({"page_size": 20, "api_token": null, "ui": {"theme": "dark"}}))
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If `patch` is not a JSON object, sets a reserved key (see [`set()`]) or there are file IO
+    /// issues.
+    ///
+    /// [JSON merge patch]: https://tools.ietf.org/html/rfc7396
+    /// [`set()`]: struct.Data.html#method.set
+    pub fn apply_patch(&mut self, patch: Value) -> Result<()> {
+        let patch = match patch {
+            Value::Object(patch) => patch,
+            _ => bail!("patch must be a JSON object"),
+        };
+        for k in patch.keys() {
+            check_key(k)?;
+        }
+        for (k, v) in patch {
+            if self.is_expired(&k) {
+                self.inner.remove(&k);
+            }
+            self.expires.remove(&k);
+            self.touch(&k);
+            if v.is_null() {
+                self.inner.remove(&k);
+                self.modified.remove(&k);
+            } else {
+                merge_patch(self.inner.entry(k).or_insert(Value::Null), v);
+            }
+        }
+        self.persist()
+    }
+
+    /// Merge the key/value pairs of `other` into the data, and persist it to disk with a
+    /// single write
+    ///
+    /// Same as [`apply_patch()`] with the values of `other` as the patch: objects are merged
+    /// recursively, and keys whose value is `null` in `other` are removed. Useful to apply
+    /// defaults fetched from a server over user's settings, or the other way round.
+    ///
+    /// # Errors
+    ///
+    /// If there are file IO issues an error is returned.
+    ///
+    /// [`apply_patch()`]: struct.Data.html#method.apply_patch
+    pub fn merge(&mut self, other: &Data) -> Result<()> {
+        let patch: Map<String, Value> = other
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        self.apply_patch(Value::Object(patch))
+    }
+
+    /// Make several changes that are persisted to disk all together, or not at all
+    ///
+    /// `f` makes the changes through a [`Transaction`]. If it returns `Ok`, all changes are
+    /// written with a single write of the data file. If it returns an error (or panics), or
+    /// the data file can't be written, none of the changes is kept and the data is left as it
+    /// was. This protects values that only make sense together, such as a token and its
+    /// expiry time, from being saved halfway.
+    ///
+    /// With [`defer_writes()`] on, the changes are kept (but not written) once `f` succeeds.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut wf_data = Data::load("settings.json").unwrap();
+    ///
+    /// wf_data
+    ///     .transaction(|txn| {
+    ///         txn.set("access_token", &"b3f1...")?;
+    ///         txn.set("refresh_token", &"9d2c...")?;
+    ///         txn.set("token_expires_at", &1_700_000_000)?;
+    ///         txn.remove("login_error");
+    ///         Ok(())
+    ///     })
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// The error returned by `f`, or if there are file IO issues.
+    ///
+    /// [`Transaction`]: struct.Transaction.html
+    /// [`defer_writes()`]: struct.Data.html#method.defer_writes
+    pub fn transaction<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Transaction<'_>) -> Result<T>,
+    {
+        let mut txn = Transaction::new(self);
+        let result = f(&mut txn)?;
+        txn.commit()?;
+        Ok(result)
+    }
+
+    /// Let users override values of keys with workflow variables, on by default
+    ///
+    /// A workflow variable named `wf_setting_<key>` (set in Alfred's *Configure Workflow*
+    /// sheet) is returned by [`get()`] and the other getters instead of the value of `<key>`.
+    /// This lets users tweak the behavior of a workflow that has no settings UI of its own.
+    /// Variables holding JSON (such as `20`, `true` or `["a", "b"]`) are read as such, others
+    /// as strings. The data file is left as it is, and [`contains_key()`], [`iter()`], ...
+    /// only report the keys that are set in it.
+    ///
+    /// Data that users have no business overriding, such as tokens or caches, can turn
+    /// overrides off.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// // With variable `wf_setting_page_size` set to `50`
+    /// let settings = Data::load("settings.json").unwrap();
+    /// assert_eq!(Some(50), settings.get::<_, u32>("page_size"));
+    ///
+    /// let mut tokens = Data::load("tokens.json").unwrap();
+    /// tokens.allow_env_overrides(false);
+    /// ```
+    ///
+    /// [`get()`]: struct.Data.html#method.get
+    /// [`contains_key()`]: struct.Data.html#method.contains_key
+    /// [`iter()`]: struct.Data.html#method.iter
+    pub fn allow_env_overrides(&mut self, allow: bool) {
+        self.env_overrides = if allow {
+            env_overrides()
+        } else {
+            HashMap::new()
+        };
+    }
+
+    /// Record the time keys are set, off by default
+    ///
+    /// Once on, every key set through this `Data` remembers when it was last set, which
+    /// [`modified_at()`] returns. Times are saved in the data file (under
+    /// `alfred_rs_modified`, which is why setting that key is an error), so they are kept
+    /// across invocations of the workflow. This helps with syncing only the settings changed
+    /// since the last sync, or with finding out which invocation changed a value. Keys set
+    /// while tracking is off lose their time.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::time::SystemTime;
+    ///
+    /// let mut settings = Data::load("settings.json").unwrap();
+    /// settings.track_modifications(true);
+    /// settings.set("page_size", &20).unwrap();
+    ///
+    /// let last_sync = SystemTime::UNIX_EPOCH;
+    /// let to_sync: Vec<&str> = settings
+    ///     .keys()
+    ///     .filter(|k| settings.modified_at(k).map_or(false, |t| t > last_sync))
+    ///     .collect();
+    /// ```
+    ///
+    /// [`modified_at()`]: struct.Data.html#method.modified_at
+    pub fn track_modifications(&mut self, track: bool) {
+        self.track_modified = track;
+    }
+
+    /// Only write changes to disk when [`save()`] is called (or the `Data` is dropped)
+    ///
+    /// By default every change ([`set()`], [`remove()`], ...) rewrites the data file right
+    /// away. Workflows that update values (such as counters or timestamps) many times per
+    /// invocation can defer the writes and save all changes once. Pending changes are also
+    /// saved when the `Data` is dropped, but errors can only be reported by calling [`save()`].
+    ///
+    /// Turning deferred writes off doesn't save pending changes, they are written by the next
+    /// change or call to [`save()`] but no longer on drop.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut wf_data = Data::load("stats.json").unwrap();
+    /// wf_data.defer_writes(true);
+    ///
+    /// for query in &["rust", "alfred", "workflow"] {
+    ///     let count: u64 = wf_data.get(query).unwrap_or(0);
+    ///     wf_data.set(*query, &(count + 1)).unwrap();
+    /// }
+    /// wf_data.save().unwrap();
+    /// ```
+    ///
+    /// [`save()`]: struct.Data.html#method.save
+    /// [`set()`]: struct.Data.html#method.set
+    /// [`remove()`]: struct.Data.html#method.remove
+    pub fn defer_writes(&mut self, defer: bool) {
+        self.deferred = defer;
+    }
+
+    /// Write the JSON data file indented, with keys in alphabetical order
+    ///
+    /// Meant for settings files, so that users can read or edit them when troubleshooting and
+    /// changes are easy to diff. Caches are better left compact. Files in another [`Format`]
+    /// are not affected.
+    ///
+    /// The file is rewritten this way on the next change (or [`save()`] of pending changes).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut settings = Data::load("settings.json").unwrap();
+    /// settings.pretty_print(true);
+    /// settings.set("page_size", &20).unwrap();
+    /// ```
+    ///
+    /// [`Format`]: format/trait.Format.html
+    /// [`save()`]: struct.Data.html#method.save
+    pub fn pretty_print(&mut self, pretty: bool) {
+        self.pretty = pretty;
+    }
+
+    /// Compress the data file when it's written, or stop compressing it with `None`
+    ///
+    /// The data file can be read whether it's compressed or not, see [`Compression`].
+    ///
+    /// [`Compression`]: enum.Compression.html
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn set_compression(&mut self, compression: Option<Compression>) {
+        self.compression = compression;
+    }
+
+    /// Write pending changes to disk, see [`defer_writes()`]
+    ///
+    /// Nothing is written if there are no pending changes.
+    ///
+    /// The data file is locked while it's written, and changes that other invocations of the
+    /// workflow (such as a background job) saved since it was loaded are kept: only the keys
+    /// set or removed through this `Data` are updated. Once saved, this `Data` has the values
+    /// of those other changes too.
+    ///
+    /// # Errors
+    ///
+    /// If there are file IO issues an error is returned and the changes stay pending.
+    ///
+    /// [`defer_writes()`]: struct.Data.html#method.defer_writes
+    pub fn save(&mut self) -> Result<()> {
+        if self.dirty {
+            let _lock = Self::write_lock(&self.file_name)?;
+            // What other invocations saved is merged, it must not be missed because the file
+            // looks unchanged.
+            forget_read(&self.file_name);
+            match Self::read_entries(&self.file_name, self.format.as_deref()) {
+                Ok(entries) => {
+                    // The file is good, keep it as backup before it's replaced.
+                    if self.versions > 0 {
+                        if let Err(e) = self.keep_version() {
+                            debug!("couldn't back up data file: {}", e);
+                        }
+                    }
+                    if !self.cleared {
+                        self.apply_changes_to(entries);
+                    }
+                }
+                Err(e) if is_corrupt(&e) => {
+                    self.corrupt_file = Some(Self::set_aside_corrupt_file(&self.file_name)?);
+                }
+                Err(e) if is_undecryptable(&e) => return Err(e),
+                Err(_) => {}
+            }
+            if self.expires.is_empty()
+                && self.modified.is_empty()
+                && self.format.is_none()
+                && !self.pretty
+            {
+                self.write_with_format(&self.inner)?;
+            } else {
+                // Sorted, so that keys don't move around in files that users may edit.
+                self.with_sorted_entries(|data| self.write_with_format(data))?;
+            }
+            self.version = FileVersion::of(&self.file_name);
+            self.dirty = false;
+            self.changed.clear();
+            self.increments.clear();
+            self.cleared = false;
+        }
+        Ok(())
+    }
+
+    // Call `f` with the values that haven't expired sorted by key, along with the expiry
+    // times of expiring ones under `EXPIRES_KEY` and the times of last change under
+    // `MODIFIED_KEY`, as they are saved to data files.
+    fn with_sorted_entries<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&BTreeMap<&str, &Value>) -> Result<()>,
+    {
+        let now = now_millis();
+        let mut data: BTreeMap<&str, &Value> = BTreeMap::new();
+        let mut expires: BTreeMap<&str, u64> = BTreeMap::new();
+        let mut modified: BTreeMap<&str, u64> = BTreeMap::new();
+        for (k, v) in &self.inner {
+            match self.expires.get(k) {
+                Some(expires_at) if *expires_at <= now => continue,
+                Some(expires_at) => {
+                    expires.insert(k, *expires_at);
+                }
+                None => {}
+            }
+            if let Some(modified_at) = self.modified.get(k) {
+                modified.insert(k, *modified_at);
+            }
+            data.insert(k, v);
+        }
+        let has_expiring_keys = !expires.is_empty();
+        let expires = to_value(expires)?;
+        if has_expiring_keys {
+            data.insert(EXPIRES_KEY, &expires);
+        }
+        let has_modified_keys = !modified.is_empty();
+        let modified = to_value(modified)?;
+        if has_modified_keys {
+            data.insert(MODIFIED_KEY, &modified);
+        }
+        f(&data)
+    }
+
+    /// Export all key/value pairs to file `p`, to back up or move workflow's settings
+    ///
+    /// `p` can be anywhere (e.g. in user's Downloads folder), it is not relative to the data
+    /// dir. The file is indented JSON with keys in alphabetical order, whatever the [`Format`]
+    /// of the data file, and can be restored with [`import()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let settings = Data::load("settings.json").unwrap();
+    /// settings
+    ///     .export("/Users/me/Desktop/my-workflow-settings.json")
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If the file can't be written.
+    ///
+    /// [`Format`]: format/trait.Format.html
+    /// [`import()`]: struct.Data.html#method.import
+    pub fn export<P: AsRef<Path>>(&self, p: P) -> Result<()> {
+        let p = p.as_ref();
+        debug!("exporting data to: {:?}", p);
+        self.with_sorted_entries(|data| {
+            Self::write_atomically(p, |buf_writer| {
+                Ok(serde_json::to_writer_pretty(buf_writer, data)?)
+            })
+        })
+    }
+
+    /// Replace all key/value pairs with the ones exported to file `p` by [`export()`], and
+    /// save them
+    ///
+    /// The file is checked before anything is replaced: if it can't be read or isn't a JSON
+    /// object, an error is returned and the data is left as it is. When versions are kept, the
+    /// data file being replaced is kept as a backup, see [`restore_backup()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let mut settings = Data::load("settings.json").unwrap();
+    /// settings
+    ///     .import("/Users/me/Desktop/my-workflow-settings.json")
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If `p` is not an exported file, or the data file can't be written.
+    ///
+    /// [`export()`]: struct.Data.html#method.export
+    /// [`restore_backup()`]: struct.Data.html#method.restore_backup
+    pub fn import<P: AsRef<Path>>(&mut self, p: P) -> Result<()> {
+        let p = p.as_ref();
+        debug!("importing data from: {:?}", p);
+        let entries = Self::read_entries(p, None)
+            .map_err(|e| anyhow!("invalid settings file {:?}: {}", p, e))?;
+        self.inner = entries.inner;
+        self.expires = entries.expires;
+        self.modified = entries.modified;
+        self.changed.clear();
+        self.increments.clear();
+        self.cleared = true;
+        self.persist()
+    }
+
+    // Apply the keys changed through this `Data` to the `entries` of the data file on disk,
+    // which other invocations may have changed since it was loaded.
+    fn apply_changes_to(&mut self, entries: Entries) {
+        let Entries {
+            mut inner,
+            mut expires,
+            mut modified,
+        } = entries;
+        for k in &self.changed {
+            match (self.increments.get(k), self.inner.remove(k)) {
+                // Increment the value on disk, so that no increment of other invocations is lost.
+                (Some(delta), _) => {
+                    let v = inner.get(k).and_then(Value::as_i64).unwrap_or(0);
+                    inner.insert(k.clone(), Value::from(v.saturating_add(*delta)))
+                }
+                (None, Some(v)) => inner.insert(k.clone(), v),
+                (None, None) => inner.remove(k),
+            };
+            match self.expires.get(k) {
+                Some(expires_at) => expires.insert(k.clone(), *expires_at),
+                None => expires.remove(k),
+            };
+            match self.modified.get(k) {
+                Some(modified_at) => modified.insert(k.clone(), *modified_at),
+                None => modified.remove(k),
+            };
+        }
+        self.inner = inner;
+        self.expires = expires;
+        self.modified = modified;
+    }
+
+    // Write `data` to the data file, in data's format.
+    fn write_with_format<V: Serialize>(&self, data: &V) -> Result<()> {
+        use std::io::Write;
+        let bytes = match &self.format {
+            None if self.pretty && !self.is_compressed() => {
+                return Self::write_atomically(&self.file_name, |buf_writer| {
+                    Ok(serde_json::to_writer_pretty(buf_writer, data)?)
+                });
+            }
+            None if !self.is_compressed() => {
+                return Self::write_data_to_disk(&self.file_name, data)
+            }
+            None if self.pretty => serde_json::to_vec_pretty(data)?,
+            None => serde_json::to_vec(data)?,
+            Some(format) => {
+                let value = to_value(data)?;
+                match Self::read_bytes_from_disk(&self.file_name) {
+                    Ok(previous) => format.serialize_over(&value, &previous)?,
+                    Err(_) => format.serialize(&value)?,
+                }
+            }
+        };
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let bytes = match self.compression {
+            Some(compression) => compression.compress(&bytes)?,
+            None => bytes,
+        };
+        Self::write_atomically(&self.file_name, |buf_writer| {
+            Ok(buf_writer.write_all(&bytes)?)
+        })
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn is_compressed(&self) -> bool {
+        self.compression.is_some()
+    }
+
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    fn is_compressed(&self) -> bool {
+        false
+    }
+
+    fn write_with_format_to_disk<V: Serialize>(
+        p: PathBuf,
+        data: &V,
+        format: &dyn Format,
+    ) -> Result<()> {
+        use std::io::Write;
+        let bytes = format.serialize(&to_value(data)?)?;
+        Self::write_atomically(p, |buf_writer| Ok(buf_writer.write_all(&bytes)?))
+    }
+
+    // Write the data to disk, or just remember to do so when writes are deferred.
+    fn persist(&mut self) -> Result<()> {
+        self.dirty = true;
+        if self.deferred {
+            return Ok(());
+        }
+        let saved = self.save();
+        // The caller got the error, the write isn't retried when the `Data` is dropped.
+        if saved.is_err() {
+            self.dirty = false;
+        }
+        saved
+    }
+
+    /// Clear all key-value pairs. Does not affect data on disk.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.expires.clear();
+        self.modified.clear();
+        self.changed.clear();
+        self.increments.clear();
+        self.cleared = true;
+    }
+
+    /// Function to save (temporary) `data` to file named `p` in workflow's cache dir
+    ///
+    /// This function is provided so that workflow authors can temporarily save information
+    /// to workflow's cache dir. The saved data is considered to be irrelevant to workflow's
+    /// actual data (for which you should use [`set`] and [`get`])
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// Data::save_to_file("cached_tags.dat", &vec!["rust", "alfred"]).unwrap();
+    /// ```
+    /// ## Note
+    /// `p` is relative to workflow's cache directory. It can include sub-directories (e.g.
+    /// `queries/rust.json`) to organize cached files, they are created as needed.
+    /// # Errors
+    /// File IO related issues as well as serializing problems will cause an error to be returned.
+    /// `p` can't be out of the cache directory (an absolute path elsewhere, or one that goes up
+    /// with `..`).
+    ///
+    /// [`set`]: struct.Data.html#method.set
+    /// [`get`]: struct.Data.html#method.get
+    pub fn save_to_file<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving to: {}", p.to_str().expect(""));
+        Self::write_data_to_disk(p, data)
+    }
+
+    /// Same as [`save_to_file()`] but the file is written in the given [`Format`]
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use alfred_rs::data::{format, Data};
+    ///
+    /// // Needs the `yaml` feature
+    /// Data::save_to_file_with_format("tags.yaml", &vec!["rust", "alfred"], format::Yaml)
+    ///     .unwrap();
+    /// ```
+    /// # Errors
+    /// Same as [`save_to_file()`].
+    ///
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    /// [`Format`]: format/trait.Format.html
+    pub fn save_to_file_with_format<P, V, F>(p: P, data: &V, format: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+        F: Format,
+    {
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving to: {:?} as {:?}", p, format);
+        Self::write_with_format_to_disk(p, data, &format)
+    }
+
+    /// Same as [`load_from_file()`] but the file is read in the given [`Format`]
+    ///
+    /// [`load_from_file()`]: struct.Data.html#method.load_from_file
+    /// [`Format`]: format/trait.Format.html
+    pub fn load_from_file_with_format<P, V, F>(p: P, format: F) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+        F: Format,
+    {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        debug!("loading from: {:?} as {:?}", p, format);
+        Self::read_with_format(&p, &format).ok()
+    }
+
+    /// Same as [`save_to_file()`] but the file is compressed
+    ///
+    /// All functions that read files recognize compressed files, such as
+    /// [`load_from_file()`] and [`cached()`].
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use alfred_rs::data::{Compression, Data};
+    ///
+    /// // Needs the `gzip` feature
+    /// # let all_repos: Vec<String> = vec![];
+    /// Data::save_to_file_compressed("repos.json.gz", &all_repos, Compression::Gzip).unwrap();
+    /// let repos: Vec<String> = Data::load_from_file("repos.json.gz").unwrap();
+    /// ```
+    /// # Errors
+    /// Same as [`save_to_file()`].
+    ///
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    /// [`load_from_file()`]: struct.Data.html#method.load_from_file
+    /// [`cached()`]: struct.Data.html#method.cached
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn save_to_file_compressed<P, V>(p: P, data: &V, compression: Compression) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        use std::io::Write;
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving to: {:?} with {:?}", p, compression);
+        let bytes = compression.compress(&serde_json::to_vec(data)?)?;
+        Self::write_atomically(p, |buf_writer| Ok(buf_writer.write_all(&bytes)?))
+    }
+
+    /// Same as [`save_to_file()`] but a checksum of the data is saved with it
+    ///
+    /// The checksum is verified by [`load_from_file_with_checksum()`], so that a cache file
+    /// that was cut short or damaged on disk is never loaded. Files saved this way start with a
+    /// line holding the checksum, they can only be loaded with
+    /// [`load_from_file_with_checksum()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// # let items: Vec<String> = vec![];
+    /// Data::save_to_file_with_checksum("items.json", &items).unwrap();
+    /// let items: Vec<String> = Data::load_from_file_with_checksum("items.json").unwrap_or_default();
+    /// ```
+    /// # Errors
+    /// Same as [`save_to_file()`].
+    ///
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    /// [`load_from_file_with_checksum()`]: struct.Data.html#method.load_from_file_with_checksum
+    pub fn save_to_file_with_checksum<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        use std::io::Write;
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving to: {:?} with checksum", p);
+        let bytes = serde_json::to_vec(data)?;
+        Self::write_atomically(p, |buf_writer| {
+            writeln!(buf_writer, "{}{:016x}", CHECKSUM_HEADER, fnv1a64(&bytes))?;
+            Ok(buf_writer.write_all(&bytes)?)
+        })
+    }
+
+    /// Load data saved by [`save_to_file_with_checksum()`] from file named `p` in workflow's
+    /// cache dir
+    ///
+    /// `None` is returned if the file doesn't exist, doesn't match its checksum (or has none),
+    /// or cannot be deserialized to type `V`.
+    ///
+    /// [`save_to_file_with_checksum()`]: struct.Data.html#method.save_to_file_with_checksum
+    pub fn load_from_file_with_checksum<P, V>(p: P) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        debug!("loading from: {:?} with checksum", p);
+        let bytes = Self::read_bytes_from_disk(&p).ok()?;
+        let newline = bytes.iter().position(|b| *b == b'\n')?;
+        let (header, data) = (&bytes[..newline], &bytes[newline + 1..]);
+        let checksum = std::str::from_utf8(header)
+            .ok()?
+            .strip_prefix(CHECKSUM_HEADER)
+            .and_then(|checksum| u64::from_str_radix(checksum, 16).ok());
+        if checksum != Some(fnv1a64(data)) {
+            debug!("checksum of {:?} doesn't match its data", p);
+            return None;
+        }
+        serde_json::from_slice(data).ok()
+    }
+
+    /// Same as [`save_to_file()`] but the file is saved in workflow's data dir
+    ///
+    /// Alfred (or the user) may clear the cache dir at any time. Use this for data that must
+    /// survive that but doesn't fit in key/value pairs, such as a list of bookmarks or a
+    /// downloaded index. `p` is relative to the data dir.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// Data::save_to_data_file("bookmarks.json", &vec!["https://www.alfredapp.com"]).unwrap();
+    /// ```
+    /// # Errors
+    /// File IO related issues as well as serializing problems will cause an error to be returned.
+    ///
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    pub fn save_to_data_file<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        let p = Self::data_file_path(p.as_ref())?;
+        debug!("saving to: {:?}", p);
+        Self::write_data_to_disk(p, data)
+    }
+
+    /// Same as [`load_from_file()`] but the file is looked up in workflow's data dir
+    ///
+    /// Loads data saved with [`save_to_data_file()`].
+    ///
+    /// [`load_from_file()`]: struct.Data.html#method.load_from_file
+    /// [`save_to_data_file()`]: struct.Data.html#method.save_to_data_file
+    pub fn load_from_data_file<P, V>(p: P) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::data_file_path(p.as_ref()).ok()?;
+        debug!("loading from: {:?}", p);
+        Self::read_data_from_disk(&p).ok()
+    }
+
+    /// Append `entry` as a line to the log file named `p` in workflow's data dir
+    ///
+    /// Logs are [JSON Lines] files that only ever grow, such as usage history or events.
+    /// Unlike [`set()`] or [`save_to_data_file()`], appending an entry doesn't read nor rewrite
+    /// what was appended before, so it stays fast however long the log gets. Entries are read
+    /// back with [`read_log()`].
+    ///
+    /// The file (and its parent directories) is created if needed. `p` is relative to the data
+    /// dir.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # #[macro_use]
+    /// # extern crate serde_derive;
+    /// use alfred_rs::data::Data;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Visit {
+    ///     url: String,
+    ///     at: i64,
+    /// }
+    ///
+    /// # fn main() {
+    /// let visit = Visit {
+    ///     url: "https://www.alfredapp.com".to_string(),
+    ///     at: 1_700_000_000,
+    /// };
+    /// Data::append("history.jsonl", &visit).unwrap();
+    /// # }
+    /// ```
+    /// # Errors
+    /// File IO related issues as well as serializing problems will cause an error to be returned.
+    ///
+    /// [JSON Lines]: https://jsonlines.org
+    /// [`set()`]: struct.Data.html#method.set
+    /// [`save_to_data_file()`]: struct.Data.html#method.save_to_data_file
+    /// [`read_log()`]: struct.Data.html#method.read_log
+    pub fn append<P, V>(p: P, entry: &V) -> Result<()>
+    where
+        P: AsRef<Path>,
+        V: Serialize,
+    {
+        use std::io::Write;
+        let p = Self::data_file_path(p.as_ref())?;
+        debug!("appending to: {:?}", p);
+        // Write the whole line at once so that entries of concurrent runs don't interleave.
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if let Some(dir) = p.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(p)?;
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Read the entries appended with [`append()`] to the log file named `p`, oldest first
+    ///
+    /// Entries are read lazily as the returned [`LogReader`] is iterated. A log that doesn't
+    /// exist yet has no entries.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut visits = HashMap::new();
+    /// for entry in Data::read_log::<_, serde_json::Value>("history.jsonl").unwrap() {
+    ///     if let Some(url) = entry["url"].as_str() {
+    ///         *visits.entry(url.to_string()).or_insert(0) += 1;
+    ///     }
+    /// }
+    /// ```
+    /// # Errors
+    ///
+    /// If the data dir is not set or the log exists but can't be opened.
+    ///
+    /// [`append()`]: struct.Data.html#method.append
+    /// [`LogReader`]: struct.LogReader.html
+    pub fn read_log<P, V>(p: P) -> Result<LogReader<V>>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::data_file_path(p.as_ref())?;
+        debug!("reading log: {:?}", p);
+        match File::open(p) {
+            Ok(file) => Ok(LogReader::new(Some(file))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(LogReader::new(None)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Same as `save_to_file` but `p` is the full path of the file, which can be in any folder.
+    pub(crate) fn save_to_path<V>(p: &Path, data: &V) -> Result<()>
+    where
+        V: Serialize,
+    {
+        debug!("saving to: {:?}", p);
+        Self::write_data_to_disk(p, data)
+    }
+
+    // Same as `load_from_file` but `p` is the full path of the file.
+    pub(crate) fn load_from_path<V>(p: &Path) -> Option<V>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        debug!("loading from: {:?}", p);
+        Self::read_data_from_disk(p).ok()
+    }
+
+    fn write_data_to_disk<P, V>(p: P, data: &V) -> Result<()>
+    where
+        P: AsRef<Path> + std::fmt::Debug,
+        V: Serialize,
+    {
+        Self::write_atomically(p, |buf_writer| Ok(serde_json::to_writer(buf_writer, data)?))
+    }
+
+    // Write to a temporary file with `write` then rename it to `p`, so that readers never see
+    // a partially written file.
+    fn write_atomically<P, F>(p: P, write: F) -> Result<()>
+    where
+        P: AsRef<Path> + std::fmt::Debug,
+        F: FnOnce(&mut BufWriter<File>) -> Result<()>,
+    {
+        use std::fs;
+        use std::io::Write;
+        use tempfile::Builder;
+        // The temporary file is created next to `p`, a rename can't move files across devices.
+        let dir = match p.as_ref().parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        // Files can be in sub-directories that don't exist yet.
+        fs::create_dir_all(dir)?;
+        let named_tempfile = Builder::new()
+            .prefix(TEMP_FN_PREFIX)
+            .suffix(".json")
+            .rand_bytes(5)
+            .tempfile_in(dir)?;
+
+        let mut buf_writer = BufWriter::with_capacity(0x1000, named_tempfile.reopen()?);
+        write(&mut buf_writer)?;
+        buf_writer.flush()?;
+        drop(buf_writer);
+
+        // Rename over to main file name
+        forget_read(p.as_ref());
+        named_tempfile.persist(p).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    // Content of file `p` read with `read`, or as it was read before if the file hasn't changed
+    // since.
+    fn read_cached<F>(p: &Path, read: F) -> Result<Value>
+    where
+        F: FnOnce(&Path) -> Result<Value>,
+    {
+        let version = match FileVersion::of(p) {
+            Some(version) => version,
+            None => return read(p),
+        };
+        if let Some((cached_version, value)) =
+            READ_CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(p)
+        {
+            if *cached_version == version {
+                debug!("reading from memory: {:?}", p);
+                return Ok(value.clone());
+            }
+        }
+        let value = read(p)?;
+        READ_CACHE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(p.to_path_buf(), (version, value.clone()));
+        Ok(value)
+    }
+
+    /// Function to load some (temporary) data from file named `p` in workflow's cache dir
+    ///
+    /// This function is provided so that workflow authors can retrieve temporarily information
+    /// saved to workflow's cache dir. The saved data is considered to be irrelevant to workflow's
+    /// actual data (for which you should use [`set`] and [`get`])
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let cached_tags: Vec<String> = Data::load_from_file("cached_tags.dat").unwrap();
+    /// ```
+    ///
+    /// ## Note
+    /// `p` is relative to workflow's cache directory, see [`save_to_file`].
+    ///
+    /// [`set`]: struct.Data.html#method.set
+    /// [`get`]: struct.Data.html#method.get
+    /// [`save_to_file`]: struct.Data.html#method.save_to_file
+    pub fn load_from_file<P, V>(p: P) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        debug!("loading from: {}", p.to_str().expect(""));
+        Self::read_data_from_disk(&p).ok()
+    }
+
+    /// Save the items of `items` as a JSON array to file named `p` in workflow's cache dir
+    ///
+    /// Items are serialized and written one at a time, so very large collections (e.g.
+    /// 100k+ entries of an index) don't have to be collected in a `Vec` first. Each item is
+    /// written on its own line, which lets [`load_iter_from_file()`] read them back one by one.
+    /// The file can also be loaded as a whole with [`load_from_file()`].
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let ids = (0..100_000).map(|i| format!("id-{}", i));
+    /// Data::save_iter_to_file("ids.json", ids).unwrap();
+    /// ```
+    /// # Errors
+    /// Same as [`save_to_file()`]. Nothing is written if an item cannot be serialized.
+    ///
+    /// [`load_iter_from_file()`]: struct.Data.html#method.load_iter_from_file
+    /// [`load_from_file()`]: struct.Data.html#method.load_from_file
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    pub fn save_iter_to_file<P, I, V>(p: P, items: I) -> Result<()>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = V>,
+        V: Serialize,
+    {
+        use std::io::Write;
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving items to: {:?}", p);
+        Self::write_atomically(p, |buf_writer| {
+            buf_writer.write_all(b"[")?;
+            for (i, item) in items.into_iter().enumerate() {
+                buf_writer.write_all(if i == 0 { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut *buf_writer, &item)?;
+            }
+            buf_writer.write_all(b"\n]\n")?;
+            Ok(())
+        })
+    }
+
+    /// Read the items saved by [`save_iter_to_file()`] to file named `p` in workflow's cache
+    /// dir, one at a time
+    ///
+    /// Items are deserialized lazily as the returned [`ItemReader`] is iterated. `None` is
+    /// returned if the file doesn't exist or can't be read.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// let matches: Vec<String> = Data::load_iter_from_file("ids.json")
+    ///     .into_iter()
+    ///     .flatten()
+    ///     .filter(|id: &String| id.ends_with("42"))
+    ///     .take(20)
+    ///     .collect();
+    /// ```
+    ///
+    /// [`save_iter_to_file()`]: struct.Data.html#method.save_iter_to_file
+    /// [`ItemReader`]: struct.ItemReader.html
+    pub fn load_iter_from_file<P, V>(p: P) -> Option<ItemReader<V>>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        debug!("loading items from: {:?}", p);
+        let reader = Self::open_for_reading(&p).ok()?;
+        Some(ItemReader::new(Box::new(BufReader::new(reader))))
+    }
+
+    /// Same as [`load_from_file()`] but only if the file was saved less than `max_age` ago
+    ///
+    /// The age of the file is based on its last modification time, so data saved with
+    /// [`save_to_file()`] is fresh again every time it is saved. This makes caching results
+    /// of slow queries (such as API calls) simple:
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::time::Duration;
+    ///
+    /// # fn fetch_tags() -> Vec<String> { vec![] }
+    /// let tags: Vec<String> =
+    ///     match Data::load_from_file_if_fresh("tags.dat", Duration::from_secs(60 * 60)) {
+    ///         Some(tags) => tags,
+    ///         None => {
+    ///             let tags = fetch_tags();
+    ///             Data::save_to_file("tags.dat", &tags).unwrap();
+    ///             tags
+    ///         }
+    ///     };
+    /// ```
+    ///
+    /// `None` is returned if the file is older than `max_age`, in addition to the cases in which
+    /// [`load_from_file()`] returns `None`.
+    ///
+    /// [`load_from_file()`]: struct.Data.html#method.load_from_file
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    pub fn load_from_file_if_fresh<P, V>(p: P, max_age: Duration) -> Option<V>
+    where
+        P: AsRef<Path>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        let age = Self::cache_age(&p)?;
+        if age >= max_age {
+            debug!("stale cache file: {:?}, age: {:?}", p, age);
+            return None;
+        }
+        debug!("loading from: {:?}", p);
+        Self::read_data_from_disk(&p).ok()
+    }
+
+    /// Get data cached in file named `p` in workflow's cache dir, or compute it with `f` and
+    /// cache it
+    ///
+    /// If the file was saved less than `ttl` ago (see [`load_from_file_if_fresh()`]) its data is
+    /// returned. Otherwise `f` is called and the data it returns is saved to the file for
+    /// later calls. Failing to save it is only logged: the data is returned regardless.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::time::Duration;
+    ///
+    /// # fn fetch_repos() -> anyhow::Result<Vec<String>> { Ok(vec![]) }
+    /// let repos: Vec<String> =
+    ///     Data::cached("repos.json", Duration::from_secs(10 * 60), || fetch_repos()).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// The error returned by `f`, if the data was not cached or is stale.
+    ///
+    /// [`load_from_file_if_fresh()`]: struct.Data.html#method.load_from_file_if_fresh
+    pub fn cached<P, V, F, E>(p: P, ttl: Duration, f: F) -> Result<V>
+    where
+        P: AsRef<Path>,
+        V: Serialize + for<'d> Deserialize<'d>,
+        F: FnOnce() -> std::result::Result<V, E>,
+        E: Into<anyhow::Error>,
+    {
+        if let Some(data) = Self::load_from_file_if_fresh(p.as_ref(), ttl) {
+            return Ok(data);
+        }
+        let data = f().map_err(Into::into)?;
+        if let Err(e) = Self::save_to_file(p.as_ref(), &data) {
+            debug!("couldn't cache data to {:?}: {}", p.as_ref(), e);
+        }
+        Ok(data)
+    }
+
+    /// Save raw `bytes` to file named `p` in workflow's cache dir
+    ///
+    /// Unlike [`save_to_file()`], the bytes are written as they are, which suits payloads that
+    /// aren't JSON such as downloaded images, databases or archives. Same as
+    /// [`save_to_file()`], `p` is relative to the cache dir.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// # let png: Vec<u8> = vec![];
+    /// Data::save_blob("avatar.png", &png).unwrap();
+    /// // Alfred can show it as the icon of an item
+    /// let icon = Data::blob_path("avatar.png").unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// File IO related issues will cause an error to be returned.
+    ///
+    /// [`save_to_file()`]: struct.Data.html#method.save_to_file
+    pub fn save_blob<P: AsRef<Path>>(p: P, bytes: &[u8]) -> Result<()> {
+        use std::io::Write;
+        let p = Self::cache_file_path(p.as_ref())?;
+        debug!("saving blob to: {:?}", p);
+        Self::write_atomically(p, |buf_writer| Ok(buf_writer.write_all(bytes)?))
+    }
+
+    /// Load the bytes saved by [`save_blob()`] to file named `p` in workflow's cache dir
+    ///
+    /// `None` is returned if the file doesn't exist or can't be read.
+    ///
+    /// [`save_blob()`]: struct.Data.html#method.save_blob
+    pub fn load_blob<P: AsRef<Path>>(p: P) -> Option<Vec<u8>> {
+        let p = Self::cache_file_path(p.as_ref()).ok()?;
+        debug!("loading blob from: {:?}", p);
+        std::fs::read(p).ok()
+    }
+
+    /// Full path of file named `p` in workflow's cache dir, whether it exists or not
+    ///
+    /// Useful to hand files saved by [`save_blob()`] to other tools or to Alfred (e.g. as the
+    /// icon of an item), or to let a library (such as a database) manage the file itself.
+    ///
+    /// # Errors
+    ///
+    /// If `p` has no file name or the cache dir is not set.
+    ///
+    /// [`save_blob()`]: struct.Data.html#method.save_blob
+    pub fn blob_path<P: AsRef<Path>>(p: P) -> Result<PathBuf> {
+        Self::cache_file_path(p.as_ref())
+    }
+
+    /// Time since file named `p` in workflow's cache dir was last saved
+    ///
+    /// Handy to tell users how old the results they see are, or to refresh a cache in the
+    /// background before it goes stale. A modification time in the future (e.g. after the
+    /// clock was changed) gives an age of zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// if let Some(age) = Data::cache_age("tags.dat") {
+    ///     println!("refreshed {} min ago", age.as_secs() / 60);
+    /// }
+    /// ```
+    ///
+    /// `None` is returned if the file doesn't exist or its metadata can't be read.
+    pub fn cache_age<P: AsRef<Path>>(p: P) -> Option<Duration> {
+        let modified = Self::cache_modified(p)?;
+        Some(
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Last modification time of file named `p` in workflow's cache dir
+    ///
+    /// `None` is returned if the file doesn't exist or its metadata can't be read.
+    pub fn cache_modified<P: AsRef<Path>>(p: P) -> Option<SystemTime> {
+        Self::cache_file_path(p.as_ref())
+            .ok()?
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// Size in bytes of file named `p` in workflow's cache dir
+    ///
+    /// `None` is returned if the file doesn't exist or its metadata can't be read.
+    pub fn cache_size<P: AsRef<Path>>(p: P) -> Option<u64> {
+        Self::cache_file_path(p.as_ref())
+            .ok()?
+            .metadata()
+            .map(|meta| meta.len())
+            .ok()
+    }
+
+    /// Delete all files in workflow's cache dir, including those in its sub-directories
+    ///
+    /// Returns the number of deleted files. Cache files are deleted along with their backups
+    /// (`.bak` and `.corrupt-*` files). Files that are in use are left alone: the `.lock` files
+    /// that guard files against concurrent writes, the temporary files of saves in progress and
+    /// the files of the [`updater`] (its state and downloaded releases, in the
+    /// `alfred_rs_updater` sub-directory). The same goes for [`clear_cache_matching()`] and
+    /// [`prune_cache()`].
+    ///
+    /// # Errors
+    ///
+    /// If the cache dir is not set or files can't be deleted.
+    ///
+    /// [`updater`]: ../updater/index.html
+    /// [`clear_cache_matching()`]: struct.Data.html#method.clear_cache_matching
+    /// [`prune_cache()`]: struct.Data.html#method.prune_cache
+    pub fn clear_cache() -> Result<usize> {
+        Self::clear_cache_matching(|_| true)
+    }
+
+    /// Delete files in workflow's cache dir (and its sub-directories) whose name matches `f`
+    ///
+    /// Returns the number of deleted files. Files in use are never deleted, see
+    /// [`clear_cache()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    ///
+    /// // Remove downloaded thumbnails
+    /// Data::clear_cache_matching(|name| name.ends_with(".png")).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If the cache dir is not set or files can't be deleted.
+    ///
+    /// [`clear_cache()`]: struct.Data.html#method.clear_cache
+    pub fn clear_cache_matching<F>(mut f: F) -> Result<usize>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut removed = 0;
+        for (p, _) in Self::cache_files()? {
+            if p.file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, &mut f)
+            {
+                std::fs::remove_file(&p)?;
+                removed += 1;
+            }
+        }
+        debug!("removed {} files from cache dir", removed);
+        Ok(removed)
+    }
+
+    /// Keep workflow's cache dir from growing unboundedly
+    ///
+    /// Files (including those in sub-directories) not modified for `max_age` or more are
+    /// deleted. Then, if the remaining files take more than `max_total_size` bytes, the least
+    /// recently modified ones are deleted until they fit. Either limit can be left out.
+    ///
+    /// Returns the number of deleted files. Files in use are never deleted, see
+    /// [`clear_cache()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use alfred_rs::data::Data;
+    /// use std::time::Duration;
+    ///
+    /// // Keep a week worth of cache, in no more than 50MB.
+    /// let week = Duration::from_secs(7 * 24 * 60 * 60);
+    /// Data::prune_cache(Some(week), Some(50 * 1024 * 1024)).unwrap();
+    /// ```
+    /// # Errors
+    ///
+    /// If the cache dir is not set or files can't be deleted.
+    ///
+    /// [`clear_cache()`]: struct.Data.html#method.clear_cache
+    pub fn prune_cache(max_age: Option<Duration>, max_total_size: Option<u64>) -> Result<usize> {
+        let now = SystemTime::now();
+        let mut files = Self::cache_files()?
+            .into_iter()
+            .map(|(p, meta)| {
+                let modified = meta.modified().unwrap_or(now);
+                (p, modified, meta.len())
+            })
+            .collect::<Vec<_>>();
+        // Oldest first
+        files.sort_by_key(|&(_, modified, _)| modified);
+
+        let mut total: u64 = files.iter().map(|&(_, _, size)| size).sum();
+        let mut removed = 0;
+        for (p, modified, size) in files {
+            let age = now.duration_since(modified).unwrap_or_default();
+            let too_old = max_age.map_or(false, |max_age| age >= max_age);
+            let too_big = max_total_size.map_or(false, |max_size| total > max_size);
+            if !too_old && !too_big {
+                continue;
+            }
+            std::fs::remove_file(&p)?;
+            total -= size;
+            removed += 1;
+        }
+        debug!("pruned {} files from cache dir", removed);
+        Ok(removed)
+    }
+
+    // Files in workflow's cache dir and its sub-directories that can be deleted, with their
+    // metadata: all but lock files, temporary files and the updater's files.
+    fn cache_files() -> Result<Vec<(PathBuf, std::fs::Metadata)>> {
+        let wfc = Self::cache_dir().ok_or_else(|| {
+            anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
+        })?;
+        let updater_dir = wfc.join(UPDATER_CACHE_DIR);
+        let mut files = Vec::new();
+        let mut dirs = vec![wfc];
+        while let Some(dir) = dirs.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                // Alfred doesn't create the cache dir, there may be nothing to clean.
+                Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let meta = entry.metadata()?;
+                let p = entry.path();
+                if meta.is_dir() {
+                    if p != updater_dir {
+                        dirs.push(p);
+                    }
+                    continue;
+                }
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if !name.ends_with(".lock") && !name.starts_with(TEMP_FN_PREFIX) {
+                    files.push((p, meta));
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    // Path of file `p` in workflow's cache dir.
+    fn cache_file_path(p: &Path) -> Result<PathBuf> {
+        let wfc = Self::cache_dir().ok_or_else(|| {
+            anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
+        })?;
+        let p = Self::relative_path(&wfc, p)?;
+        Ok(wfc.join(p))
+    }
+
+    fn read_with_format<V>(p: &Path, format: &dyn Format) -> Result<V>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        let value = format.deserialize(&Self::read_bytes_from_disk(p)?)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    // Content of file `p`, decompressed if needed.
+    fn read_bytes_from_disk(p: &Path) -> Result<Vec<u8>> {
+        use std::io::Read;
+        let mut bytes = Vec::new();
+        Self::open_for_reading(p)?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_data_from_disk<V>(p: &Path) -> Result<V>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        let d: V = serde_json::from_reader(Self::open_for_reading(p)?)?;
+        Ok(d)
+    }
+
+    // Deserialize the JSON content of a file read as a whole, decompressing it if needed.
+    #[cfg(feature = "async")]
+    fn parse_file_bytes<V>(bytes: &[u8]) -> Result<V>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let d: V = serde_json::from_reader(compression::decoder(bytes)?)?;
+        #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+        let d: V = serde_json::from_slice(bytes)?;
+        Ok(d)
+    }
+
+    // Reader of file `p` that decompresses it if needed.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn open_for_reading(p: &Path) -> Result<Box<dyn std::io::Read>> {
+        let buf_reader = BufReader::with_capacity(0x1000, File::open(p)?);
+        compression::decoder(buf_reader)
+    }
+
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    fn open_for_reading(p: &Path) -> Result<BufReader<File>> {
+        Ok(BufReader::with_capacity(0x1000, File::open(p)?))
+    }
+}
+
+// Drop what was read of file `p`, which is about to be replaced.
+fn forget_read(p: &Path) {
+    READ_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(p);
+}
+
+// Apply JSON merge patch `patch` (RFC 7396) to `target`.
+fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (k, v) in patch {
+                    if v.is_null() {
+                        target.remove(&k);
+                    } else {
+                        merge_patch(target.entry(k).or_insert(Value::Null), v);
+                    }
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+// Fail if `k` is one of the keys under which metadata of the other keys is saved in data files.
+fn check_key(k: &str) -> Result<()> {
+    if k == EXPIRES_KEY || k == MODIFIED_KEY {
+        bail!("key `{}` is reserved", k);
+    }
+    Ok(())
+}
+
+// Path of the file next to `p` named after it with `.<extension>` appended.
+fn sibling_path(p: &Path, extension: &str) -> PathBuf {
+    let mut name = p.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    p.with_file_name(name)
+}
+
+// Whether reading a file failed because of its content, rather than failing to read it.
+fn is_corrupt(e: &anyhow::Error) -> bool {
+    if e.downcast_ref::<std::io::Error>().is_some() || is_undecryptable(e) {
+        return false;
+    }
+    e.downcast_ref::<serde_json::Error>()
+        .map_or(true, |e| !e.is_io())
+}
+
+// Whether reading a file failed because it couldn't be decrypted with the key it was read with,
+// such files must be neither set aside nor overwritten.
+#[cfg(feature = "encryption")]
+fn is_undecryptable(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<DecryptionFailed>().is_some()
+}
+
+#[cfg(not(feature = "encryption"))]
+fn is_undecryptable(_e: &anyhow::Error) -> bool {
+    false
+}
+
+// 64-bit FNV-1a hash of `bytes`, stable across versions and platforms unlike std's hashers.
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Values of keys overridden by users with workflow variables, see
+// `Data::allow_env_overrides()`.
+fn env_overrides() -> HashMap<String, Value> {
+    std::env::vars_os()
+        .filter_map(|(var, v)| {
+            let var = var.into_string().ok()?;
+            let k = var.strip_prefix(ENV_OVERRIDE_PREFIX)?;
+            let v = v.into_string().ok()?;
+            let v = serde_json::from_str(&v).unwrap_or(Value::String(v));
+            Some((k.to_string(), v))
+        })
+        .collect()
+}
+
+// Milliseconds since Unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+impl Drop for Data {
+    // Only deferred writes are saved, and not while unwinding: saving takes a lock and can fail.
+    fn drop(&mut self) {
+        if !self.deferred || std::thread::panicking() {
+            return;
+        }
+        if let Err(e) = self.save() {
+            debug!(
+                "couldn't save pending changes to {:?}: {}",
+                self.file_name, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use std::env as StdEnv;
+    use std::ffi::OsStr;
+    use std::fs::remove_file;
+    use std::{thread, time};
+    use tempfile::Builder;
+
+    #[test]
+    fn it_sets_gets_data() {
+        #[derive(Serialize, Deserialize)]
+        struct User {
+            name: String,
+            age: usize,
+        }
+
+        setup_workflow_env_vars(true);
+
+        let user = User {
+            name: "Hamid".to_string(),
+            age: 42,
+        };
+
+        {
+            let mut wf_data: Data = Data::load("settings_test.json").unwrap();
+            wf_data.set("key1", &8).unwrap();
+            wf_data.set("key2", &user).unwrap();
+            wf_data.set("date", &Utc::now()).unwrap();
+            println!("{:?}", wf_data);
+        }
+
+        {
+            let wf_data = Data::load("settings_test.json").unwrap();
+
+            assert_eq!(3, wf_data.inner.len());
+            let user: User = wf_data.get("key2").unwrap();
+            assert_eq!(42, user.age);
+
+            let x: i8 = wf_data.get("key1").unwrap();
+            assert_eq!(8, x);
+            let _last_log: DateTime<Utc> = wf_data.get("date").expect("log date was not set");
+        }
+    }
+
+    #[test]
+    fn it_sets_many_values() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data.set_many(vec![("a", 1), ("b", 2)]).unwrap();
+        wf_data
+            .set_many(vec![("b", Value::from(3)), ("c", Value::from("three"))])
+            .unwrap();
+
+        let wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(3, wf_data.len());
+        assert_eq!(Some(1), wf_data.get("a"));
+        assert_eq!(Some(3), wf_data.get("b"));
+        assert_eq!(Some("three".to_string()), wf_data.get("c"));
+    }
+
+    #[test]
+    fn it_defers_writes() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data.defer_writes(true);
+        wf_data.set("count", &1).unwrap();
+        wf_data.set("count", &2).unwrap();
+        assert!(Data::load("settings_test.json").unwrap().is_empty());
+
+        wf_data.save().unwrap();
+        assert_eq!(
+            Some(2),
+            Data::load("settings_test.json").unwrap().get("count")
+        );
+
+        // Pending changes are saved when dropped.
+        wf_data.set("count", &3).unwrap();
+        drop(wf_data);
+        assert_eq!(
+            Some(3),
+            Data::load("settings_test.json").unwrap().get("count")
+        );
+    }
+
+    #[test]
+    fn it_does_not_retry_failed_writes_on_drop() {
+        let wfd = setup_workflow_env_vars(true);
+        let path = wfd.join("failed_write_test.json");
+
+        let mut wf_data = Data::load("failed_write_test.json").unwrap();
+        // A directory in place of the data file makes writes fail.
+        std::fs::create_dir(&path).unwrap();
+        assert!(wf_data.set("count", &1).is_err());
+        std::fs::remove_dir(&path).unwrap();
+        drop(wf_data);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn it_pretty_prints_data_files() {
+        let wfd = setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("pretty_test.json").unwrap();
+        wf_data.pretty_print(true);
+        wf_data.set("user", &"me").unwrap();
+        wf_data.set("accounts", &vec![1]).unwrap();
+        assert_eq!(
+            "{\n  \"accounts\": [\n    1\n  ],\n  \"user\": \"me\"\n}",
+            std::fs::read_to_string(wfd.join("pretty_test.json")).unwrap()
+        );
+
+        wf_data.pretty_print(false);
+        wf_data.remove("accounts").unwrap();
+        assert_eq!(
+            "{\"user\":\"me\"}",
+            std::fs::read_to_string(wfd.join("pretty_test.json")).unwrap()
+        );
+        remove_file(wfd.join("pretty_test.json")).unwrap();
+    }
+
+    #[test]
+    fn it_keeps_changes_of_concurrent_writers() {
+        let wfd = setup_workflow_env_vars(true);
+
+        let mut first = Data::load("concurrent_test.json").unwrap();
+        let mut second = Data::load("concurrent_test.json").unwrap();
+        first.set("a", &1).unwrap();
+        second.set("b", &2).unwrap();
+        first.set("c", &3).unwrap();
+        assert_eq!(Some(2), first.get("b"));
+        first.remove("b").unwrap();
+        second.set("a", &4).unwrap();
+
+        assert_eq!(Some(3), second.get("c"));
+        assert!(!second.contains_key("b"));
+        let saved = Data::load("concurrent_test.json").unwrap();
+        assert_eq!(Some(4), saved.get("a"));
+        assert_eq!(Some(3), saved.get("c"));
+        assert_eq!(2, saved.len());
+        assert!(wfd.join("concurrent_test.json.lock").exists());
+    }
+
+    #[test]
+    fn it_backs_up_data_files() {
+        let wfd = setup_workflow_env_vars(true);
+        let p = wfd.join("backup_test.json");
+        std::fs::write(&p, "{\"a\": 1,").unwrap();
+
+        // Corrupt files are moved aside instead of being overwritten.
+        let mut wf_data = Data::load("backup_test.json").unwrap();
+        assert!(wf_data.is_empty());
+        let corrupt = wf_data.corrupt_file().unwrap().to_path_buf();
+        assert_eq!("{\"a\": 1,", std::fs::read_to_string(&corrupt).unwrap());
+        assert!(!p.exists());
+        assert!(!wf_data.restore_backup().unwrap());
+
+        // Previous versions are only kept on demand.
+        wf_data.set("a", &1).unwrap();
+        wf_data.set("c", &3).unwrap();
+        assert!(!wfd.join("backup_test.json.bak").exists());
+        wf_data.remove("c").unwrap();
+        wf_data.keep_versions(1);
+        wf_data.set("b", &2).unwrap();
+        assert_eq!(
+            "{\"a\":1}",
+            std::fs::read_to_string(wfd.join("backup_test.json.bak")).unwrap()
+        );
+
+        // Only the most recent corrupt files are kept.
+        for timestamp in 1..=4 {
+            std::fs::write(
+                wfd.join(format!("backup_test.json.corrupt-{}", timestamp)),
+                "",
+            )
+            .unwrap();
+        }
+        std::fs::write(&p, "not json").unwrap();
+        let mut wf_data = Data::load("backup_test.json").unwrap();
+        assert!(wf_data.corrupt_file().is_some());
+        assert!(wf_data.corrupt_file().unwrap().exists());
+        assert!(corrupt.exists());
+        assert!(wfd.join("backup_test.json.corrupt-4").exists());
+        assert!(!wfd.join("backup_test.json.corrupt-3").exists());
+        assert!(wf_data.restore_backup().unwrap());
+        assert_eq!(Some(1), wf_data.get("a"));
+        assert_eq!(1, Data::load("backup_test.json").unwrap().len());
+    }
+
+    #[test]
+    fn it_reloads_data_files() {
+        setup_workflow_env_vars(true);
+
+        let mut first = Data::load("reload_test.json").unwrap();
+        let mut second = Data::load("reload_test.json").unwrap();
+        assert!(!first.is_stale());
+        second.set("progress", &10).unwrap();
+        assert!(first.is_stale());
+        assert!(!second.is_stale());
+
+        first.defer_writes(true);
+        first.set("query", &"rust").unwrap();
+        first.reload().unwrap();
+        assert!(!first.is_stale());
+        assert_eq!(Some(10), first.get("progress"));
+        assert_eq!(Some("rust"), first.get_ref("query"));
+        first.save().unwrap();
+        assert_eq!(2, Data::load("reload_test.json").unwrap().len());
+    }
+
+    #[test]
+    fn it_exports_imports_data() {
+        let wfd = setup_workflow_env_vars(true);
+        let export = Builder::new()
+            .prefix("alfred_export_test")
+            .tempdir()
+            .unwrap();
+        let p = export.path().join("settings.json");
+
+        let mut wf_data = Data::load("export_test.json").unwrap();
+        wf_data.set("user", &"me").unwrap();
+        wf_data.set("page_size", &20).unwrap();
+        wf_data.export(&p).unwrap();
+        assert_eq!(
+            "{\n  \"page_size\": 20,\n  \"user\": \"me\"\n}",
+            std::fs::read_to_string(&p).unwrap()
+        );
+
+        wf_data.clear();
+        wf_data.set("user", &"you").unwrap();
+        std::fs::write(wfd.join("invalid.json"), "[1, 2]").unwrap();
+        assert!(wf_data.import(wfd.join("invalid.json")).is_err());
+        assert!(wf_data.import(export.path().join("missing.json")).is_err());
+        assert_eq!(1, wf_data.len());
+
+        wf_data.import(&p).unwrap();
+        assert_eq!(Some("me"), wf_data.get_ref("user"));
+        let wf_data = Data::load("export_test.json").unwrap();
+        assert_eq!(Some(20), wf_data.get("page_size"));
+        assert_eq!(2, wf_data.len());
+    }
+
+    #[test]
+    fn it_applies_merge_patches() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("patch_test.json").unwrap();
+        wf_data
+            .set_many(vec![
+                ("token", serde_json::json!("secret")),
+                ("ui", serde_json::json!({"theme": "light", "size": 12})),
+                ("tags", serde_json::json!(["a", "b"])),
+            ])
+            .unwrap();
+        wf_data
+            .apply_patch(serde_json::json!({
+                "token": null,
+                "ui": {"theme": "dark", "size": null, "font": {"name": "Menlo", "bold": null}},
+                "tags": ["c"],
+                "page_size": 20,
+            }))
+            .unwrap();
+        assert!(wf_data.apply_patch(serde_json::json!([1])).is_err());
+
+        let mut wf_data = Data::load("patch_test.json").unwrap();
+        assert!(!wf_data.contains_key("token"));
+        assert_eq!(
+            Some(&serde_json::json!({"theme": "dark", "font": {"name": "Menlo"}})),
+            wf_data.get_value("ui")
+        );
+        assert_eq!(Some(vec!["c".to_string()]), wf_data.get("tags"));
+
+        let mut defaults = Data::load("patch_defaults_test.json").unwrap();
+        defaults
+            .set("ui", &serde_json::json!({"size": 14}))
+            .unwrap();
+        defaults.set("page_size", &50).unwrap();
+        wf_data.merge(&defaults).unwrap();
+        assert_eq!(Some(50), wf_data.get("page_size"));
+        assert_eq!(
+            Some(&serde_json::json!({"theme": "dark", "size": 14, "font": {"name": "Menlo"}})),
+            wf_data.get_value("ui")
+        );
+    }
+
+    #[test]
+    fn it_commits_or_rolls_back_transactions() {
+        let wfd = setup_workflow_env_vars(true);
+        let path = wfd.join("txn_test.json");
+        let _ = remove_file(&path);
+
+        let mut wf_data = Data::load("txn_test.json").unwrap();
+        wf_data.set("login_error", &"expired").unwrap();
+        wf_data
+            .transaction(|txn| {
+                txn.set("access_token", &"a1")?;
+                txn.set("refresh_token", &"r1")?;
+                assert_eq!(Some("a1".to_string()), txn.get("access_token"));
+                assert!(txn.remove("login_error").is_some());
+                Ok(())
+            })
+            .unwrap();
+        let on_disk = Data::load("txn_test.json").unwrap();
+        assert_eq!(Some("a1".to_string()), on_disk.get("access_token"));
+        assert_eq!(Some("r1".to_string()), on_disk.get("refresh_token"));
+        assert!(!on_disk.contains_key("login_error"));
+
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        let result: Result<()> = wf_data.transaction(|txn| {
+            txn.set("access_token", &"a2")?;
+            txn.remove("refresh_token");
+            bail!("refresh failed")
+        });
+        assert!(result.is_err());
+        assert_eq!(Some("a1".to_string()), wf_data.get("access_token"));
+        assert_eq!(Some("r1".to_string()), wf_data.get("refresh_token"));
+        assert_eq!(
+            modified,
+            std::fs::metadata(&path).unwrap().modified().unwrap()
+        );
+
+        // Nothing is left pending either.
+        wf_data.set("page_size", &20).unwrap();
+        let on_disk = Data::load("txn_test.json").unwrap();
+        assert_eq!(Some("a1".to_string()), on_disk.get("access_token"));
+        assert_eq!(Some("r1".to_string()), on_disk.get("refresh_token"));
+    }
+
+    #[test]
+    fn it_falls_back_to_defaults() {
+        #[derive(Serialize)]
+        struct Defaults {
+            page_size: u32,
+            theme: &'static str,
+            api_token: Option<String>,
+        }
+
+        let wfd = setup_workflow_env_vars(true);
+        let path = wfd.join("defaults_test.json");
+        let _ = remove_file(&path);
+
+        let defaults = Defaults {
+            page_size: 20,
+            theme: "dark",
+            api_token: None,
+        };
+        let mut wf_data = Data::load("defaults_test.json")
+            .and_then(|data| data.with_defaults(&defaults))
+            .unwrap();
+        assert_eq!(Some(20), wf_data.get::<_, u32>("page_size"));
+        assert_eq!(Some("dark".to_string()), wf_data.get_str("theme"));
+        assert_eq!(None, wf_data.get_value("api_token"));
+        assert!(!wf_data.contains_key("page_size"));
+        assert!(wf_data.is_empty());
+
+        wf_data.set("page_size", &50).unwrap();
+        assert_eq!(Some(50), wf_data.get::<_, u32>("page_size"));
+        wf_data.remove("page_size").unwrap();
+        assert_eq!(Some(20), wf_data.get::<_, u32>("page_size"));
+
+        // Defaults are not saved.
+        wf_data.set("theme", &"light").unwrap();
+        let saved: HashMap<String, Value> =
+            serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+        assert_eq!(1, saved.len());
+
+        assert!(Data::load("defaults_test.json")
+            .unwrap()
+            .with_defaults(&20)
+            .is_err());
+    }
+
+    #[test]
+    fn it_applies_env_overrides() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = remove_file(wfd.join("env_overrides_test.json"));
+        StdEnv::set_var("wf_setting_env_test_page_size", "50");
+        StdEnv::set_var("wf_setting_env_test_theme", "dark");
+
+        let mut wf_data = Data::load("env_overrides_test.json").unwrap();
+        wf_data.set("env_test_page_size", &20).unwrap();
+        wf_data.set("env_test_theme", &"light").unwrap();
+        assert_eq!(Some(50), wf_data.get::<_, u32>("env_test_page_size"));
+        assert_eq!(Some("dark".to_string()), wf_data.get_str("env_test_theme"));
+        assert_eq!(2, wf_data.len());
+
+        wf_data.allow_env_overrides(false);
+        assert_eq!(Some(20), wf_data.get::<_, u32>("env_test_page_size"));
+        assert_eq!(Some("light".to_string()), wf_data.get_str("env_test_theme"));
+
+        StdEnv::remove_var("wf_setting_env_test_page_size");
+        StdEnv::remove_var("wf_setting_env_test_theme");
+    }
+
+    #[test]
+    fn it_tracks_modifications() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = remove_file(wfd.join("modified_test.json"));
+
+        let mut wf_data = Data::load("modified_test.json").unwrap();
+        wf_data.set("untracked", &1).unwrap();
+        assert_eq!(None, wf_data.modified_at("untracked"));
+
+        let before = SystemTime::now() - Duration::from_millis(1);
+        wf_data.track_modifications(true);
+        wf_data.set("page_size", &20).unwrap();
+        wf_data
+            .apply_patch(serde_json::json!({"theme": "dark"}))
+            .unwrap();
+        let page_size_modified = wf_data.modified_at("page_size").unwrap();
+        assert!(page_size_modified > before);
+        assert!(wf_data.modified_at("theme").is_some());
+        assert_eq!(None, wf_data.modified_at("untracked"));
+        assert_eq!(3, wf_data.len());
+
+        let mut wf_data = Data::load("modified_test.json").unwrap();
+        assert_eq!(Some(page_size_modified), wf_data.modified_at("page_size"));
+        assert!(!wf_data.contains_key(MODIFIED_KEY));
+        assert!(wf_data.set(MODIFIED_KEY, &"x").is_err());
+        assert!(wf_data
+            .apply_patch(serde_json::json!({ "a": 1, MODIFIED_KEY: 2 }))
+            .is_err());
+        assert!(!wf_data.contains_key("a"));
+
+        wf_data.set("page_size", &30).unwrap();
+        assert_eq!(None, wf_data.modified_at("page_size"));
+        wf_data.remove("theme").unwrap();
+        assert_eq!(None, wf_data.modified_at("theme"));
+    }
+
+    #[test]
+    fn it_increments_counters() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = remove_file(wfd.join("counter_test.json"));
+
+        let mut first = Data::load("counter_test.json").unwrap();
+        let mut second = Data::load("counter_test.json").unwrap();
+        assert_eq!(1, first.increment("launch_count").unwrap());
+        assert_eq!(2, second.increment("launch_count").unwrap());
+        assert_eq!(5, first.increment_by("launch_count", 3).unwrap());
+
+        // Pending increments are added to the value on disk.
+        second.defer_writes(true);
+        second.increment("launch_count").unwrap();
+        second.increment_by("launch_count", -2).unwrap();
+        first.increment("launch_count").unwrap();
+        second.save().unwrap();
+        assert_eq!(Some(5), second.get_i64("launch_count"));
+
+        // Setting a key replaces increments made before.
+        second.increment("launch_count").unwrap();
+        second.set("launch_count", &10).unwrap();
+        second.increment("launch_count").unwrap();
+        first.increment("launch_count").unwrap();
+        second.save().unwrap();
+        assert_eq!(Some(11), second.get_i64("launch_count"));
+
+        second.set("launch_count", &"many").unwrap();
+        assert!(second.increment("launch_count").is_err());
+        second.set("launch_count", &i64::MAX).unwrap();
+        assert!(second.increment("launch_count").is_err());
+    }
+
+    #[test]
+    fn it_rolls_back_versions() {
+        let wfd = setup_workflow_env_vars(true);
+        for name in &["", ".bak", ".bak.2", ".bak.3", ".bak.4"] {
+            let _ = remove_file(wfd.join(format!("versions_test.json{}", name)));
+        }
+
+        let mut wf_data = Data::load("versions_test.json").unwrap();
+        assert!(!wf_data.rollback(1).unwrap());
+        assert!(wf_data.rollback(0).is_err());
+        wf_data.keep_versions(3);
+        for page_size in 1..=5 {
+            wf_data.set("page_size", &page_size).unwrap();
+        }
+        assert_eq!(3, wf_data.versions().len());
+        assert!(!wfd.join("versions_test.json.bak.4").exists());
+
+        assert!(wf_data.rollback(1).unwrap());
+        assert_eq!(Some(4), wf_data.get_i64("page_size"));
+        assert_eq!(2, wf_data.versions().len());
+        assert!(wf_data.rollback(2).unwrap());
+        assert_eq!(Some(2), wf_data.get_i64("page_size"));
+        assert!(wf_data.versions().is_empty());
+        assert!(!wf_data.rollback(1).unwrap());
+        let on_disk = Data::load("versions_test.json").unwrap();
+        assert_eq!(Some(2), on_disk.get_i64("page_size"));
+
+        // Fewer versions are kept once the limit is lowered.
+        wf_data.set("page_size", &6).unwrap();
+        wf_data.set("page_size", &7).unwrap();
+        wf_data.keep_versions(1);
+        wf_data.set("page_size", &8).unwrap();
+        assert_eq!(1, wf_data.versions().len());
+        assert!(wf_data.rollback(1).unwrap());
+        assert_eq!(Some(7), wf_data.get_i64("page_size"));
+    }
+
+    #[test]
+    fn it_caches_files_read() {
+        let wfd = setup_workflow_env_vars(true);
+        let path = wfd.join("read_cache_test.json");
+        let _ = remove_file(&path);
+        assert!(!Data::exists("read_cache_test.json"));
+
+        let mut wf_data = Data::load("read_cache_test.json").unwrap();
+        assert_eq!(path, wf_data.path());
+        wf_data.set("page_size", &20).unwrap();
+        assert!(Data::exists("read_cache_test.json"));
+        assert_eq!(
+            Some(20),
+            Data::load("read_cache_test.json")
+                .unwrap()
+                .get_i64("page_size")
+        );
+        assert!(READ_CACHE.lock().unwrap().contains_key(&path));
+
+        // Files changed by others are read again.
+        thread::sleep(time::Duration::from_millis(10));
+        std::fs::write(&path, r#"{"page_size": 50}"#).unwrap();
+        assert_eq!(
+            Some(50),
+            Data::load("read_cache_test.json")
+                .unwrap()
+                .get_i64("page_size")
+        );
+        wf_data.set("page_size", &30).unwrap();
+        assert_eq!(
+            Some(30),
+            Data::load("read_cache_test.json")
+                .unwrap()
+                .get_i64("page_size")
+        );
+
+        // Files replaced right away with the same size are read again too.
+        let replacement = wfd.join("read_cache_test.json.new");
+        std::fs::write(&replacement, r#"{"page_size": 40}"#).unwrap();
+        std::fs::rename(&replacement, &path).unwrap();
+        assert_eq!(
+            Some(40),
+            Data::load("read_cache_test.json")
+                .unwrap()
+                .get_i64("page_size")
+        );
+
+        // Cache files are not kept in memory.
+        Data::save_to_file("read_cache_test.json", &vec![1, 2]).unwrap();
+        assert_eq!(
+            Some(vec![1, 2]),
+            Data::load_from_file::<_, Vec<u32>>("read_cache_test.json")
+        );
+        let cache_path = Data::cache_file_path(Path::new("read_cache_test.json")).unwrap();
+        assert!(!READ_CACHE.lock().unwrap().contains_key(&cache_path));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn it_encrypts_data_files() {
+        let wfd = setup_workflow_env_vars(true);
+        let path = wfd.join("encrypted_test.bin");
+        let _ = remove_file(&path);
+
+        let key = format::Encrypted::generate_key().unwrap();
+        let mut wf_data =
+            Data::load_with_format("encrypted_test.bin", format::Encrypted::new(&key)).unwrap();
+        wf_data.set("session_cookie", &"a4c9e1").unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("a4c9e1"));
+
+        let wf_data =
+            Data::load_with_format("encrypted_test.bin", format::Encrypted::new(&key)).unwrap();
+        assert_eq!(
+            Some("a4c9e1".to_string()),
+            wf_data.get_str("session_cookie")
+        );
+        drop(wf_data);
+
+        // Files that can't be decrypted are left in place.
+        let other_key = format::Encrypted::generate_key().unwrap();
+        let err = Data::load_with_format("encrypted_test.bin", format::Encrypted::new(&other_key))
+            .unwrap_err();
+        assert!(err.downcast_ref::<DecryptionFailed>().is_some());
+        assert_eq!(bytes, std::fs::read(&path).unwrap());
+        let wf_data =
+            Data::load_with_format("encrypted_test.bin", format::Encrypted::new(&key)).unwrap();
+        assert!(wf_data.corrupt_file().is_none());
+        assert_eq!(
+            Some("a4c9e1".to_string()),
+            wf_data.get_str("session_cookie")
+        );
+    }
+
+    #[test]
+    fn it_redacts_secrets() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = remove_file(wfd.join("secret_test.json"));
+
+        let mut wf_data = Data::load("secret_test.json").unwrap();
+        wf_data
+            .set("api_token", &Secret::new("b3f1e9".to_string()))
+            .unwrap();
+        let token = wf_data.get_secret("api_token").unwrap();
+        assert_eq!("b3f1e9", token.expose());
+        assert_eq!("[REDACTED]", format!("{}", token));
+        assert_eq!("Some([REDACTED])", format!("{:?}", Some(&token)));
+        assert_eq!(Some("b3f1e9".to_string()), wf_data.get_str("api_token"));
+    }
+
+    #[test]
+    fn it_removes_keys() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data.set("token", &"secret").unwrap();
+        wf_data.set("user_id", &0xFF).unwrap();
+
+        assert_eq!(
+            Some(Value::from("secret")),
+            wf_data.remove("token").unwrap()
+        );
+        assert_eq!(None, wf_data.remove("token").unwrap());
+
+        let wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(None, wf_data.get::<_, String>("token"));
+        assert_eq!(Some(0xFF), wf_data.get("user_id"));
+    }
+
+    #[test]
+    fn it_gets_or_inserts_values() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        let id: u32 = wf_data.get_or_insert_with("device_id", || 7).unwrap();
+        assert_eq!(7, id);
+        let id: u32 = wf_data
+            .get_or_insert_with("device_id", || panic!("value should be stored"))
+            .unwrap();
+        assert_eq!(7, id);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(Some(7), wf_data.get("device_id"));
+        let err = wf_data
+            .get_or_insert_with::<_, String, _>("device_id", || "x".into())
+            .unwrap_err();
+        assert_eq!("device_id", err.downcast::<TypeMismatch>().unwrap().key());
+        let wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(Some(7), wf_data.get("device_id"));
+    }
+
+    #[test]
+    fn it_reports_type_mismatches() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("try_get_test.json").unwrap();
+        wf_data.set("page_size", &"twenty").unwrap();
+        assert!(wf_data.try_get::<_, u32>("missing").unwrap().is_none());
+        assert_eq!(
+            Some("twenty".to_string()),
+            wf_data.try_get("page_size").unwrap()
+        );
+        let e = wf_data.try_get::<_, u32>("page_size").unwrap_err();
+        assert_eq!("page_size", e.key());
+        assert!(e.to_string().starts_with("value of key `page_size`"));
+    }
+
+    #[test]
+    fn it_coerces_typed_values() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data
+            .set_many(vec![
+                ("name", serde_json::json!("alfred")),
+                ("flag", serde_json::json!(" Yes")),
+                ("one", serde_json::json!(1)),
+                ("count", serde_json::json!("20")),
+                ("whole", serde_json::json!(3.0)),
+                ("ratio", serde_json::json!("0.5")),
+                ("list", serde_json::json!([1])),
+            ])
+            .unwrap();
+
+        assert_eq!(Some("alfred".to_string()), wf_data.get_str("name"));
+        assert_eq!(Some("1".to_string()), wf_data.get_str("one"));
+        assert_eq!(None, wf_data.get_str("list"));
+        assert_eq!(None, wf_data.get_str("missing"));
+
+        assert_eq!(Some(true), wf_data.get_bool("flag"));
+        assert_eq!(Some(true), wf_data.get_bool("one"));
+        assert_eq!(None, wf_data.get_bool("name"));
+        assert_eq!(None, wf_data.get_bool("count"));
+
+        assert_eq!(Some(20), wf_data.get_i64("count"));
+        assert_eq!(Some(3), wf_data.get_i64("whole"));
+        assert_eq!(None, wf_data.get_i64("ratio"));
+        assert_eq!(None, wf_data.get_i64("flag"));
+
+        assert_eq!(Some(0.5), wf_data.get_f64("ratio"));
+        assert_eq!(Some(1.0), wf_data.get_f64("one"));
+        assert_eq!(None, wf_data.get_f64("name"));
+    }
+
+    #[test]
+    fn it_expires_values() {
+        let wfd = setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data
+            .set_with_ttl("weather", &"sunny", Duration::from_millis(200))
+            .unwrap();
+        wf_data
+            .set_with_ttl("token", &"secret", Duration::from_secs(60))
+            .unwrap();
+        wf_data.set("user_id", &7).unwrap();
+        assert_eq!(Some("sunny".to_string()), wf_data.get("weather"));
+        assert_eq!(3, wf_data.len());
+
+        let wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(Some("sunny"), wf_data.get_ref("weather"));
+        assert!(!wf_data.keys().any(|k| k == EXPIRES_KEY));
+
+        thread::sleep(time::Duration::from_millis(250));
+        assert_eq!(None, wf_data.get::<_, String>("weather"));
+        assert!(!wf_data.contains_key("weather"));
+        assert_eq!(2, wf_data.len());
+        drop(wf_data);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        assert_eq!(Some("secret".to_string()), wf_data.get("token"));
+        // Setting a value again makes it permanent.
+        wf_data.set("token", &"secret").unwrap();
+        let saved: HashMap<String, Value> =
+            Data::load_from_path(&wfd.join("settings_test.json")).unwrap();
+        assert_eq!(2, saved.len());
+        assert!(!saved.contains_key(EXPIRES_KEY));
+
+        // The key expiry times are saved under can't be set.
+        assert!(wf_data.set(EXPIRES_KEY, &"x").is_err());
+        assert!(wf_data.set_many(vec![("a", 1), (EXPIRES_KEY, 2)]).is_err());
+        assert!(wf_data.increment(EXPIRES_KEY).is_err());
+        assert!(!wf_data.contains_key("a"));
+        assert_eq!(2, Data::load("settings_test.json").unwrap().len());
+    }
+
+    #[test]
+    fn it_uses_custom_formats() {
+        // Reversed JSON, so that files in this format can't be read as JSON.
+        #[derive(Debug)]
+        struct Reversed;
+
+        impl Format for Reversed {
+            fn serialize(&self, value: &Value) -> Result<Vec<u8>> {
+                let mut bytes = serde_json::to_vec(value)?;
+                bytes.reverse();
+                Ok(bytes)
+            }
+
+            fn deserialize(&self, bytes: &[u8]) -> Result<Value> {
+                let mut bytes = bytes.to_vec();
+                bytes.reverse();
+                Ok(serde_json::from_slice(&bytes)?)
+            }
+        }
+
+        let wfd = setup_workflow_env_vars(true);
+        let mut wf_data = Data::load_with_format("settings_test.rev", Reversed).unwrap();
+        wf_data.set("page_size", &20).unwrap();
+        assert_eq!(
+            "}02:\"ezis_egap\"{",
+            std::fs::read_to_string(wfd.join("settings_test.rev")).unwrap()
+        );
+        let wf_data = Data::load_with_format("settings_test.rev", Reversed).unwrap();
+        assert_eq!(Some(20), wf_data.get("page_size"));
+
+        Data::save_to_file_with_format("tags_test.rev", &vec!["rust"], Reversed).unwrap();
+        let tags: Option<Vec<String>> = Data::load_from_file_with_format("tags_test.rev", Reversed);
+        assert_eq!(Some(vec!["rust".to_string()]), tags);
+        assert_eq!(
+            None,
+            Data::load_from_file::<_, Vec<String>>("tags_test.rev")
+        );
+        let tags: Option<Vec<String>> =
+            Data::load_from_file_with_format("tags_test.rev", format::Json);
+        assert_eq!(None, tags);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn it_keeps_comments_of_toml_files() {
+        let wfd = setup_workflow_env_vars(true);
+        let p = wfd.join("settings_test.toml");
+        std::fs::write(
+            &p,
+            "# Results shown in Alfred\npage_size = 20 # max 50\n\n[account]\n# your login\nuser = \"me\"\n",
+        )
+        .unwrap();
+
+        let mut wf_data = Data::load_with_format("settings_test.toml", format::Toml).unwrap();
+        assert_eq!(Some(20), wf_data.get_i64("page_size"));
+        wf_data.set("page_size", &30).unwrap();
+        wf_data
+            .set("account", &serde_json::json!({"user": "you"}))
+            .unwrap();
+        wf_data.set("dark_mode", &true).unwrap();
+
+        let saved = std::fs::read_to_string(&p).unwrap();
+        assert!(saved.contains("# Results shown in Alfred\npage_size = 30 # max 50\n"));
+        assert!(saved.contains("# your login\nuser = \"you\""));
+        assert!(saved.contains("dark_mode = true"));
+
+        let wf_data = Data::load_with_format("settings_test.toml", format::Toml).unwrap();
+        assert_eq!(Some(true), wf_data.get("dark_mode"));
+        assert_eq!(3, wf_data.len());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn it_uses_yaml_files() {
+        let wfd = setup_workflow_env_vars(true);
+        std::fs::write(wfd.join("settings_test.yaml"), "page_size: 20\nuser: me\n").unwrap();
+
+        let mut wf_data = Data::load_with_format("settings_test.yaml", format::Yaml).unwrap();
+        assert_eq!(Some(20), wf_data.get("page_size"));
+        wf_data.set("page_size", &30).unwrap();
+        let saved = std::fs::read_to_string(wfd.join("settings_test.yaml")).unwrap();
+        assert_eq!("page_size: 30\nuser: me\n", saved);
+
+        Data::save_to_file_with_format("tags_test.yaml", &vec!["rust"], format::Yaml).unwrap();
+        assert_eq!(
+            "- rust\n",
+            std::fs::read_to_string(wfd.join("tags_test.yaml")).unwrap()
+        );
+        let tags: Option<Vec<String>> =
+            Data::load_from_file_with_format("tags_test.yaml", format::Yaml);
+        assert_eq!(Some(vec!["rust".to_string()]), tags);
+        assert_eq!(
+            None,
+            Data::load_from_file::<_, Vec<String>>("tags_test.yaml")
+        );
+    }
+
+    #[cfg(feature = "plist")]
+    #[test]
+    fn it_uses_plist_files() {
+        let wfd = setup_workflow_env_vars(true);
+        std::fs::write(
+            wfd.join("settings_test.plist"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>page_size</key>
+	<integer>20</integer>
+	<key>user</key>
+	<string>me</string>
+</dict>
+</plist>
+"#,
+        )
+        .unwrap();
+
+        let mut wf_data = Data::load_with_format("settings_test.plist", format::Plist).unwrap();
+        assert_eq!(Some(20), wf_data.get("page_size"));
+        wf_data.set("page_size", &30).unwrap();
+        let saved = std::fs::read_to_string(wfd.join("settings_test.plist")).unwrap();
+        assert!(saved.contains("<key>page_size</key>\n\t<integer>30</integer>"));
+
+        Data::save_to_file_with_format("tags_test.plist", &vec!["rust"], format::BinaryPlist)
+            .unwrap();
+        assert!(std::fs::read(wfd.join("tags_test.plist"))
+            .unwrap()
+            .starts_with(b"bplist00"));
+        let tags: Option<Vec<String>> =
+            Data::load_from_file_with_format("tags_test.plist", format::Plist);
+        assert_eq!(Some(vec!["rust".to_string()]), tags);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn it_uses_message_pack_files() {
+        let wfc = setup_workflow_env_vars(true);
+        let items: Vec<(u32, String)> = (0..1000).map(|i| (i, format!("item {}", i))).collect();
+
+        Data::save_to_file_with_format("items_test.msgpack", &items, format::MessagePack).unwrap();
+        Data::save_to_file("items_test.json", &items).unwrap();
+        assert!(
+            Data::cache_size("items_test.msgpack").unwrap()
+                < Data::cache_size("items_test.json").unwrap()
+        );
+        let cached: Option<Vec<(u32, String)>> =
+            Data::load_from_file_with_format("items_test.msgpack", format::MessagePack);
+        assert_eq!(Some(items), cached);
+
+        let mut wf_data =
+            Data::load_with_format("settings_test.msgpack", format::MessagePack).unwrap();
+        wf_data.set("user", &serde_json::json!({"id": 7})).unwrap();
+        let wf_data = Data::load_with_format("settings_test.msgpack", format::MessagePack).unwrap();
+        assert_eq!(
+            Some(&serde_json::json!({"id": 7})),
+            wf_data.get_value("user")
+        );
+        remove_file(wfc.join("items_test.json")).unwrap();
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn it_compresses_files() {
+        fn check(compression: Compression, magic: &[u8]) {
+            let wfc = setup_workflow_env_vars(true);
+            let items: Vec<String> = (0..1000).map(|i| format!("item {}", i)).collect();
+
+            Data::save_to_file("items_test.json", &items).unwrap();
+            Data::save_to_file_compressed("items_test.z", &items, compression).unwrap();
+            assert!(std::fs::read(wfc.join("items_test.z"))
+                .unwrap()
+                .starts_with(magic));
+            assert!(
+                Data::cache_size("items_test.z").unwrap()
+                    < Data::cache_size("items_test.json").unwrap()
+            );
+            assert_eq!(Some(&items), Data::load_from_file("items_test.z").as_ref());
+
+            let mut wf_data = Data::load("settings_test.json").unwrap();
+            wf_data.set_compression(Some(compression));
+            wf_data.set("items", &items).unwrap();
+            assert!(std::fs::read(wfc.join("settings_test.json"))
+                .unwrap()
+                .starts_with(magic));
+            let mut wf_data = Data::load("settings_test.json").unwrap();
+            assert_eq!(Some(&items), wf_data.get("items").as_ref());
+            // Back to plain JSON
+            wf_data.set("user", &"me").unwrap();
+            let saved: HashMap<String, Value> =
+                serde_json::from_slice(&std::fs::read(wfc.join("settings_test.json")).unwrap())
+                    .unwrap();
+            assert_eq!(2, saved.len());
+        }
+
+        #[cfg(feature = "gzip")]
+        check(Compression::Gzip, &[0x1f, 0x8b]);
+        #[cfg(feature = "zstd")]
+        check(Compression::Zstd, &[0x28, 0xb5, 0x2f, 0xfd]);
+    }
+
+    #[test]
+    fn it_reports_stored_keys() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        assert!(wf_data.is_empty());
+        assert!(!wf_data.contains_key("user_id"));
+
+        wf_data.set("user_id", &Value::Null).unwrap();
+        assert!(wf_data.contains_key("user_id"));
+        assert_eq!(None, wf_data.get::<_, u32>("user_id"));
+        assert_eq!(1, wf_data.len());
+        assert!(!wf_data.is_empty());
+    }
+
+    #[test]
+    fn it_iterates_over_entries() {
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data.set("work", &42).unwrap();
+        wf_data.set("home", &7).unwrap();
+        wf_data.set("name", &"alfred").unwrap();
+
+        let mut keys = wf_data.keys().collect::<Vec<_>>();
+        keys.sort_unstable();
+        assert_eq!(vec!["home", "name", "work"], keys);
+        assert_eq!(3, wf_data.iter().count());
+        assert!(wf_data
+            .iter()
+            .any(|(k, v)| k == "name" && v == &Value::from("alfred")));
+
+        let mut numbers = wf_data.iter_as::<u32>().collect::<Vec<_>>();
+        numbers.sort_unstable();
+        assert_eq!(vec![("home", 7), ("work", 42)], numbers);
+    }
+
+    #[test]
+    fn it_borrows_values() {
+        #[derive(Deserialize)]
+        struct Profile<'a> {
+            name: &'a str,
+            tags: Vec<&'a str>,
+        }
+
+        setup_workflow_env_vars(true);
+
+        let mut wf_data = Data::load("settings_test.json").unwrap();
+        wf_data.set("user", &"hamid").unwrap();
+        wf_data
+            .set(
+                "profile",
+                &serde_json::json!({"name": "work", "tags": ["a", "b"]}),
+            )
+            .unwrap();
+
+        assert_eq!(Some("hamid"), wf_data.get_ref("user"));
+        let profile: Profile<'_> = wf_data.get_ref("profile").unwrap();
+        assert_eq!("work", profile.name);
+        assert_eq!(vec!["a", "b"], profile.tags);
+        assert_eq!(Some(&Value::from("hamid")), wf_data.get_value("user"));
+        assert_eq!(None, wf_data.get_value("missing"));
+    }
+
+    #[test]
+    fn it_binds_whole_files() {
+        #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+        struct Settings {
+            page_size: u32,
+            #[serde(default)]
+            dark_mode: bool,
+        }
+
+        let wfd = setup_workflow_env_vars(true);
+
+        let mut settings: Binding<Settings> = Data::bind("bound_test.json").unwrap();
+        assert_eq!(Settings::default(), *settings);
+        settings.page_size = 50;
+        settings.save().unwrap();
+
+        let settings = Data::bind::<Settings, _>("bound_test.json").unwrap();
+        assert_eq!(50, settings.page_size);
+        assert_eq!(wfd.join("bound_test.json"), settings.path());
+
+        std::fs::write(wfd.join("bound_test.json"), r#"{"page_size": "all"}"#).unwrap();
+        assert!(Data::bind::<Settings, _>("bound_test.json").is_err());
+    }
+
+    #[test]
+    fn it_saves_loads_from_file() {
+        let wfc = setup_workflow_env_vars(true);
+        let path = wfc.join("_test_saves_loads_from_file");
+        let _r = remove_file(&path);
+
+        let now = Utc::now();
+        Data::save_to_file(&path, &now).expect("couldn't write to file");
         let what_now: DateTime<Utc> =
             Data::load_from_file(path).expect("couldn't get value from test file");
         assert_eq!(now, what_now);
     }
 
+    #[test]
+    fn it_appends_to_logs() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = remove_file(wfd.join("log_test.jsonl"));
+
+        assert_eq!(
+            0,
+            Data::read_log::<_, u32>("log_test.jsonl").unwrap().count()
+        );
+        Data::append("log_test.jsonl", &1).unwrap();
+        Data::append("log_test.jsonl", &2).unwrap();
+        // Entries that can't be read are skipped.
+        Data::append("log_test.jsonl", &"three").unwrap();
+        Data::append("log_test.jsonl", &4).unwrap();
+
+        let entries: Vec<u32> = Data::read_log("log_test.jsonl").unwrap().collect();
+        assert_eq!(vec![1, 2, 4], entries);
+        remove_file(wfd.join("log_test.jsonl")).unwrap();
+    }
+
+    #[test]
+    fn it_streams_items() {
+        let wfc = setup_workflow_env_vars(true);
+
+        let items = (0..1000).map(|i| (i, format!("item {}\n", i)));
+        Data::save_iter_to_file("items_test.json", items.clone()).unwrap();
+        let all: Vec<(u32, String)> = Data::load_from_file("items_test.json").unwrap();
+        assert_eq!(items.clone().collect::<Vec<_>>(), all);
+        let read: Vec<(u32, String)> = Data::load_iter_from_file("items_test.json")
+            .unwrap()
+            .collect();
+        assert_eq!(all, read);
+
+        Data::save_iter_to_file("items_test.json", Vec::<u32>::new()).unwrap();
+        let all: Vec<u32> = Data::load_from_file("items_test.json").unwrap();
+        assert!(all.is_empty());
+        assert_eq!(
+            0,
+            Data::load_iter_from_file::<_, u32>("items_test.json")
+                .unwrap()
+                .count()
+        );
+        assert!(Data::load_iter_from_file::<_, u32>("missing_test.json").is_none());
+        remove_file(wfc.join("items_test.json")).unwrap();
+    }
+
+    #[test]
+    fn it_verifies_checksums() {
+        let wfc = setup_workflow_env_vars(true);
+        let p = wfc.join("checksum_test.json");
+
+        Data::save_to_file_with_checksum("checksum_test.json", &vec![1, 2]).unwrap();
+        let items: Option<Vec<u32>> = Data::load_from_file_with_checksum("checksum_test.json");
+        assert_eq!(Some(vec![1, 2]), items);
+
+        let mut bytes = std::fs::read(&p).unwrap();
+        *bytes.last_mut().unwrap() = b'}';
+        std::fs::write(&p, &bytes).unwrap();
+        let items: Option<Vec<u32>> = Data::load_from_file_with_checksum("checksum_test.json");
+        assert_eq!(None, items);
+
+        Data::save_to_file("checksum_test.json", &vec![1, 2]).unwrap();
+        let items: Option<Vec<u32>> = Data::load_from_file_with_checksum("checksum_test.json");
+        assert_eq!(None, items);
+        remove_file(p).unwrap();
+    }
+
+    #[test]
+    fn it_loads_fresh_cache_files() {
+        let wfc = setup_workflow_env_vars(true);
+
+        Data::save_to_file("fresh_test.dat", &vec![1, 2]).unwrap();
+        let fresh: Option<Vec<u32>> =
+            Data::load_from_file_if_fresh("fresh_test.dat", Duration::from_secs(60));
+        assert_eq!(Some(vec![1, 2]), fresh);
+
+        thread::sleep(time::Duration::from_millis(50));
+        let stale: Option<Vec<u32>> =
+            Data::load_from_file_if_fresh("fresh_test.dat", Duration::from_millis(10));
+        assert_eq!(None, stale);
+
+        let missing: Option<Vec<u32>> =
+            Data::load_from_file_if_fresh("missing_test.dat", Duration::from_secs(60));
+        assert_eq!(None, missing);
+        remove_file(wfc.join("fresh_test.dat")).unwrap();
+    }
+
+    #[test]
+    fn it_caches_computed_data() {
+        let wfc = setup_workflow_env_vars(true);
+        let ttl = Duration::from_secs(60);
+
+        let data: Vec<u32> =
+            Data::cached("cached_test.dat", ttl, || Ok::<_, anyhow::Error>(vec![1])).unwrap();
+        assert_eq!(vec![1], data);
+        let data: Vec<u32> = Data::cached("cached_test.dat", ttl, || -> Result<_> {
+            panic!("data should be cached")
+        })
+        .unwrap();
+        assert_eq!(vec![1], data);
+
+        // Stale data is computed again, errors are returned as they are.
+        let data: Result<Vec<u32>> =
+            Data::cached("cached_test.dat", Duration::from_secs(0), || {
+                "x".parse::<u32>().map(|n| vec![n])
+            });
+        assert!(data.is_err());
+        remove_file(wfc.join("cached_test.dat")).unwrap();
+    }
+
+    #[test]
+    fn it_reports_cache_metadata() {
+        let wfc = setup_workflow_env_vars(true);
+
+        assert_eq!(None, Data::cache_age("meta_test.dat"));
+        assert_eq!(None, Data::cache_size("meta_test.dat"));
+        assert_eq!(None, Data::cache_modified("meta_test.dat"));
+
+        Data::save_to_file("meta_test.dat", &"abc").unwrap();
+        assert!(Data::cache_age("meta_test.dat").unwrap() < Duration::from_secs(60));
+        assert_eq!(Some(5), Data::cache_size("meta_test.dat"));
+        assert!(Data::cache_modified("meta_test.dat").unwrap() <= SystemTime::now());
+        remove_file(wfc.join("meta_test.dat")).unwrap();
+    }
+
+    #[test]
+    fn it_cleans_cache_dir() {
+        let wfc = setup_workflow_env_vars(true);
+        std::fs::create_dir(wfc.join("thumbs")).unwrap();
+        std::fs::write(wfc.join("thumbs").join("a.png"), [0; 10]).unwrap();
+        std::fs::write(wfc.join("b.png"), [0; 10]).unwrap();
+        Data::save_to_file("tags.dat", &"tags").unwrap();
+
+        assert_eq!(
+            2,
+            Data::clear_cache_matching(|name| name.ends_with(".png")).unwrap()
+        );
+        assert!(!wfc.join("thumbs").join("a.png").exists());
+        assert_eq!(Some("tags".to_string()), Data::load_from_file("tags.dat"));
+
+        std::fs::write(wfc.join("old.dat"), [0; 100]).unwrap();
+        thread::sleep(time::Duration::from_millis(1100));
+        std::fs::write(wfc.join("new.dat"), [0; 100]).unwrap();
+        // Over the size limit: oldest files go first.
+        assert_eq!(2, Data::prune_cache(None, Some(150)).unwrap());
+        assert!(wfc.join("new.dat").exists());
+        assert_eq!(
+            0,
+            Data::prune_cache(Some(Duration::from_secs(60)), None).unwrap()
+        );
+        assert_eq!(
+            1,
+            Data::prune_cache(Some(Duration::from_secs(0)), None).unwrap()
+        );
+
+        Data::save_to_file("tags.dat", &"tags").unwrap();
+        assert_eq!(1, Data::clear_cache().unwrap());
+        assert_eq!(None, Data::load_from_file::<_, String>("tags.dat"));
+    }
+
+    #[test]
+    fn it_keeps_files_in_use_when_clearing_cache() {
+        let wfc = setup_workflow_env_vars(true);
+        Data::save_to_file("tags.dat", &"tags").unwrap();
+        let _lock = FileLock::exclusive(&wfc.join("tags.dat")).unwrap();
+        let temp = wfc.join(format!("{}abcde.json", TEMP_FN_PREFIX));
+        std::fs::write(&temp, "{}").unwrap();
+        let updater_dir = wfc.join(UPDATER_CACHE_DIR);
+        std::fs::create_dir(&updater_dir).unwrap();
+        let updater_state = updater_dir.join("workflow-updater.json");
+        std::fs::write(&updater_state, "{}").unwrap();
+
+        assert_eq!(1, Data::clear_cache().unwrap());
+        assert!(!wfc.join("tags.dat").exists());
+        assert!(wfc.join("tags.dat.lock").exists());
+        assert!(temp.exists());
+        assert!(updater_state.exists());
+        assert_eq!(
+            0,
+            Data::prune_cache(Some(Duration::from_secs(0)), None).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_keeps_data_of_current_session() {
+        let wfc = setup_workflow_env_vars(true);
+        StdEnv::remove_var(SESSION_ID_VAR);
+        assert!(Data::session().is_err());
+
+        StdEnv::set_var(SESSION_ID_VAR, "session-1");
+        let mut session = Data::session().unwrap();
+        session.set("page", &2).unwrap();
+        assert_eq!(Some(2), Data::session().unwrap().get("page"));
+
+        StdEnv::set_var(SESSION_ID_VAR, "session-2");
+        let session = Data::session().unwrap();
+        assert!(session.is_empty());
+        assert!(!wfc.join("alfred_rs_session_session-1.json").exists());
+        StdEnv::remove_var(SESSION_ID_VAR);
+    }
+
+    #[test]
+    fn it_saves_loads_blobs() {
+        let wfc = setup_workflow_env_vars(true);
+        let bytes = [0x89, b'P', b'N', b'G', 0, 0xff];
+
+        assert_eq!(None, Data::load_blob("blob_test.png"));
+        Data::save_blob("blob_test.png", &bytes).unwrap();
+        assert_eq!(Some(bytes.to_vec()), Data::load_blob("blob_test.png"));
+        assert_eq!(
+            wfc.join("blob_test.png"),
+            Data::blob_path("blob_test.png").unwrap()
+        );
+        remove_file(wfc.join("blob_test.png")).unwrap();
+    }
+
+    #[test]
+    fn it_saves_to_sub_directories() {
+        let wfc = setup_workflow_env_vars(true);
+
+        Data::save_to_file("queries/./rust.json", &vec!["alfred"]).unwrap();
+        assert!(wfc.join("queries").join("rust.json").exists());
+        let cached: Option<Vec<String>> = Data::load_from_file("queries/rust.json");
+        assert_eq!(Some(vec!["alfred".to_string()]), cached);
+        assert_eq!(None, Data::load_from_file::<_, Vec<String>>("rust.json"));
+
+        let mut wf_data = Data::load("accounts/work.json").unwrap();
+        wf_data.set("user", &"me").unwrap();
+        assert!(wfc.join("accounts").join("work.json").exists());
+
+        assert!(Data::save_to_file("../escape.json", &0).is_err());
+        assert!(Data::save_to_file("queries/../../escape.json", &0).is_err());
+        Data::save_to_file(wfc.join("queries").join("go.json"), &0).unwrap();
+        assert_eq!(Some(0), Data::load_from_file("queries/go.json"));
+        assert!(Data::load("/tmp/settings.json").is_err());
+        assert!(Data::load(".").is_err());
+    }
+
+    #[test]
+    fn it_saves_loads_from_data_file() {
+        let wfd = setup_workflow_env_vars(true);
+        let wfc = Builder::new().tempdir().unwrap();
+        StdEnv::set_var("alfred_workflow_cache", wfc.path());
+
+        Data::save_to_data_file("bookmarks.json", &vec!["alfred"]).unwrap();
+        assert!(wfd.join("bookmarks.json").exists());
+        assert!(!wfc.path().join("bookmarks.json").exists());
+
+        let bookmarks: Option<Vec<String>> = Data::load_from_data_file("bookmarks.json");
+        assert_eq!(Some(vec!["alfred".to_string()]), bookmarks);
+        assert_eq!(
+            None,
+            Data::load_from_file::<_, Vec<String>>("bookmarks.json")
+        );
+    }
+
     #[test]
     fn it_overwrites_cached_data_file() {
         let wfc = setup_workflow_env_vars(true);
@@ -355,6 +4355,8 @@ mod tests {
 
     pub(super) fn setup_workflow_env_vars(secure_temp_dir: bool) -> PathBuf {
         // Mimic Alfred's environment variables
+        // `TempDir::keep()` replaces `into_path()` from tempfile 3.20 on, which needs Rust 1.63.
+        #[allow(deprecated)]
         let path = if secure_temp_dir {
             Builder::new()
                 .prefix("alfred_workflow_test")
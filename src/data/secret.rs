@@ -0,0 +1,91 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// What secrets are displayed as.
+const REDACTED: &str = "[REDACTED]";
+
+/// A value, such as a password or an API token, that is never displayed
+///
+/// `Secret` is displayed (and debug-printed) as `[REDACTED]`, so secrets don't end up in the
+/// workflow's logs or in Alfred's debugger when they are logged by mistake, or as part of a
+/// struct that derives `Debug`. The value itself is only available through [`expose()`], which
+/// makes the places that use it easy to find.
+///
+/// It is (de)serialized as the value it holds, so secrets can be read with [`Data::get()`] and
+/// stored in structs saved to data files. Secrets read from the [`Keychain`] are returned as
+/// `Secret`s.
+///
+/// # Example
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// use alfred_rs::data::Secret;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Account {
+///     user: String,
+///     token: Secret<String>,
+/// }
+///
+/// # fn main() {
+/// let account: Account = serde_json::from_str(r#"{"user": "me", "token": "b3f1"}"#).unwrap();
+/// assert_eq!(
+///     r#"Account { user: "me", token: [REDACTED] }"#,
+///     format!("{:?}", account)
+/// );
+/// assert_eq!("b3f1", account.token.expose());
+/// # }
+/// ```
+///
+/// [`expose()`]: struct.Secret.html#method.expose
+/// [`Data::get()`]: struct.Data.html#method.get
+/// [`Keychain`]: struct.Keychain.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap `value`
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// The value of the secret
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the value of the secret
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
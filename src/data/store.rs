@@ -0,0 +1,317 @@
+use super::{serde_json, Data, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs::create_dir_all;
+use std::path::Path;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS entries (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL,
+    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+)";
+
+/// A key/value store in an SQLite database, for workflows with many records
+///
+/// Unlike [`Data`], which reads and rewrites its whole file, `Store` only reads and writes the
+/// records it is asked for. This keeps workflows that index tens of thousands of records
+/// (bookmarks, notes, ...) fast. Values are stored as JSON, same as in [`Data`].
+///
+/// Besides getting and setting records by key, records can be looked up by key prefix
+/// ([`scan_prefix()`]) or by text ([`search()`]). For anything else, the underlying
+/// [`Connection`] is available through [`connection()`].
+///
+/// # Example
+/// ```rust,ignore
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// use alfred_rs::data::Store;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Bookmark {
+///     title: String,
+///     url: String,
+/// }
+///
+/// # fn main() {
+/// // Needs the `sqlite` feature
+/// let store = Store::open("bookmarks.sqlite").unwrap();
+/// let bookmark = Bookmark {
+///     title: "Alfred".to_string(),
+///     url: "https://www.alfredapp.com".to_string(),
+/// };
+/// store.set("bookmark:alfred", &bookmark).unwrap();
+///
+/// let found: Vec<(String, Bookmark)> = store.search("alfred", 20).unwrap();
+/// # }
+/// ```
+///
+/// [`Data`]: struct.Data.html
+/// [`scan_prefix()`]: struct.Store.html#method.scan_prefix
+/// [`search()`]: struct.Store.html#method.search
+/// [`connection()`]: struct.Store.html#method.connection
+/// [`Connection`]: https://docs.rs/rusqlite/0.31/rusqlite/struct.Connection.html
+#[derive(Debug)]
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (or creates) the database `p` in workflow's data dir
+    ///
+    /// Same as [`Data::load()`], `p` is relative to the data dir.
+    ///
+    /// # Errors
+    ///
+    /// If the data dir is not set, or the database can't be opened or created.
+    ///
+    /// [`Data::load()`]: struct.Data.html#method.load
+    pub fn open<P: AsRef<Path>>(p: P) -> Result<Self> {
+        let p = Data::data_file_path(p.as_ref())?;
+        if let Some(dir) = p.parent() {
+            create_dir_all(dir)?;
+        }
+        debug!("opening store: {:?}", p);
+        Self::with_connection(Connection::open(p)?)
+    }
+
+    /// Opens a store that only lives in memory, handy for tests
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be created.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute(SCHEMA, [])?;
+        Ok(Store { conn })
+    }
+
+    /// Set the value of key `k` to `v`
+    ///
+    /// # Errors
+    ///
+    /// If `v` cannot be serialized or the database can't be written to.
+    pub fn set<K, V>(&self, k: K, v: &V) -> Result<()>
+    where
+        K: AsRef<str>,
+        V: Serialize,
+    {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO entries (key, value) VALUES (?1, ?2)",
+            params![k.as_ref(), serde_json::to_string(v)?],
+        )?;
+        Ok(())
+    }
+
+    /// Set multiple key/value pairs in a single transaction
+    ///
+    /// Much faster than calling [`set()`] for each pair when (re)building an index. Either all
+    /// pairs are set or none is.
+    ///
+    /// # Errors
+    ///
+    /// If a value cannot be serialized or the database can't be written to.
+    ///
+    /// [`set()`]: struct.Store.html#method.set
+    pub fn set_many<I, K, V>(&mut self, pairs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Serialize,
+    {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO entries (key, value) VALUES (?1, ?2)")?;
+            for (k, v) in pairs {
+                stmt.execute(params![k.as_ref(), serde_json::to_string(&v)?])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get the value of key `k`
+    ///
+    /// Same as [`Data::get()`], `None` is returned if the key is not set or its value cannot be
+    /// deserialized to type `V`.
+    ///
+    /// [`Data::get()`]: struct.Data.html#method.get
+    pub fn get<K, V>(&self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM entries WHERE key = ?1",
+                params![k.as_ref()],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()?;
+        serde_json::from_str(&value?).ok()
+    }
+
+    /// Remove key `k`, returning whether it was set
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be written to.
+    pub fn remove<K: AsRef<str>>(&self, k: K) -> Result<bool> {
+        let removed = self
+            .conn
+            .execute("DELETE FROM entries WHERE key = ?1", params![k.as_ref()])?;
+        Ok(removed > 0)
+    }
+
+    /// Number of records in the store
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be read.
+    pub fn len(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Returns `true` if the store has no records
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be read.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Records whose key starts with `prefix`, ordered by key
+    ///
+    /// Records whose value cannot be deserialized to type `V` are skipped. Prefixes make it
+    /// easy to keep several kinds of records in one store (`bookmark:...`, `tag:...`).
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be read.
+    pub fn scan_prefix<V>(&self, prefix: &str) -> Result<Vec<(String, V)>>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        self.query(
+            "SELECT key, value FROM entries WHERE key LIKE ?1 ESCAPE '\\' ORDER BY key",
+            &[&format!("{}%", escape_like(prefix))],
+        )
+    }
+
+    /// At most `limit` records whose key or value contains `text` (case-insensitive for ASCII
+    /// letters), most recently set first
+    ///
+    /// Records whose value cannot be deserialized to type `V` are skipped.
+    ///
+    /// # Errors
+    ///
+    /// If the database can't be read.
+    pub fn search<V>(&self, text: &str, limit: usize) -> Result<Vec<(String, V)>>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        let pattern = format!("%{}%", escape_like(text));
+        self.query(
+            "SELECT key, value FROM entries
+             WHERE key LIKE ?1 ESCAPE '\\' OR value LIKE ?1 ESCAPE '\\'
+             ORDER BY updated_at DESC, key LIMIT ?2",
+            &[&pattern, &(limit as i64)],
+        )
+    }
+
+    /// The underlying SQLite connection, for queries that the store doesn't provide
+    ///
+    /// Records are kept in table `entries` with columns `key`, `value` (JSON text) and
+    /// `updated_at` (Unix timestamp).
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    fn query<V>(&self, sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<(String, V)>>
+    where
+        V: for<'d> Deserialize<'d>,
+    {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (k, v) = row?;
+            if let Ok(v) = serde_json::from_str(&v) {
+                records.push((k, v));
+            }
+        }
+        Ok(records)
+    }
+}
+
+// Escape the wildcards of SQL's `LIKE` in `s`.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::setup_workflow_env_vars;
+    use super::*;
+
+    #[test]
+    fn it_stores_records() {
+        let wfd = setup_workflow_env_vars(true);
+
+        let mut store = Store::open("store_test.sqlite").unwrap();
+        assert!(wfd.join("store_test.sqlite").exists());
+        assert!(store.is_empty().unwrap());
+
+        store
+            .set("bookmark:alfred", &"https://www.alfredapp.com")
+            .unwrap();
+        store
+            .set_many(vec![
+                ("bookmark:rust", "https://www.rust-lang.org"),
+                ("bookmark:100%_rust", "https://crates.io"),
+                ("tag:rust", "programming"),
+            ])
+            .unwrap();
+        assert_eq!(4, store.len().unwrap());
+        assert_eq!(Some("programming".to_string()), store.get("tag:rust"));
+        assert_eq!(None, store.get::<_, u32>("tag:rust"));
+        assert_eq!(None, store.get::<_, String>("tag:alfred"));
+
+        let bookmarks: Vec<(String, String)> = store.scan_prefix("bookmark:").unwrap();
+        assert_eq!(3, bookmarks.len());
+        let bookmarks: Vec<(String, String)> = store.scan_prefix("bookmark:100%_").unwrap();
+        assert_eq!(
+            vec![(
+                "bookmark:100%_rust".to_string(),
+                "https://crates.io".to_string()
+            )],
+            bookmarks
+        );
+
+        let found: Vec<(String, String)> = store.search("RUST", 10).unwrap();
+        assert_eq!(3, found.len());
+        let found: Vec<(String, String)> = store.search("rust", 1).unwrap();
+        assert_eq!(1, found.len());
+
+        assert!(store.remove("tag:rust").unwrap());
+        assert!(!store.remove("tag:rust").unwrap());
+        drop(store);
+
+        let store = Store::open("store_test.sqlite").unwrap();
+        assert_eq!(3, store.len().unwrap());
+    }
+}
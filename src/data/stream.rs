@@ -0,0 +1,61 @@
+use super::serde_json;
+use serde::Deserialize;
+use std::io::BufRead;
+use std::marker::PhantomData;
+
+/// Iterator over the items of a file saved by [`Data::save_iter_to_file()`]
+///
+/// Items are deserialized one at a time as the iterator advances, so only the current item
+/// is held in memory. Items that cannot be deserialized to `V` are skipped.
+///
+/// Created by [`Data::load_iter_from_file()`].
+///
+/// [`Data::save_iter_to_file()`]: struct.Data.html#method.save_iter_to_file
+/// [`Data::load_iter_from_file()`]: struct.Data.html#method.load_iter_from_file
+pub struct ItemReader<V> {
+    lines: std::io::Lines<Box<dyn BufRead>>,
+    item: PhantomData<fn() -> V>,
+}
+
+impl<V> ItemReader<V> {
+    pub(super) fn new(reader: Box<dyn BufRead>) -> Self {
+        ItemReader {
+            lines: reader.lines(),
+            item: PhantomData,
+        }
+    }
+}
+
+impl<V> std::fmt::Debug for ItemReader<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ItemReader").finish()
+    }
+}
+
+impl<V> Iterator for ItemReader<V>
+where
+    V: for<'d> Deserialize<'d>,
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => {
+                    debug!("couldn't read items: {}", e);
+                    return None;
+                }
+            };
+            // Items are written one per line, between the lines of the array's brackets.
+            let item = line.trim().trim_end_matches(',');
+            if item.is_empty() || item == "[" || item == "]" {
+                continue;
+            }
+            match serde_json::from_str(item) {
+                Ok(item) => return Some(item),
+                Err(e) => debug!("skipping item: {}", e),
+            }
+        }
+    }
+}
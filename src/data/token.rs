@@ -0,0 +1,190 @@
+use super::{now_millis, serde_json, Keychain, Result, Secret};
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime};
+
+/// An access token (such as one of OAuth) with its expiry, refresh token and scopes
+///
+/// The token and its refresh token are [`Secret`]s, they are never displayed in logs. A
+/// `StoredToken` can be kept in the [`Keychain`] with [`save_to_keychain()`], or in a data file
+/// like any other value, preferably one that is [encrypted].
+///
+/// # Example
+/// ```rust,no_run
+/// use alfred_rs::data::{Keychain, StoredToken};
+/// use std::time::Duration;
+///
+/// let keychain = Keychain::for_workflow().unwrap();
+///
+/// let token = match StoredToken::load_from_keychain(&keychain, "github").unwrap() {
+///     Some(token) if !token.expires_within(Duration::from_secs(60)) => token,
+///     _ => {
+///         // values of the response of the authorization server
+///         let token = StoredToken::new("gho_16C7e42F292c")
+///             .with_expiry(Duration::from_secs(28800))
+///             .with_refresh_token("ghr_1B4a2e77838347")
+///             .with_scopes(vec!["repo", "gist"]);
+///         token.save_to_keychain(&keychain, "github").unwrap();
+///         token
+///     }
+/// };
+/// // call the API with `token.value().expose()`
+/// ```
+///
+/// [`Secret`]: struct.Secret.html
+/// [`Keychain`]: struct.Keychain.html
+/// [`save_to_keychain()`]: struct.StoredToken.html#method.save_to_keychain
+/// [encrypted]: format/struct.Encrypted.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+    value: Secret<String>,
+    // Milliseconds since Unix epoch, same as expiry of keys of `Data`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<Secret<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    scopes: Vec<String>,
+}
+
+impl StoredToken {
+    /// Token `value`, which never expires and has no refresh token nor scopes
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        StoredToken {
+            value: Secret::new(value.into()),
+            expires_at: None,
+            refresh_token: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Make the token expire `ttl` from now (usually the `expires_in` of an OAuth response)
+    pub fn with_expiry(mut self, ttl: Duration) -> Self {
+        let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        self.expires_at = Some(now_millis().saturating_add(ttl));
+        self
+    }
+
+    /// Set the refresh token of the token
+    pub fn with_refresh_token<S: Into<String>>(mut self, refresh_token: S) -> Self {
+        self.refresh_token = Some(Secret::new(refresh_token.into()));
+        self
+    }
+
+    /// Set the scopes the token was granted
+    pub fn with_scopes<I, S>(mut self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The token
+    pub fn value(&self) -> &Secret<String> {
+        &self.value
+    }
+
+    /// When the token expires, `None` if it doesn't
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+            .map(|expires_at| SystemTime::UNIX_EPOCH + Duration::from_millis(expires_at))
+    }
+
+    /// Returns `true` if the token has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::from_secs(0))
+    }
+
+    /// Returns `true` if the token has expired or expires within `margin`
+    ///
+    /// Useful to refresh tokens a bit before they expire, so that they don't expire while a
+    /// request is made.
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        let margin = u64::try_from(margin.as_millis()).unwrap_or(u64::MAX);
+        self.expires_at.map_or(false, |expires_at| {
+            expires_at <= now_millis().saturating_add(margin)
+        })
+    }
+
+    /// The refresh token, if the token has one
+    pub fn refresh_token(&self) -> Option<&Secret<String>> {
+        self.refresh_token.as_ref()
+    }
+
+    /// The scopes the token was granted
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    /// Returns `true` if the token was granted `scope`
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Save the token to `keychain`, as the secret of `account`
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be written.
+    pub fn save_to_keychain(&self, keychain: &Keychain, account: &str) -> Result<()> {
+        keychain.set(account, &serde_json::to_string(self)?)
+    }
+
+    /// Load the token saved to `keychain` as the secret of `account`, `None` if there is none
+    ///
+    /// # Errors
+    ///
+    /// If the Keychain can't be read, or the secret of `account` is not a token.
+    pub fn load_from_keychain(keychain: &Keychain, account: &str) -> Result<Option<Self>> {
+        match keychain.get(account)? {
+            Some(secret) => Ok(Some(serde_json::from_str(secret.expose())?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::setup_workflow_env_vars;
+    use super::super::Data;
+    use super::*;
+
+    #[test]
+    fn it_stores_tokens() {
+        let wfd = setup_workflow_env_vars(true);
+        let _ = std::fs::remove_file(wfd.join("token_test.json"));
+
+        let token = StoredToken::new("gho_16C7e42F292c")
+            .with_expiry(Duration::from_secs(3600))
+            .with_refresh_token("ghr_1B4a2e77838347")
+            .with_scopes(vec!["repo", "gist"]);
+        assert!(!token.is_expired());
+        assert!(token.expires_within(Duration::from_secs(3601)));
+        assert!(token.has_scope("gist"));
+        assert!(!token.has_scope("user"));
+        assert!(!format!("{:?}", token).contains("gh"));
+
+        let mut wf_data = Data::load("token_test.json").unwrap();
+        wf_data.set("github", &token).unwrap();
+        let mut wf_data = Data::load("token_test.json").unwrap();
+        let loaded: StoredToken = wf_data.get("github").unwrap();
+        assert_eq!(token, loaded);
+        assert_eq!(
+            "ghr_1B4a2e77838347",
+            loaded.refresh_token().unwrap().expose()
+        );
+
+        wf_data
+            .set(
+                "github",
+                &StoredToken::new("gho_1").with_expiry(Duration::from_secs(0)),
+            )
+            .unwrap();
+        let expired: StoredToken = wf_data.get("github").unwrap();
+        assert!(expired.is_expired());
+        assert!(expired.scopes().is_empty());
+        assert!(expired.refresh_token().is_none());
+        assert!(!StoredToken::new("gho_1").is_expired());
+    }
+}
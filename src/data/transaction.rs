@@ -0,0 +1,136 @@
+use super::{serde_json, Data, Result};
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Changes to a [`Data`] that are saved all together or not at all, see
+/// [`Data::transaction()`]
+///
+/// Values read through a transaction include the changes it has made so far.
+///
+/// [`Data`]: struct.Data.html
+/// [`Data::transaction()`]: struct.Data.html#method.transaction
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    data: &'a mut Data,
+    // State of `data` before the transaction, restored unless it is committed.
+    inner: HashMap<String, Value>,
+    expires: HashMap<String, u64>,
+    modified: HashMap<String, u64>,
+    changed: HashSet<String>,
+    increments: HashMap<String, i64>,
+    cleared: bool,
+    deferred: bool,
+    dirty: bool,
+    committed: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(super) fn new(data: &'a mut Data) -> Self {
+        let txn = Transaction {
+            inner: data.inner.clone(),
+            expires: data.expires.clone(),
+            modified: data.modified.clone(),
+            changed: data.changed.clone(),
+            increments: data.increments.clone(),
+            cleared: data.cleared,
+            deferred: data.deferred,
+            dirty: data.dirty,
+            committed: false,
+            data,
+        };
+        txn.data.deferred = true;
+        txn
+    }
+
+    // Write the changes of the transaction (unless writes are deferred).
+    pub(super) fn commit(mut self) -> Result<()> {
+        self.data.deferred = self.deferred;
+        if !self.deferred {
+            self.data.save()?;
+        }
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Set the value of key `k` to `v`, see [`Data::set()`]
+    ///
+    /// # Errors
+    ///
+    /// If `v` cannot be serialized.
+    ///
+    /// [`Data::set()`]: struct.Data.html#method.set
+    pub fn set<K, V>(&mut self, k: K, v: &V) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        self.data.set(k, v)
+    }
+
+    /// Set the value of key `k` to `v` for `ttl`, see [`Data::set_with_ttl()`]
+    ///
+    /// # Errors
+    ///
+    /// If `v` cannot be serialized.
+    ///
+    /// [`Data::set_with_ttl()`]: struct.Data.html#method.set_with_ttl
+    pub fn set_with_ttl<K, V>(&mut self, k: K, v: &V, ttl: Duration) -> Result<()>
+    where
+        K: Into<String>,
+        V: Serialize,
+    {
+        self.data.set_with_ttl(k, v, ttl)
+    }
+
+    /// Remove key `k`, returning the value that was stored for it, see [`Data::remove()`]
+    ///
+    /// [`Data::remove()`]: struct.Data.html#method.remove
+    pub fn remove<K>(&mut self, k: K) -> Option<Value>
+    where
+        K: AsRef<str>,
+    {
+        // Writes are deferred during the transaction, removing can't fail.
+        self.data.remove(k).ok().flatten()
+    }
+
+    /// Get (possible) value of key `k`, see [`Data::get()`]
+    ///
+    /// [`Data::get()`]: struct.Data.html#method.get
+    pub fn get<K, V>(&self, k: K) -> Option<V>
+    where
+        K: AsRef<str>,
+        V: for<'d> Deserialize<'d>,
+    {
+        self.data.get(k)
+    }
+
+    /// Returns `true` if key `k` is set, see [`Data::contains_key()`]
+    ///
+    /// [`Data::contains_key()`]: struct.Data.html#method.contains_key
+    pub fn contains_key<K>(&self, k: K) -> bool
+    where
+        K: AsRef<str>,
+    {
+        self.data.contains_key(k)
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.committed {
+            debug!("rolling back transaction on {:?}", self.data.file_name);
+            let data = &mut *self.data;
+            data.inner = std::mem::take(&mut self.inner);
+            data.expires = std::mem::take(&mut self.expires);
+            data.modified = std::mem::take(&mut self.modified);
+            data.changed = std::mem::take(&mut self.changed);
+            data.increments = std::mem::take(&mut self.increments);
+            data.cleared = self.cleared;
+            data.deferred = self.deferred;
+            data.dirty = self.dirty;
+        }
+    }
+}
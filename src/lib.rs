@@ -14,6 +14,24 @@
 //! - Set up automatic update of workflow ([`updater`] module).
 //! - Painlessly read/write data related to workflow (settings, cache data, ...) ([`data`] module).
 //!
+//! ## Cargo features
+//! - `updater` (default): the [`updater`] module, using `reqwest` for network operations.
+//! - `updater-ureq`: the same [`updater`] module built on the much lighter `ureq` HTTP client.
+//!   Use it with `default-features = false` to keep `reqwest` and `tokio` out of your workflow.
+//!   If `updater` is enabled too (such as with `--all-features`), `reqwest` is used.
+//! - `updater-async`: adds an async API to the [`updater`] (see `Updater::as_async()`), for
+//!   workflows that already run on `tokio`.
+//! - `derive`: `#[derive(WorkflowConfig)]` for workflow settings structs (see
+//!   `data::WorkflowConfig`).
+//! - `sqlite`: `data::Store`, a key/value store in an SQLite database for workflows with many
+//!   records.
+//! - `async`: `data::AsyncData`, an async API to the [`data`] module for workflows that run on
+//!   `tokio`.
+//! - `encryption`: `data::format::Encrypted`, to encrypt data files with a key kept in the
+//!   Keychain.
+//! - `touch-id`: `data::authenticate()`, to ask the user for Touch ID (or their password) before
+//!   releasing a secret or running a destructive command.
+//!
 //! [`updater`]: updater/index.html
 //! [`data`]: data/index.html
 //! [alfred]: https://crates.io/crates/alfred
@@ -21,8 +39,6 @@
 //! [Workflows]: https://www.alfredapp.com/workflows/
 //!
 
-// TODO: check for "status" field of json returned by github to make sure it is fully uploaded
-// before reporting that a release is available.
 // TODO: Automatically update html_root_url's version when publishing to crates.io
 // TODO: Use https://github.com/softprops/hubcaps for github API?
 
@@ -50,7 +66,14 @@ use anyhow::Result;
 use anyhow::{anyhow, bail};
 
 pub mod data;
+mod lock;
 pub mod updater;
 
 pub use self::data::Data;
 pub use self::updater::Updater;
+
+// Used by code generated by `alfred-rs-derive`.
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow::Result;
+}
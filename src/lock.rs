@@ -0,0 +1,123 @@
+use super::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Advisory lock guarding a file against concurrent invocations of the workflow, held until
+// dropped. The lock is taken on a separate `<file>.lock` file since the guarded file itself is
+// replaced (renamed over) when it is saved.
+pub(crate) struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    // Lock for reading `p`, shared with other readers.
+    pub(crate) fn shared(p: &Path) -> Result<Self> {
+        Self::acquire(p, false)
+    }
+
+    // Lock for writing `p`, exclusive of readers and other writers.
+    pub(crate) fn exclusive(p: &Path) -> Result<Self> {
+        Self::acquire(p, true)
+    }
+
+    fn acquire(p: &Path, exclusive: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::lock_path(p))?;
+        Self::lock(&file, exclusive)?;
+        Ok(FileLock { _file: file })
+    }
+
+    // Delete `p` and its lock file, holding the lock (if there is one) while `p` is deleted.
+    // Returns `false` if `p` didn't exist.
+    pub(crate) fn remove(p: &Path) -> Result<bool> {
+        let lock_path = Self::lock_path(p);
+        let lock = if lock_path.exists() {
+            Some(Self::exclusive(p)?)
+        } else {
+            None
+        };
+        let removed = match fs::remove_file(p) {
+            Ok(()) => true,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        if lock.is_some() {
+            fs::remove_file(lock_path)?;
+        }
+        Ok(removed)
+    }
+
+    fn lock_path(p: &Path) -> PathBuf {
+        let mut name = p.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        p.with_file_name(name)
+    }
+
+    // The lock is released when the file is closed.
+    #[cfg(unix)]
+    fn lock(file: &File, exclusive: bool) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+        let operation = if exclusive {
+            libc::LOCK_EX
+        } else {
+            libc::LOCK_SH
+        };
+        // SAFETY: `file` owns a valid open file descriptor for the duration of the call.
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn lock(_file: &File, _exclusive: bool) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::Builder;
+
+    #[test]
+    fn it_serializes_writers() {
+        let dir = Builder::new().prefix("alfred_lock_test").tempdir().unwrap();
+        let p = dir.path().join("state.json");
+
+        let lock = FileLock::exclusive(&p).unwrap();
+        assert!(dir.path().join("state.json.lock").exists());
+
+        let (tx, rx) = mpsc::channel();
+        let other = p.clone();
+        let handle = thread::spawn(move || {
+            let _lock = FileLock::shared(&other).unwrap();
+            tx.send(()).unwrap();
+        });
+        // Reader has to wait for the writer to finish.
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(lock);
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn it_removes_lock_files() {
+        let dir = Builder::new().prefix("alfred_lock_test").tempdir().unwrap();
+        let p = dir.path().join("state.json");
+        assert!(!FileLock::remove(&p).unwrap());
+
+        std::fs::write(&p, "{}").unwrap();
+        drop(FileLock::exclusive(&p).unwrap());
+        assert!(FileLock::remove(&p).unwrap());
+        assert!(!p.exists());
+        assert!(!dir.path().join("state.json.lock").exists());
+    }
+}
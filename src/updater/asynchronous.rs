@@ -0,0 +1,129 @@
+use super::bundle::validate_workflow_bundle;
+use super::imp::UpdateInfo;
+use super::{anyhow, GithubReleaser, PathBuf, Releaser, Result, Utc};
+use crate::Updater;
+
+/// Async API of an [`Updater`] that checks `github.com` for new releases.
+///
+/// Workflows that already use `tokio` (for example to talk to their own APIs) can use this
+/// instead of the blocking methods and the background thread of [`Updater::init()`]. Network
+/// requests and file operations run on tokio's blocking thread pool, through the same code as
+/// the blocking methods: the same [`network settings`] are honored and the results are saved to
+/// the same files, so [`Updater::update_ready()`], [`Updater::latest_available()`], ... report
+/// what the last async check found.
+///
+/// Get one by calling [`Updater::as_async()`]. Available with the `updater-async` feature.
+///
+/// Like `Updater`, the returned futures are not `Send`: await them on the task that owns the
+/// updater (or use `block_on`), rather than spawning them.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # extern crate alfred_rs;
+/// # use anyhow::Result;
+/// use alfred_rs::Updater;
+///
+/// # async fn run() -> Result<()> {
+/// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+/// if updater.as_async().check().await? {
+///     let downloaded = updater.as_async().download_latest().await?;
+///     println!("new release saved to {}", downloaded.display());
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// [`Updater`]: struct.Updater.html
+/// [`Updater::init()`]: struct.Updater.html#method.init
+/// [`network settings`]: struct.NetworkSettings.html
+/// [`Updater::update_ready()`]: struct.Updater.html#method.update_ready
+/// [`Updater::latest_available()`]: struct.Updater.html#method.latest_available
+/// [`Updater::as_async()`]: struct.Updater.html#method.as_async
+pub struct AsyncUpdater<'a> {
+    updater: &'a Updater<GithubReleaser>,
+}
+
+impl Updater<GithubReleaser> {
+    /// Returns the async API of this updater.
+    ///
+    /// See [`AsyncUpdater`]. Available with the `updater-async` feature.
+    ///
+    /// [`AsyncUpdater`]: struct.AsyncUpdater.html
+    pub fn as_async(&self) -> AsyncUpdater<'_> {
+        AsyncUpdater { updater: self }
+    }
+}
+
+impl<'a> AsyncUpdater<'a> {
+    /// Checks `github.com` for the latest release and returns `true` if it is newer than the
+    /// workflow's version.
+    ///
+    /// Unlike [`Updater::init()`], the check is made right away, regardless of the update
+    /// interval, and its outcome is saved the same way as the background checks do. Checks
+    /// are never made if users turned them off (see [`Updater::is_enabled()`]). A failed check
+    /// is retried according to the [`retry policy`].
+    ///
+    /// # Errors
+    /// Network errors, errors reported by `github.com` or errors saving the updater's state
+    /// are returned.
+    ///
+    /// [`Updater::init()`]: struct.Updater.html#method.init
+    /// [`Updater::is_enabled()`]: struct.Updater.html#method.is_enabled
+    /// [`retry policy`]: struct.Updater.html#method.set_retry
+    pub async fn check(&self) -> Result<bool> {
+        if !self.updater.checks_enabled() {
+            return Ok(false);
+        }
+        // Don't keep the releaser borrowed while waiting for the server.
+        let releaser = self.updater.releaser.borrow().clone();
+        releaser.forget_latest_release();
+        let retry_policy = self.updater.retry_policy();
+        let outcome = tokio::task::spawn_blocking(move || {
+            retry_policy.run(|| releaser.fetch_latest_release())
+        })
+        .await?
+        .map(|release| {
+            let mut info = UpdateInfo::new(&release);
+            info.set_fetched_at(Utc::now());
+            info
+        });
+        self.updater.record_check(outcome)
+    }
+
+    /// Downloads the latest available release into workflow's cache dir.
+    ///
+    /// Works the same as [`Updater::download_latest()`]: interrupted downloads are resumed,
+    /// download mirrors are tried and the downloaded file is checked to be a workflow bundle
+    /// matching the release's checksum.
+    ///
+    /// # Errors
+    /// Same as [`Updater::download_latest()`].
+    ///
+    /// [`Updater::download_latest()`]: struct.Updater.html#method.download_latest
+    pub async fn download_latest(&self) -> Result<PathBuf> {
+        let url = self
+            .updater
+            .state
+            .download_url()
+            .ok_or_else(|| anyhow!("no release info avail yet"))?;
+        let checksum = self.updater.state.download_checksum();
+        let p = Updater::<GithubReleaser>::release_download_fn(None)?;
+        if self.updater.dry_run() {
+            debug!("  dry run, not downloading {} to {:?}", url, p);
+            return Ok(p);
+        }
+        let network = self.updater.network().clone();
+        tokio::task::spawn_blocking(move || {
+            Updater::<GithubReleaser>::download_with(
+                &network,
+                &url,
+                checksum.as_deref(),
+                p,
+                &validate_workflow_bundle,
+            )
+        })
+        .await?
+    }
+}
@@ -0,0 +1,388 @@
+use super::location::StateLocation;
+use super::{
+    anyhow, parse_lenient, Duration, GithubReleaser, RedirectPolicy, Releaser, Result, RetryPolicy,
+    StateDir, UpdatePolicy, Updater,
+};
+
+/// Builder for configuring an [`Updater`] before it is created.
+///
+/// Settings are applied before the updater's state file is written for the first time, as
+/// opposed to mutating the updater after construction with methods such as
+/// [`set_interval()`] or [`set_version()`].
+///
+/// Use [`Updater::builder()`] for workflows hosted on `github.com`, or [`UpdaterBuilder::new()`]
+/// for other [`Releaser`]s.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate alfred_rs;
+/// # extern crate chrono;
+/// # use anyhow::Result;
+/// use alfred_rs::Updater;
+/// use chrono::Duration;
+/// # use std::env;
+///
+/// # fn run() -> Result<()> {
+/// # env::set_var("alfred_workflow_uid", "abcdef");
+/// # env::set_var("alfred_workflow_data", env::temp_dir());
+/// # env::set_var("alfred_workflow_cache", env::temp_dir());
+/// let updater = Updater::builder("spamwax/alfred-pinboard-rs")
+///     .interval(Duration::days(7))
+///     .version("1.2.3")
+///     .channel("beta")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// # fn main() {
+/// # run().unwrap();
+/// # }
+/// ```
+///
+/// [`Updater`]: struct.Updater.html
+/// [`Updater::builder()`]: struct.Updater.html#method.builder
+/// [`UpdaterBuilder::new()`]: #method.new
+/// [`Releaser`]: trait.Releaser.html
+/// [`set_interval()`]: struct.Updater.html#method.set_interval
+/// [`set_version()`]: struct.Updater.html#method.set_version
+pub struct UpdaterBuilder<T> {
+    releaser: T,
+    version: Option<String>,
+    interval: Option<Duration>,
+    interval_jitter: Option<Duration>,
+    notify_interval: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    update_policy: Option<UpdatePolicy>,
+    network_timeout: Option<std::time::Duration>,
+    proxy: Option<String>,
+    mirrors: Vec<String>,
+    redirect_policy: Option<RedirectPolicy>,
+    download_hosts: Vec<String>,
+    auto_install: Option<bool>,
+    detached_check: Option<bool>,
+    dry_run: Option<bool>,
+    state_dir: StateDir,
+    state_file_name: Option<String>,
+}
+
+impl<T> UpdaterBuilder<T>
+where
+    T: Releaser + Send + 'static,
+{
+    /// Create a builder for an `Updater` that uses releaser `T` identified by `repo_name`.
+    ///
+    /// See [`Updater::new()`].
+    ///
+    /// [`Updater::new()`]: struct.Updater.html#method.new
+    pub fn new<S: Into<String>>(repo_name: S) -> Self {
+        UpdaterBuilder {
+            releaser: T::new(repo_name),
+            version: None,
+            interval: None,
+            interval_jitter: None,
+            notify_interval: None,
+            retry: None,
+            update_policy: None,
+            network_timeout: None,
+            proxy: None,
+            mirrors: Vec::new(),
+            redirect_policy: None,
+            download_hosts: Vec::new(),
+            auto_install: None,
+            detached_check: None,
+            dry_run: None,
+            state_dir: StateDir::default(),
+            state_file_name: None,
+        }
+    }
+
+    /// Version of the workflow, overriding the one Alfred reports.
+    ///
+    /// See [`Updater::set_version()`].
+    ///
+    /// [`Updater::set_version()`]: struct.Updater.html#method.set_version
+    #[must_use]
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Interval between checks for a newer release.
+    ///
+    /// See [`Updater::set_interval()`].
+    ///
+    /// [`Updater::set_interval()`]: struct.Updater.html#method.set_interval
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Maximum random delay added to the interval between checks.
+    ///
+    /// See [`Updater::set_interval_jitter()`].
+    ///
+    /// [`Updater::set_interval_jitter()`]: struct.Updater.html#method.set_interval_jitter
+    #[must_use]
+    pub fn interval_jitter(mut self, jitter: Duration) -> Self {
+        self.interval_jitter = Some(jitter);
+        self
+    }
+
+    /// Minimum interval between two update prompts.
+    ///
+    /// See [`Updater::set_notify_interval()`].
+    ///
+    /// [`Updater::set_notify_interval()`]: struct.Updater.html#method.set_notify_interval
+    #[must_use]
+    pub fn notify_interval(mut self, interval: Duration) -> Self {
+        self.notify_interval = Some(interval);
+        self
+    }
+
+    /// See [`Updater::set_retry()`].
+    ///
+    /// [`Updater::set_retry()`]: struct.Updater.html#method.set_retry
+    #[must_use]
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// See [`Updater::set_update_policy()`].
+    ///
+    /// [`Updater::set_update_policy()`]: struct.Updater.html#method.set_update_policy
+    #[must_use]
+    pub fn update_policy(mut self, policy: UpdatePolicy) -> Self {
+        self.update_policy = Some(policy);
+        self
+    }
+
+    /// See [`Updater::set_network_timeout()`].
+    ///
+    /// [`Updater::set_network_timeout()`]: struct.Updater.html#method.set_network_timeout
+    #[must_use]
+    pub fn network_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.network_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Updater::set_proxy()`].
+    ///
+    /// [`Updater::set_proxy()`]: struct.Updater.html#method.set_proxy
+    #[must_use]
+    pub fn proxy<S: Into<String>>(mut self, url: S) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Add a download mirror, can be called multiple times.
+    ///
+    /// See [`Updater::add_download_mirror()`].
+    ///
+    /// [`Updater::add_download_mirror()`]: struct.Updater.html#method.add_download_mirror
+    #[must_use]
+    pub fn download_mirror<S: Into<String>>(mut self, template: S) -> Self {
+        self.mirrors.push(template.into());
+        self
+    }
+
+    /// See [`Updater::set_redirect_policy()`].
+    ///
+    /// [`Updater::set_redirect_policy()`]: struct.Updater.html#method.set_redirect_policy
+    #[must_use]
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = Some(policy);
+        self
+    }
+
+    /// Only download releases from `host`, can be called multiple times.
+    ///
+    /// See [`Updater::pin_download_host()`].
+    ///
+    /// [`Updater::pin_download_host()`]: struct.Updater.html#method.pin_download_host
+    #[must_use]
+    pub fn download_host<S: Into<String>>(mut self, host: S) -> Self {
+        self.download_hosts.push(host.into());
+        self
+    }
+
+    /// See [`Updater::set_auto_install()`].
+    ///
+    /// [`Updater::set_auto_install()`]: struct.Updater.html#method.set_auto_install
+    #[must_use]
+    pub fn auto_install(mut self, auto_install: bool) -> Self {
+        self.auto_install = Some(auto_install);
+        self
+    }
+
+    /// See [`Updater::set_detached_check()`].
+    ///
+    /// [`Updater::set_detached_check()`]: struct.Updater.html#method.set_detached_check
+    #[must_use]
+    pub fn detached_check(mut self, detached: bool) -> Self {
+        self.detached_check = Some(detached);
+        self
+    }
+
+    /// Run the updater in dry-run mode, without saving anything (not even the initial state).
+    ///
+    /// See [`Updater::set_dry_run()`].
+    ///
+    /// [`Updater::set_dry_run()`]: struct.Updater.html#method.set_dry_run
+    #[must_use]
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    /// Folder where the updater's state and the status of update checks are saved.
+    ///
+    /// By default they are saved to the workflow's cache folder, which Alfred (or the user)
+    /// may purge, losing track of when the last check was made. Use [`StateDir::Data`] to
+    /// keep them in the workflow's data folder instead.
+    ///
+    /// Since the state is loaded when the `Updater` is created, the location can only be set
+    /// through the builder. Moving the state doesn't carry over a state saved to the old
+    /// location.
+    ///
+    /// [`StateDir::Data`]: enum.StateDir.html#variant.Data
+    #[must_use]
+    pub fn state_dir(mut self, dir: StateDir) -> Self {
+        self.state_dir = dir;
+        self
+    }
+
+    /// Name of the file the updater's state is saved to, instead of one derived from the
+    /// workflow's uid and name. It must be a plain file name, without any folders.
+    ///
+    /// See [`state_dir()`] and [`Updater::state_file()`].
+    ///
+    /// [`state_dir()`]: #method.state_dir
+    /// [`Updater::state_file()`]: struct.Updater.html#method.state_file
+    #[must_use]
+    pub fn state_file_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.state_file_name = Some(name.into());
+        self
+    }
+
+    /// Create the `Updater`.
+    ///
+    /// # Errors
+    /// Besides the errors listed in [`Updater::new()`], building fails if the version, proxy,
+    /// a mirror, a download host or the state file name is invalid.
+    ///
+    /// [`Updater::new()`]: struct.Updater.html#method.new
+    pub fn build(self) -> Result<Updater<T>> {
+        let UpdaterBuilder {
+            releaser,
+            version,
+            interval,
+            interval_jitter,
+            notify_interval,
+            retry,
+            update_policy,
+            network_timeout,
+            proxy,
+            mirrors,
+            redirect_policy,
+            download_hosts,
+            auto_install,
+            detached_check,
+            dry_run,
+            state_dir,
+            state_file_name,
+        } = self;
+        let location = StateLocation::new(state_dir, state_file_name)?;
+        Updater::load_or_new_with(releaser, location, move |updater| {
+            if let Some(version) = version {
+                let v = parse_lenient(&version)
+                    .ok_or_else(|| anyhow!("invalid workflow version: {}", version))?;
+                updater.state.set_version(v);
+            }
+            if let Some(interval) = interval {
+                updater.set_update_interval(interval.num_seconds());
+            }
+            if let Some(jitter) = interval_jitter {
+                updater.set_max_jitter(jitter.num_seconds());
+            }
+            if let Some(interval) = notify_interval {
+                updater.set_notify_interval_secs(interval.num_seconds());
+            }
+            if let Some(policy) = retry {
+                updater.set_retry_policy(policy);
+            }
+            if let Some(policy) = update_policy {
+                updater.set_policy(policy);
+            }
+            if let Some(timeout) = network_timeout {
+                updater.set_network_timeout(timeout);
+            }
+            if let Some(proxy) = proxy {
+                updater.set_proxy(proxy)?;
+            }
+            for mirror in mirrors {
+                updater.add_download_mirror(mirror)?;
+            }
+            if let Some(policy) = redirect_policy {
+                updater.set_redirect_policy(policy);
+            }
+            for host in download_hosts {
+                updater.pin_download_host(host)?;
+            }
+            if let Some(auto_install) = auto_install {
+                updater.set_auto_install_mode(auto_install);
+            }
+            if let Some(detached) = detached_check {
+                updater.set_detached_check_mode(detached);
+            }
+            if let Some(dry_run) = dry_run {
+                updater.set_dry_run_mode(dry_run);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl UpdaterBuilder<GithubReleaser> {
+    /// Release channel to follow: `"stable"` (the default) or the name of a pre-release
+    /// channel such as `"beta"`.
+    ///
+    /// Following a pre-release channel makes the updater also consider pre-releases whose
+    /// version's pre-release identifier starts with the channel's name, e.g. `1.3.0-beta.2`
+    /// for `"beta"`. See [`Updater::set_channel()`].
+    ///
+    /// [`Updater::set_channel()`]: struct.Updater.html#method.set_channel
+    #[must_use]
+    pub fn channel(mut self, channel: &str) -> Self {
+        self.releaser.set_channel(channel);
+        self
+    }
+
+    /// See [`Updater::set_tag_prefix()`].
+    ///
+    /// [`Updater::set_tag_prefix()`]: struct.Updater.html#method.set_tag_prefix
+    #[must_use]
+    pub fn tag_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.releaser.set_tag_prefix(prefix.into());
+        self
+    }
+
+    /// See [`Updater::lenient_versions()`].
+    ///
+    /// [`Updater::lenient_versions()`]: struct.Updater.html#method.lenient_versions
+    #[must_use]
+    pub fn lenient_versions(mut self, lenient: bool) -> Self {
+        self.releaser.set_lenient_versions(lenient);
+        self
+    }
+
+    /// See [`Updater::set_asset_pattern()`].
+    ///
+    /// [`Updater::set_asset_pattern()`]: struct.Updater.html#method.set_asset_pattern
+    #[must_use]
+    pub fn asset_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.releaser.set_asset_pattern(pattern.into());
+        self
+    }
+}
@@ -0,0 +1,110 @@
+use super::{bail, Result};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Signatures of zip records we need to find the names of archived files.
+const END_OF_CENTRAL_DIR_SIG: &[u8] = b"PK\x05\x06";
+const CENTRAL_DIR_HEADER_SIG: &[u8] = b"PK\x01\x02";
+const END_OF_CENTRAL_DIR_LEN: usize = 22;
+const CENTRAL_DIR_HEADER_LEN: usize = 46;
+const MAX_COMMENT_LEN: usize = 0xFFFF;
+
+// Every workflow has this file at the root of its bundle.
+const WORKFLOW_INFO_FN: &str = "info.plist";
+
+// Check that file at `p` is an Alfred workflow bundle, which is a zip archive that contains
+// `info.plist`.
+//
+// Only the archive's central directory (list of files) is read, contents are not decompressed.
+pub(super) fn validate_workflow_bundle(p: &Path) -> Result<()> {
+    let mut fp = File::open(p)?;
+    let file_len = fp.metadata()?.len();
+
+    // End of central directory record is at the end of the archive, followed by a comment.
+    let tail_len = file_len.min((END_OF_CENTRAL_DIR_LEN + MAX_COMMENT_LEN) as u64);
+    fp.seek(SeekFrom::Start(file_len - tail_len))?;
+    let mut tail = Vec::with_capacity(tail_len as usize);
+    fp.by_ref().take(tail_len).read_to_end(&mut tail)?;
+    let eocd = match tail
+        .windows(END_OF_CENTRAL_DIR_SIG.len())
+        .rposition(|w| w == END_OF_CENTRAL_DIR_SIG)
+        .filter(|&pos| pos + END_OF_CENTRAL_DIR_LEN <= tail.len())
+    {
+        Some(pos) => &tail[pos..pos + END_OF_CENTRAL_DIR_LEN],
+        None => bail!("{:?} is not a zip archive", p),
+    };
+    let cd_size = read_u32(eocd, 12);
+    let cd_offset = read_u32(eocd, 16);
+    if u64::from(cd_offset) + u64::from(cd_size) > file_len {
+        bail!("{:?} is a truncated zip archive", p);
+    }
+
+    let mut central_dir = vec![0; usize::try_from(cd_size)?];
+    fp.seek(SeekFrom::Start(u64::from(cd_offset)))?;
+    fp.read_exact(&mut central_dir)?;
+
+    let mut pos = 0;
+    while pos + CENTRAL_DIR_HEADER_LEN <= central_dir.len() {
+        let header = &central_dir[pos..];
+        if &header[..4] != CENTRAL_DIR_HEADER_SIG {
+            break;
+        }
+        let name_len = read_u16(header, 28);
+        let extra_len = read_u16(header, 30);
+        let comment_len = read_u16(header, 32);
+        let name = header
+            .get(CENTRAL_DIR_HEADER_LEN..CENTRAL_DIR_HEADER_LEN + name_len)
+            .unwrap_or_default();
+        if name == WORKFLOW_INFO_FN.as_bytes() {
+            return Ok(());
+        }
+        pos += CENTRAL_DIR_HEADER_LEN + name_len + extra_len + comment_len;
+    }
+    bail!(
+        "{:?} is not an Alfred workflow: missing {}",
+        p,
+        WORKFLOW_INFO_FN
+    )
+}
+
+fn read_u16(buf: &[u8], at: usize) -> usize {
+    usize::from(u16::from_le_bytes([buf[at], buf[at + 1]]))
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::Builder;
+
+    #[test]
+    fn it_validates_workflow_bundles() {
+        let dir = Builder::new()
+            .prefix("alfred_bundle_test")
+            .tempdir()
+            .unwrap();
+        let bundle = include_bytes!("../../tests/workflow.alfredworkflow");
+
+        let p = dir.path().join("good.alfredworkflow");
+        std::fs::write(&p, &bundle[..]).unwrap();
+        assert!(validate_workflow_bundle(&p).is_ok());
+
+        // An error page saved instead of the bundle.
+        let p = dir.path().join("html.alfredworkflow");
+        std::fs::write(&p, "<html><body>502 Bad Gateway</body></html>").unwrap();
+        assert!(validate_workflow_bundle(&p).is_err());
+
+        let p = dir.path().join("truncated.alfredworkflow");
+        std::fs::write(&p, &bundle[..bundle.len() / 2]).unwrap();
+        assert!(validate_workflow_bundle(&p).is_err());
+
+        let p = dir.path().join("empty.alfredworkflow");
+        std::fs::write(&p, "").unwrap();
+        assert!(validate_workflow_bundle(&p).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use super::{bail, Result};
+use ring::digest::{Context, SHA256};
+use std::io::{self, Read, Write};
+
+// Prefix of sha256 checksums, the format `github.com` reports the digest of release assets in.
+const SHA256_PREFIX: &str = "sha256:";
+
+// Computes the sha256 digest of what is written to it.
+pub(super) struct Sha256(Context);
+
+impl Sha256 {
+    pub(super) fn new() -> Self {
+        Sha256(Context::new(&SHA256))
+    }
+
+    // Check the digest of the data written so far against `checksum`, as reported by releasers:
+    // `sha256:<hex digest>` or only the hex digest. Other algorithms can't be verified.
+    pub(super) fn verify(self, checksum: &str) -> Result<()> {
+        let expected = checksum.strip_prefix(SHA256_PREFIX).unwrap_or(checksum);
+        if expected.len() != 64 || !expected.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("unsupported checksum: {}", checksum);
+        }
+        let actual = self
+            .0
+            .finish()
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        if !actual.eq_ignore_ascii_case(expected) {
+            bail!(
+                "checksum mismatch: expected {}, got sha256:{}",
+                checksum,
+                actual
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Write for Sha256 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Reader that adds everything read through it to a digest.
+pub(super) struct DigestReader<'a, R> {
+    inner: R,
+    digest: &'a mut Sha256,
+}
+
+impl<'a, R: Read> DigestReader<'a, R> {
+    pub(super) fn new(inner: R, digest: &'a mut Sha256) -> Self {
+        DigestReader { inner, digest }
+    }
+}
+
+impl<'a, R: Read> Read for DigestReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.digest.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256 of `abc`.
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn it_verifies_sha256_checksums() {
+        let digest = |data: &[u8]| {
+            let mut digest = Sha256::new();
+            io::copy(&mut DigestReader::new(data, &mut digest), &mut io::sink()).unwrap();
+            digest
+        };
+        assert!(digest(b"abc").verify(ABC_SHA256).is_ok());
+        assert!(digest(b"abc")
+            .verify(&format!("sha256:{}", ABC_SHA256.to_uppercase()))
+            .is_ok());
+        assert!(digest(b"abd").verify(ABC_SHA256).is_err());
+        assert!(digest(b"abc")
+            .verify("md5:900150983cd24fb0d6963f7d28e17f72")
+            .is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use super::{anyhow, DateTime, PathBuf, Result, Utc, Version};
+use crate::lock::FileLock;
+use std::collections::VecDeque;
+use std::fs::create_dir_all;
+use std::path::Path;
+
+// File in workflow's data dir that keeps the most recent checks.
+const CHECK_HISTORY_FN: &str = "updater_check_history.json";
+
+// Number of checks kept in the history file, older ones are dropped.
+pub(super) const CHECK_HISTORY_LEN: usize = 50;
+
+/// A check for updates, as recorded in the check history.
+///
+/// See [`Updater::check_history()`].
+///
+/// [`Updater::check_history()`]: struct.Updater.html#method.check_history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckRecord {
+    checked_at: DateTime<Utc>,
+
+    // Latest version reported by the releaser
+    #[serde(default)]
+    version: Option<Version>,
+
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl CheckRecord {
+    pub(super) fn new(outcome: std::result::Result<&Version, &anyhow::Error>) -> Self {
+        CheckRecord {
+            checked_at: Utc::now(),
+            version: outcome.ok().cloned(),
+            error: outcome.err().map(ToString::to_string),
+        }
+    }
+
+    /// Time of the check.
+    pub fn checked_at(&self) -> &DateTime<Utc> {
+        &self.checked_at
+    }
+
+    /// Whether the check succeeded.
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Latest version that the check found, if it succeeded.
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    /// Error that made the check fail, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+// Path of the history file.
+pub(super) fn history_fn() -> Result<PathBuf> {
+    crate::Data::data_dir()
+        .map(|dir| dir.join(CHECK_HISTORY_FN))
+        .ok_or_else(|| {
+            anyhow!("missing env variable for data dir. forgot to set workflow bundle id?")
+        })
+}
+
+// Add `record` to the history file at `p`, dropping the oldest checks beyond
+// `CHECK_HISTORY_LEN`.
+pub(super) fn append(p: &Path, record: CheckRecord) -> Result<()> {
+    // Alfred doesn't create workflow's data dir, it may not exist yet.
+    if let Some(dir) = p.parent() {
+        create_dir_all(dir)?;
+    }
+    let _lock = FileLock::exclusive(p)?;
+    let mut history: VecDeque<CheckRecord> = crate::Data::load_from_path(p).unwrap_or_default();
+    history.push_back(record);
+    while history.len() > CHECK_HISTORY_LEN {
+        history.pop_front();
+    }
+    crate::Data::save_to_path(p, &history)
+}
+
+// Checks saved in the history file at `p`, oldest first.
+pub(super) fn read(p: &Path) -> Result<Vec<CheckRecord>> {
+    if !p.exists() {
+        return Ok(Vec::new());
+    }
+    let _lock = FileLock::shared(p)?;
+    Ok(crate::Data::load_from_path(p).unwrap_or_default())
+}
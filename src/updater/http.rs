@@ -0,0 +1,742 @@
+use super::{anyhow, bail, Result, Url};
+use std::io::Read;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "updater")]
+use super::reqwest;
+
+// HTTP backend used by the updater. `reqwest` is the default, the lighter `ureq` is used when
+// the `updater-ureq` feature is enabled without `updater`. When both are enabled `reqwest` is
+// used, the same client as `updater-async`.
+#[cfg(feature = "updater")]
+type Client = reqwest::blocking::Client;
+#[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+type Client = ureq::Agent;
+
+#[cfg(feature = "updater")]
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+#[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+const PROXY_SCHEMES: &[&str] = &["http", "socks4", "socks4a", "socks5"];
+
+// Environment variables that HTTP clients read a proxy from.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "http_proxy",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+// Longest time a reachability probe waits for the name lookup and connection together.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+// Most redirects followed for a single request that the updater checks itself.
+const MAX_REDIRECTS: usize = 10;
+
+/// Which redirects [`Updater`] follows when talking to remote servers.
+///
+/// See [`Updater::set_redirect_policy()`].
+///
+/// [`Updater`]: struct.Updater.html
+/// [`Updater::set_redirect_policy()`]: struct.Updater.html#method.set_redirect_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Follow all redirects (default).
+    Any,
+    /// Only request `https` urls: both the url a request starts at and the urls it is
+    /// redirected to.
+    HttpsOnly,
+    /// Only follow redirects to the host of the original request, without downgrading from
+    /// `https` to `http`.
+    SameHost,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Any
+    }
+}
+
+impl RedirectPolicy {
+    // Whether a request made to `original` may be sent to `target`, either `original` itself
+    // or a url it was redirected to.
+    fn check(self, original: &Url, target: &Url) -> Result<()> {
+        let allowed = match self {
+            RedirectPolicy::Any => true,
+            RedirectPolicy::HttpsOnly => target.scheme() == "https",
+            RedirectPolicy::SameHost => {
+                target.host_str() == original.host_str()
+                    && (original.scheme() != "https" || target.scheme() == "https")
+            }
+        };
+        if !allowed {
+            bail!("request to {} is not allowed ({:?})", target, self);
+        }
+        Ok(())
+    }
+}
+
+/// Network settings that [`Updater`] uses when talking to remote servers.
+///
+/// The same settings are applied to checking for the latest release (through [`Releaser`]) and
+/// to downloading it ([`download_latest()`]). Use the setters on `Updater` (such as
+/// [`set_network_timeout()`]) to change them.
+///
+/// A single HTTP client is built from the settings on first use and shared by all clones of
+/// the settings, so the release check and the download reuse the same connections.
+///
+/// [`Updater`]: struct.Updater.html
+/// [`Releaser`]: trait.Releaser.html
+/// [`download_latest()`]: struct.Updater.html#method.download_latest
+/// [`set_network_timeout()`]: struct.Updater.html#method.set_network_timeout
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSettings {
+    timeout: Option<Duration>,
+    proxy: Option<Url>,
+    mirrors: Vec<String>,
+    redirect_policy: RedirectPolicy,
+    download_hosts: Vec<String>,
+    client: Arc<Mutex<Option<Client>>>,
+}
+
+// Response of a successful (2xx) request.
+pub(super) struct Response {
+    body: Box<dyn Read + Send>,
+    // Body continues from the requested offset rather than starting from the beginning.
+    resumed: bool,
+}
+
+impl Response {
+    pub(super) fn resumed(&self) -> bool {
+        self.resumed
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+impl NetworkSettings {
+    /// Timeout applied to both establishing a connection and the whole request.
+    ///
+    /// `None` means the HTTP client's defaults are used.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub(super) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+        self.reset_client();
+    }
+
+    /// Proxy that all requests are sent through.
+    ///
+    /// `None` means the proxy is picked from `HTTP_PROXY`, `HTTPS_PROXY` or `ALL_PROXY`
+    /// environment variables (if any is set).
+    pub fn proxy(&self) -> Option<&Url> {
+        self.proxy.as_ref()
+    }
+
+    pub(super) fn set_proxy(&mut self, proxy: Url) -> Result<()> {
+        if !PROXY_SCHEMES.contains(&proxy.scheme()) {
+            bail!("unsupported proxy scheme: {}", proxy.scheme());
+        }
+        self.proxy = Some(proxy);
+        self.reset_client();
+        Ok(())
+    }
+
+    /// Templates of mirrors that releases are downloaded from when their own url fails.
+    ///
+    /// See [`Updater::add_download_mirror()`].
+    ///
+    /// [`Updater::add_download_mirror()`]: struct.Updater.html#method.add_download_mirror
+    pub fn mirrors(&self) -> &[String] {
+        &self.mirrors
+    }
+
+    pub(super) fn add_mirror(&mut self, template: String) -> Result<()> {
+        if !template.contains("{url}") && !template.contains("{path}") {
+            bail!("mirror should contain {{url}} or {{path}}: {}", template);
+        }
+        let sample =
+            Url::parse("https://github.com/user/repo/releases/download/v1/a.alfredworkflow")?;
+        Self::mirror_url(&template, &sample)?;
+        self.mirrors.push(template);
+        Ok(())
+    }
+
+    /// Which redirects are followed.
+    ///
+    /// See [`Updater::set_redirect_policy()`].
+    ///
+    /// [`Updater::set_redirect_policy()`]: struct.Updater.html#method.set_redirect_policy
+    pub fn redirect_policy(&self) -> RedirectPolicy {
+        self.redirect_policy
+    }
+
+    pub(super) fn set_redirect_policy(&mut self, policy: RedirectPolicy) {
+        self.redirect_policy = policy;
+        self.reset_client();
+    }
+
+    /// Hosts that releases may be downloaded from. Empty means any host.
+    ///
+    /// See [`Updater::pin_download_host()`].
+    ///
+    /// [`Updater::pin_download_host()`]: struct.Updater.html#method.pin_download_host
+    pub fn download_hosts(&self) -> &[String] {
+        &self.download_hosts
+    }
+
+    pub(super) fn pin_download_host(&mut self, host: &str) -> Result<()> {
+        let host = host.trim().to_lowercase();
+        let url = Url::parse(&format!("https://{}/", host))
+            .map_err(|_| anyhow!("invalid download host: {}", host))?;
+        if url.host_str() != Some(host.as_str()) {
+            bail!("invalid download host: {}", host);
+        }
+        self.download_hosts.push(host);
+        self.reset_client();
+        Ok(())
+    }
+
+    fn mirror_url(template: &str, url: &Url) -> Result<Url> {
+        let mirrored = template
+            .replace("{url}", url.as_str())
+            .replace("{path}", url.path());
+        Ok(Url::parse(&mirrored)?)
+    }
+
+    // Urls to download `url` from: itself followed by its mirrors. Fails if `url` is not on one
+    // of the pinned download hosts, mirrors that are not on one are skipped.
+    pub(super) fn download_urls(&self, url: &Url) -> Result<Vec<Url>> {
+        self.check_pinned(url)?;
+        let mirrored = self
+            .mirrors
+            .iter()
+            .filter_map(|template| Self::mirror_url(template, url).ok())
+            .filter(|mirrored| match self.check_pinned(mirrored) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!("  skipping mirror: {}", e);
+                    false
+                }
+            });
+        Ok(std::iter::once(url.clone()).chain(mirrored).collect())
+    }
+
+    fn check_pinned(&self, url: &Url) -> Result<()> {
+        let host = url.host_str().unwrap_or_default();
+        if !self.download_hosts.is_empty() && !self.download_hosts.iter().any(|h| h == host) {
+            bail!("release is not hosted on a pinned download host: {}", url);
+        }
+        Ok(())
+    }
+
+    // Whether the HTTP client may follow redirects on its own. Otherwise they are followed by
+    // `send()`, which checks every url a request is redirected to.
+    fn follows_redirects(&self) -> bool {
+        self.redirect_policy == RedirectPolicy::Any && self.download_hosts.is_empty()
+    }
+
+    // Check `url`, which a request to `original` is about to be sent to, against the redirect
+    // policy and (when downloading a release) the pinned download hosts.
+    fn check_url(&self, original: &Url, url: &Url, pinned: bool) -> Result<()> {
+        self.redirect_policy.check(original, url)?;
+        if pinned {
+            self.check_pinned(url)?;
+        }
+        Ok(())
+    }
+
+    // Url that a request to `url` is redirected to by `location`.
+    fn redirect_target(
+        original: &Url,
+        url: &Url,
+        location: Option<&str>,
+        redirects: &mut usize,
+    ) -> Result<Url> {
+        let location = location.ok_or_else(|| anyhow!("redirect without location from {}", url))?;
+        *redirects += 1;
+        if *redirects > MAX_REDIRECTS {
+            bail!("too many redirects for url ({})", original);
+        }
+        Ok(url.join(location)?)
+    }
+
+    // Cheap check that `url`'s server (or the proxy) accepts TCP connections, to tell when the
+    // network is clearly down without waiting for an HTTP client's long timeouts. Servers
+    // behind a proxy from environment variables are assumed to be reachable.
+    // The check takes at most `PROBE_TIMEOUT` (or the network timeout if shorter), name lookup
+    // included: lookups can't be cancelled, so they run on a thread that is left behind when
+    // it's too slow, and the server is taken as unreachable.
+    pub(super) fn reachable(&self, url: &Url) -> bool {
+        let target = match self.proxy {
+            Some(ref proxy) => proxy,
+            None if PROXY_ENV_VARS
+                .iter()
+                .any(|var| std::env::var_os(var).is_some()) =>
+            {
+                return true
+            }
+            None => url,
+        };
+        let timeout = self.timeout.map_or(PROBE_TIMEOUT, |t| t.min(PROBE_TIMEOUT));
+        let deadline = Instant::now() + timeout;
+        let (tx, rx) = mpsc::channel();
+        let lookup = target.clone();
+        let spawned = std::thread::Builder::new().spawn(move || {
+            let addr = lookup
+                .socket_addrs(|| None)
+                .ok()
+                .and_then(|addrs| addrs.into_iter().next());
+            let _ = tx.send(addr);
+        });
+        let reachable = spawned
+            .ok()
+            .and_then(|_| rx.recv_timeout(timeout).ok().flatten())
+            .map_or(false, |addr| {
+                let left = deadline.saturating_duration_since(Instant::now());
+                !left.is_zero() && TcpStream::connect_timeout(&addr, left).is_ok()
+            });
+        debug!("  {} is reachable: {}", target, reachable);
+        reachable
+    }
+
+    // Send a GET request to `url`. Responses with a non-success status are turned into errors.
+    pub(super) fn get(&self, url: &str) -> Result<Response> {
+        self.get_from(url, 0)
+    }
+
+    // Same as `get` but asks server to skip the first `offset` bytes of the body. Servers that
+    // don't support range requests send the whole body, check `Response::resumed()`.
+    pub(super) fn get_from(&self, url: &str, offset: u64) -> Result<Response> {
+        let client = self.client()?;
+        self.send(&client, &Url::parse(url)?, offset, false)
+    }
+
+    // Same as `get_from` but for the url of a release: it and every url it is redirected to
+    // have to be on a pinned download host.
+    pub(super) fn download_from(&self, url: &Url, offset: u64) -> Result<Response> {
+        let client = self.client()?;
+        self.send(&client, url, offset, true)
+    }
+
+    // Send a POST request with a JSON `body` to `url`, authorized by the bearer `token`.
+    // Responses with a non-success status are turned into errors.
+    pub(super) fn post_json(&self, url: &str, token: &str, body: &str) -> Result<Response> {
+        let url = Url::parse(url)?;
+        self.redirect_policy.check(&url, &url)?;
+        let client = self.client()?;
+        Self::post(&client, url.as_str(), token, body)
+    }
+
+    // Whether a `Content-Range` header reports a body starting at `offset`.
+    fn starts_at(content_range: Option<&str>, offset: u64) -> bool {
+        content_range.map_or(false, |r| r.starts_with(&format!("bytes {}-", offset)))
+    }
+
+    // Unless the client follows redirects on its own (see `follows_redirects()`), they are
+    // checked and followed here.
+    #[cfg(feature = "updater")]
+    fn send(&self, client: &Client, original: &Url, offset: u64, pinned: bool) -> Result<Response> {
+        use reqwest::header::{CONTENT_RANGE, LOCATION, RANGE};
+        use reqwest::StatusCode;
+
+        let mut url = original.clone();
+        let mut redirects = 0;
+        loop {
+            self.check_url(original, &url, pinned)?;
+            let mut req = client.get(url.as_str());
+            if offset > 0 {
+                req = req.header(RANGE, format!("bytes={}-", offset));
+            }
+            let resp = req.send()?;
+            if resp.status().is_redirection() {
+                let location = resp.headers().get(LOCATION).and_then(|l| l.to_str().ok());
+                url = Self::redirect_target(original, &url, location, &mut redirects)?;
+                continue;
+            }
+            if offset > 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+                debug!("  cannot resume download from {}, starting over", offset);
+                return self.send(client, original, 0, pinned);
+            }
+            let resp = resp.error_for_status()?;
+            let resumed = offset > 0 && resp.status() == StatusCode::PARTIAL_CONTENT;
+            if resumed {
+                let content_range = resp
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|r| r.to_str().ok());
+                if !Self::starts_at(content_range, offset) {
+                    return self.send(client, original, 0, pinned);
+                }
+            }
+            return Ok(Response {
+                body: Box::new(resp),
+                resumed,
+            });
+        }
+    }
+
+    #[cfg(feature = "updater")]
+    fn post(client: &Client, url: &str, token: &str, body: &str) -> Result<Response> {
+        use reqwest::header::CONTENT_TYPE;
+
+        let resp = client
+            .post(url)
+            .bearer_auth(token)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()?
+            .error_for_status()?;
+        Ok(Response {
+            body: Box::new(resp),
+            resumed: false,
+        })
+    }
+
+    // Unless the client follows redirects on its own (see `follows_redirects()`), they are
+    // checked and followed here.
+    #[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+    fn send(&self, client: &Client, original: &Url, offset: u64, pinned: bool) -> Result<Response> {
+        let mut url = original.clone();
+        let mut redirects = 0;
+        loop {
+            self.check_url(original, &url, pinned)?;
+            let mut req = client.get(url.as_str());
+            if offset > 0 {
+                req = req.set("Range", &format!("bytes={}-", offset));
+            }
+            let resp = match req.call() {
+                Ok(resp) => resp,
+                Err(ureq::Error::Status(416, _)) if offset > 0 => {
+                    debug!("  cannot resume download from {}, starting over", offset);
+                    return self.send(client, original, 0, pinned);
+                }
+                Err(e) => return Err(Self::ureq_error(e, url.as_str())),
+            };
+            if (300..400).contains(&resp.status()) {
+                let location = resp.header("Location");
+                url = Self::redirect_target(original, &url, location, &mut redirects)?;
+                continue;
+            }
+            let resumed = offset > 0 && resp.status() == 206;
+            if resumed && !Self::starts_at(resp.header("Content-Range"), offset) {
+                return self.send(client, original, 0, pinned);
+            }
+            return Ok(Response {
+                body: Box::new(resp.into_reader()),
+                resumed,
+            });
+        }
+    }
+
+    #[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+    fn post(client: &Client, url: &str, token: &str, body: &str) -> Result<Response> {
+        let resp = client
+            .post(url)
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Content-Type", "application/json")
+            .send_string(body)
+            .map_err(|e| Self::ureq_error(e, url))?;
+        Ok(Response {
+            body: Box::new(resp.into_reader()),
+            resumed: false,
+        })
+    }
+
+    // Same wording as reqwest's errors.
+    #[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+    fn ureq_error(e: ureq::Error, url: &str) -> anyhow::Error {
+        match e {
+            ureq::Error::Status(code, resp) => anyhow!(
+                "HTTP status {} error ({} {}) for url ({})",
+                if code < 500 { "client" } else { "server" },
+                code,
+                resp.status_text(),
+                url
+            ),
+            ureq::Error::Transport(t) => t.into(),
+        }
+    }
+
+    // Shared HTTP client honoring these settings, built on first use.
+    fn client(&self) -> Result<Client> {
+        let mut cached = self
+            .client
+            .lock()
+            .map_err(|_| anyhow!("http client lock is poisoned"))?;
+        if let Some(ref client) = *cached {
+            return Ok(client.clone());
+        }
+        let client = self.build_client()?;
+        *cached = Some(client.clone());
+        Ok(client)
+    }
+
+    // Settings changed: stop sharing the old client with earlier clones.
+    fn reset_client(&mut self) {
+        self.client = Arc::default();
+    }
+
+    #[cfg(feature = "updater")]
+    fn build_client(&self) -> Result<Client> {
+        let redirect = if self.follows_redirects() {
+            reqwest::redirect::Policy::default()
+        } else {
+            reqwest::redirect::Policy::none()
+        };
+        let mut builder = reqwest::blocking::Client::builder()
+            .user_agent(USER_AGENT)
+            .redirect(redirect);
+        if let Some(timeout) = self.timeout {
+            builder = builder.connect_timeout(timeout).timeout(timeout);
+        }
+        // Without an explicit proxy, reqwest picks one up from the environment variables.
+        if let Some(ref proxy) = self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+        }
+        Ok(builder.build()?)
+    }
+
+    #[cfg(all(feature = "updater-ureq", not(feature = "updater")))]
+    fn build_client(&self) -> Result<Client> {
+        let mut builder = ureq::AgentBuilder::new()
+            .user_agent(USER_AGENT)
+            .try_proxy_from_env(true);
+        if !self.follows_redirects() {
+            builder = builder.redirects(0);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout_connect(timeout).timeout(timeout);
+        }
+        if let Some(ref proxy) = self.proxy {
+            builder = builder.proxy(ureq::Proxy::new(proxy.as_str())?);
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn it_times_out_unresponsive_servers() {
+        // A server that accepts connections but never replies.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/releases/latest", listener.local_addr().unwrap());
+
+        let mut settings = NetworkSettings::default();
+        settings.set_timeout(Duration::from_millis(200));
+
+        let start = Instant::now();
+        assert!(settings.get(&url).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn it_probes_reachability() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // With a configured proxy, its server is probed whatever the proxy env vars are.
+        let mut settings = NetworkSettings::default();
+        settings
+            .set_proxy(Url::parse(&format!("http://{}", addr)).unwrap())
+            .unwrap();
+
+        let url = Url::parse("https://api.github.com/releases/latest").unwrap();
+        assert!(settings.reachable(&url));
+
+        drop(listener);
+        let start = Instant::now();
+        assert!(!settings.reachable(&url));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn it_validates_proxy_scheme() {
+        let mut settings = NetworkSettings::default();
+        assert!(settings
+            .set_proxy(Url::parse("ftp://proxy.local:21").unwrap())
+            .is_err());
+        assert!(settings.proxy().is_none());
+
+        settings
+            .set_proxy(Url::parse("socks5://127.0.0.1:1080").unwrap())
+            .expect("socks5 proxy should be accepted");
+        assert_eq!("socks5", settings.proxy().unwrap().scheme());
+        assert!(settings.client().is_ok());
+    }
+
+    #[test]
+    fn it_expands_mirror_templates() {
+        let mut settings = NetworkSettings::default();
+        assert!(settings
+            .add_mirror("https://mirror.local/".to_string())
+            .is_err());
+        assert!(settings.add_mirror("not a url {path}".to_string()).is_err());
+        settings
+            .add_mirror("https://proxy.local/{url}".to_string())
+            .unwrap();
+        settings
+            .add_mirror("https://mirror.local/gh{path}".to_string())
+            .unwrap();
+
+        let url =
+            Url::parse("https://github.com/u/r/releases/download/v1/w.alfredworkflow").unwrap();
+        let urls = settings
+            .download_urls(&url)
+            .unwrap()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "https://github.com/u/r/releases/download/v1/w.alfredworkflow",
+                "https://proxy.local/https://github.com/u/r/releases/download/v1/w.alfredworkflow",
+                "https://mirror.local/gh/u/r/releases/download/v1/w.alfredworkflow",
+            ],
+            urls
+        );
+    }
+
+    #[test]
+    fn it_follows_redirect_policy() {
+        let _m = [
+            mockito::mock("GET", "/redirect/same-host")
+                .with_status(302)
+                .with_header("location", "/target")
+                .create(),
+            mockito::mock("GET", "/redirect/other-host")
+                .with_status(302)
+                .with_header(
+                    "location",
+                    &mockito::server_url().replace("127.0.0.1", "localhost"),
+                )
+                .create(),
+            mockito::mock("GET", "/target")
+                .with_status(200)
+                .with_body("target")
+                .create(),
+        ];
+        let get = |settings: &NetworkSettings, path: &str| -> Result<String> {
+            let mut body = String::new();
+            settings
+                .get(&format!("{}{}", mockito::server_url(), path))?
+                .read_to_string(&mut body)?;
+            Ok(body)
+        };
+
+        let mut settings = NetworkSettings::default();
+        settings.set_redirect_policy(RedirectPolicy::SameHost);
+        assert_eq!("target", get(&settings, "/redirect/same-host").unwrap());
+        assert!(get(&settings, "/redirect/other-host").is_err());
+
+        settings.set_redirect_policy(RedirectPolicy::HttpsOnly);
+        assert!(get(&settings, "/redirect/same-host").is_err());
+        // The url a request starts at is checked too, not only redirects.
+        assert!(get(&settings, "/target").is_err());
+
+        settings.set_redirect_policy(RedirectPolicy::Any);
+        assert_eq!("target", get(&settings, "/redirect/same-host").unwrap());
+    }
+
+    #[test]
+    fn it_pins_download_hosts() {
+        let url =
+            Url::parse("https://github.com/u/r/releases/download/v1/w.alfredworkflow").unwrap();
+        let mut settings = NetworkSettings::default();
+        assert!(settings.download_urls(&url).is_ok());
+
+        assert!(settings.pin_download_host("https://github.com").is_err());
+        assert!(settings.pin_download_host("github.com:443").is_err());
+        settings.pin_download_host("example.com").unwrap();
+        assert!(settings.download_urls(&url).is_err());
+        settings.pin_download_host("GitHub.com").unwrap();
+        assert_eq!(vec![url.clone()], settings.download_urls(&url).unwrap());
+        assert_eq!(&["example.com", "github.com"], settings.download_hosts());
+
+        // Mirrors have to be on a pinned download host too.
+        settings
+            .add_mirror("https://mirror.local/gh{path}".to_string())
+            .unwrap();
+        settings
+            .add_mirror("https://example.com/gh{path}".to_string())
+            .unwrap();
+        assert_eq!(
+            vec![
+                url.clone(),
+                Url::parse("https://example.com/gh/u/r/releases/download/v1/w.alfredworkflow")
+                    .unwrap()
+            ],
+            settings.download_urls(&url).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_checks_pinned_hosts_on_redirects() {
+        let _m = [
+            mockito::mock("GET", "/pinned/same-host")
+                .with_status(302)
+                .with_header("location", "/pinned/target")
+                .create(),
+            mockito::mock("GET", "/pinned/other-host")
+                .with_status(302)
+                .with_header(
+                    "location",
+                    &format!(
+                        "{}/pinned/target",
+                        mockito::server_url().replace("127.0.0.1", "localhost")
+                    ),
+                )
+                .create(),
+            mockito::mock("GET", "/pinned/target")
+                .with_status(200)
+                .with_body("target")
+                .create(),
+        ];
+        let download = |settings: &NetworkSettings, path: &str| -> Result<String> {
+            let url = Url::parse(&format!("{}{}", mockito::server_url(), path))?;
+            let mut body = String::new();
+            settings.download_from(&url, 0)?.read_to_string(&mut body)?;
+            Ok(body)
+        };
+
+        let mut settings = NetworkSettings::default();
+        assert_eq!("target", download(&settings, "/pinned/other-host").unwrap());
+        settings.pin_download_host("127.0.0.1").unwrap();
+        assert_eq!("target", download(&settings, "/pinned/same-host").unwrap());
+        assert!(download(&settings, "/pinned/other-host").is_err());
+        // Only downloads of releases are pinned.
+        assert!(settings
+            .get(&format!("{}/pinned/other-host", mockito::server_url()))
+            .is_ok());
+        settings.pin_download_host("localhost").unwrap();
+        assert_eq!("target", download(&settings, "/pinned/other-host").unwrap());
+    }
+
+    #[test]
+    fn it_shares_client_between_clones() {
+        let mut settings = NetworkSettings::default();
+        let shared = settings.clone();
+        settings.client().unwrap();
+        assert!(shared.client.lock().unwrap().is_some());
+
+        // Changing a setting must not affect clones made before the change.
+        settings.set_timeout(Duration::from_secs(1));
+        assert!(settings.client.lock().unwrap().is_none());
+        assert!(shared.client.lock().unwrap().is_some());
+    }
+}
@@ -1,35 +1,157 @@
+use super::bundle::validate_workflow_bundle;
+use super::checksum::{DigestReader, Sha256};
+use super::history::{self, CheckRecord};
+use super::interval::parse_interval;
+use super::location::{updater_cache_dir, StateLocation};
+use super::migration::{self, STATE_VERSION};
 use super::{
-    anyhow, env, env_logger, remove_file, DateTime, PathBuf, Receiver, RefCell, Releaser, Result,
-    Url, Utc, Version, UPDATE_INTERVAL,
+    anyhow, bail, env, env_logger, parse_lenient, remove_file, DateTime, Duration, NetworkSettings,
+    PathBuf, Receiver, RefCell, ReleaseInfo, Releaser, Result, RetryPolicy, UpdatePolicy, Url, Utc,
+    Version, UPDATE_INTERVAL,
 };
+use crate::lock::FileLock;
 use crate::Updater;
+use rand::Rng;
 use std::cell::Cell;
 use std::cell::Ref;
 use std::cell::RefMut;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{rename, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
+use std::process::{Command, Stdio};
 use std::sync::mpsc;
 
 pub(super) const LATEST_UPDATE_INFO_CACHE_FN_ASYNC: &str = "last_check_status_async.json";
+// Status file of the deprecated blocking check.
+const LATEST_UPDATE_INFO_CACHE_FN: &str = "last_check_status.json";
+// Version that was last installed automatically, guards against install loops.
+const AUTO_INSTALL_FLAG_FN: &str = "auto_install_attempt.json";
+
+// Set for the detached process that `init()` spawns to check for updates in background.
+const DETACHED_CHECK_ENV: &str = "ALFRED_RS_UPDATER_DETACHED_CHECK";
+
+// Delays (in seconds) before checking again after 1, 2, 3 or more consecutive failed checks.
+// They never exceed the update interval.
+const FAILURE_BACKOFF: [i64; 3] = [60 * 60, 6 * 60 * 60, 24 * 60 * 60];
+
+// Workflow variable that users can set (to `1`) in workflow's configuration to turn off
+// update checks.
+const UPDATE_DISABLED_VAR: &str = "alfred_workflow_update_disabled";
+
+// Workflow variable that users can set in workflow's configuration to change the interval
+// between checks, e.g. `7d` or `12h` (see `parse_interval()`).
+const UPDATE_INTERVAL_VAR: &str = "alfred_workflow_update_interval";
+
+// Bundle ids used to open a workflow bundle with Alfred (Alfred 3 has its own).
+const ALFRED_BUNDLE_ID: &str = "com.runningwithcrayons.Alfred";
+const ALFRED_3_BUNDLE_ID: &str = "com.runningwithcrayons.Alfred-3";
 
 // Payload that the worker thread will send back
 type ReleasePayloadResult = Result<Option<UpdateInfo>>;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(super) struct UpdaterState {
+    // Version of the schema this state was saved with, see `migration::migrate`.
+    #[serde(default)]
+    state_version: u64,
+
     pub(super) last_check: Cell<Option<DateTime<Utc>>>,
 
     current_version: Version,
 
     avail_release: RefCell<Option<UpdateInfo>>,
 
+    // Error of the last check for the latest release, if it failed.
+    #[serde(default)]
+    last_error: RefCell<Option<String>>,
+
+    // Number of consecutive failed checks and when the last one happened.
+    #[serde(default)]
+    failed_checks: Cell<u32>,
+
+    #[serde(default)]
+    last_failure: Cell<Option<DateTime<Utc>>>,
+
+    // Latest version that was reported to `on_update_available` callback.
+    #[serde(default)]
+    announced_version: RefCell<Option<Version>>,
+
+    // Update checks were turned off by the workflow, see `Updater::set_enabled()`.
+    #[serde(default)]
+    checks_disabled: bool,
+
     #[serde(skip, default = "default_interval")]
     update_interval: i64,
 
+    // Random delay (seconds) added to the interval after last check, see `set_interval_jitter`.
+    #[serde(default)]
+    check_jitter: Cell<i64>,
+
+    #[serde(skip)]
+    max_jitter: i64,
+
+    #[serde(default)]
+    last_notified: Cell<Option<DateTime<Utc>>>,
+
+    #[serde(skip, default = "default_interval")]
+    notify_interval: i64,
+
+    #[serde(skip)]
+    retry_policy: RetryPolicy,
+
+    #[serde(skip)]
+    network: NetworkSettings,
+
+    #[serde(skip)]
+    update_policy: UpdatePolicy,
+
+    #[serde(skip)]
+    auto_install: bool,
+
+    #[serde(skip)]
+    detached_check: bool,
+
+    #[serde(skip)]
+    dry_run: bool,
+
+    #[serde(skip)]
+    location: StateLocation,
+
     #[serde(skip)]
     worker_state: RefCell<Option<MPSCState>>,
 }
 
 impl UpdaterState {
+    // State of an updater that has never checked for updates.
+    fn new(current_version: Version, location: StateLocation) -> Self {
+        UpdaterState {
+            state_version: STATE_VERSION,
+            current_version,
+            last_check: Cell::new(None),
+            avail_release: RefCell::new(None),
+            last_error: RefCell::new(None),
+            failed_checks: Cell::new(0),
+            last_failure: Cell::new(None),
+            announced_version: RefCell::new(None),
+            checks_disabled: false,
+            worker_state: RefCell::new(None),
+            update_interval: UPDATE_INTERVAL,
+            check_jitter: Cell::new(0),
+            max_jitter: 0,
+            last_notified: Cell::new(None),
+            notify_interval: UPDATE_INTERVAL,
+            retry_policy: RetryPolicy::default(),
+            network: NetworkSettings::default(),
+            update_policy: UpdatePolicy::default(),
+            auto_install: false,
+            detached_check: false,
+            dry_run: false,
+            location,
+        }
+    }
+
     pub(super) fn current_version(&self) -> &Version {
         &self.current_version
     }
@@ -45,6 +167,46 @@ impl UpdaterState {
             .map(|ui| ui.version().clone())
     }
 
+    pub(super) fn last_error(&self) -> Option<String> {
+        self.last_error.borrow().clone()
+    }
+
+    pub(super) fn latest_available(&self) -> Option<UpdateInfo> {
+        self.avail_release.borrow().clone()
+    }
+
+    // Count consecutive failed checks, for the backoff of `failure_backoff_until()`.
+    fn record_check_outcome(&self, success: bool) {
+        if success {
+            self.failed_checks.set(0);
+            self.last_failure.set(None);
+        } else {
+            let failed = self.failed_checks.get().saturating_add(1);
+            self.failed_checks.set(failed);
+            self.last_failure.set(Some(Utc::now()));
+        }
+    }
+
+    // Take what another invocation of the workflow saved to `saved` after this state was
+    // loaded: the results of a more recent check and the latest notification.
+    fn merge_newer(&self, saved: UpdaterState) {
+        if saved.last_check.get() > self.last_check.get() {
+            self.last_check.set(saved.last_check.get());
+            self.check_jitter.set(saved.check_jitter.get());
+            *self.avail_release.borrow_mut() = saved.avail_release.into_inner();
+            *self.last_error.borrow_mut() = saved.last_error.into_inner();
+            self.failed_checks.set(saved.failed_checks.get());
+            self.last_failure.set(saved.last_failure.get());
+        }
+        if saved.last_notified.get() > self.last_notified.get() {
+            self.last_notified.set(saved.last_notified.get());
+        }
+        let announced = saved.announced_version.into_inner();
+        if announced > *self.announced_version.borrow() {
+            *self.announced_version.borrow_mut() = announced;
+        }
+    }
+
     pub(super) fn borrow_worker(&self) -> Ref<'_, Option<MPSCState>> {
         self.worker_state.borrow()
     }
@@ -59,41 +221,109 @@ impl UpdaterState {
             .as_ref()
             .map(|info| info.downloadable_url.clone())
     }
+
+    pub(super) fn download_checksum(&self) -> Option<String> {
+        self.avail_release
+            .borrow()
+            .as_ref()
+            .and_then(|info| info.checksum.clone())
+    }
 }
 
+/// Information about the latest release that was received from [`Releaser`].
+///
+/// See [`Updater::on_update_available()`].
+///
+/// [`Releaser`]: trait.Releaser.html
+/// [`Updater::on_update_available()`]: struct.Updater.html#method.on_update_available
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(super) struct UpdateInfo {
+pub struct UpdateInfo {
     // Latest version available from github or releaser
-    pub version: Version,
+    version: Version,
 
-    pub fetched_at: Option<DateTime<Utc>>,
+    fetched_at: Option<DateTime<Utc>>,
 
     // Link to use to download the above version
-    pub downloadable_url: Url,
+    downloadable_url: Url,
+
+    #[serde(default)]
+    published_at: Option<DateTime<Utc>>,
+
+    // Size of the workflow bundle in bytes
+    #[serde(default)]
+    size: Option<u64>,
+
+    #[serde(default)]
+    notes: Option<String>,
+
+    // Checksum of the workflow bundle, such as `sha256:<hex digest>`
+    #[serde(default)]
+    checksum: Option<String>,
 }
 
 impl UpdateInfo {
-    pub fn new(v: Version, url: Url) -> Self {
+    pub(super) fn new(release: &ReleaseInfo) -> Self {
         UpdateInfo {
-            version: v,
+            version: release.version().clone(),
             fetched_at: None,
-            downloadable_url: url,
+            downloadable_url: release.url().clone(),
+            published_at: release.published_at().cloned(),
+            size: release.size(),
+            notes: release.notes().map(String::from),
+            checksum: release.checksum().map(String::from),
         }
     }
 
-    pub(super) fn version(&self) -> &Version {
+    /// Version of the release.
+    pub fn version(&self) -> &Version {
         &self.version
     }
 
-    pub(super) fn fetched_at(&self) -> Option<&DateTime<Utc>> {
+    /// Time at which the release info was fetched from remote server.
+    pub fn fetched_at(&self) -> Option<&DateTime<Utc>> {
         self.fetched_at.as_ref()
     }
 
+    /// Link to download the release's workflow bundle.
+    pub fn url(&self) -> &Url {
+        &self.downloadable_url
+    }
+
+    /// Time the release was published, if known.
+    pub fn published_at(&self) -> Option<&DateTime<Utc>> {
+        self.published_at.as_ref()
+    }
+
+    /// Size of the release's workflow bundle in bytes, if known.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Release notes, if any.
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// Checksum of the release's workflow bundle, if known.
+    ///
+    /// Downloads of the release are verified against it.
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
+    }
+
     pub(super) fn set_fetched_at(&mut self, date_time: DateTime<Utc>) {
         self.fetched_at = Some(date_time);
     }
 }
 
+// How long to wait for the worker thread to send its payload.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Wait {
+    Poll,
+    Block,
+    Timeout(std::time::Duration),
+}
+
 #[derive(Debug)]
 pub(super) struct MPSCState {
     // First successful call on rx.recv() will cache the results into this field
@@ -116,68 +346,379 @@ where
     T: Releaser + Send + 'static,
 {
     pub(super) fn load_or_new(r: T) -> Result<Self> {
+        Self::load_or_new_with(r, StateLocation::default(), |_| Ok(()))
+    }
+
+    // Same as `load_or_new` but the state is kept at `location`, and `configure` can change the
+    // updater before a fresh state is saved for the first time.
+    pub(super) fn load_or_new_with<F>(
+        mut r: T,
+        location: StateLocation,
+        configure: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
         let _ = env_logger::try_init();
-        if let Ok(mut saved_state) = Self::load() {
+        if let Ok(mut saved_state) = Self::load(&location) {
+            saved_state.location = location;
             // Use the version that workflow reports through environment variable
             // This version takes priortiy over what we may have saved last time.
-            let env_ver = env::workflow_version().and_then(|v| Version::parse(&v).ok());
+            let env_ver = env::workflow_version().and_then(|v| parse_lenient(&v));
             if let Some(v) = env_ver {
                 saved_state.current_version = v;
             }
-            Ok(Updater {
+            Self::adopt_last_check_status(&mut saved_state);
+            // Share updater's HTTP client with the releaser.
+            r.set_network_settings(&saved_state.network);
+            let mut updater = Updater {
                 state: saved_state,
                 releaser: RefCell::new(r),
-            })
-        } else {
-            let current_version = env::workflow_version()
-                .map_or_else(|| Ok(Version::new(0, 0, 0)), |v| Version::parse(&v))?;
-            let state = UpdaterState {
-                current_version,
-                last_check: Cell::new(None),
-                avail_release: RefCell::new(None),
-                worker_state: RefCell::new(None),
-                update_interval: UPDATE_INTERVAL,
+                on_update: RefCell::new(None),
             };
-            let updater = Updater {
+            configure(&mut updater)?;
+            Ok(updater)
+        } else {
+            let current_version = env::workflow_version().map_or_else(
+                || Ok(Version::new(0, 0, 0)),
+                |v| parse_lenient(&v).ok_or_else(|| anyhow!("invalid workflow version: {}", v)),
+            )?;
+            let state = UpdaterState::new(current_version, location);
+            r.set_network_settings(&state.network);
+            let mut updater = Updater {
                 state,
                 releaser: RefCell::new(r),
+                on_update: RefCell::new(None),
             };
+            configure(&mut updater)?;
             updater.save()?;
             Ok(updater)
         }
     }
 
+    // A worker whose results were never received (the workflow exited or used
+    // `check_in_background()`) still writes them to the status file. Use them if they are
+    // newer than what the saved state knows about.
+    fn adopt_last_check_status(state: &mut UpdaterState) {
+        let status = state
+            .location
+            .path()
+            .map(|p| p.with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC))
+            .and_then(|p| Self::read_last_check_status(&p));
+        if let Ok(Some(info)) = status {
+            if let Some(fetched_at) = info.fetched_at().copied() {
+                if state.last_check.get().map_or(true, |dt| dt < fetched_at) {
+                    debug!("  using results of unreceived check from {}", fetched_at);
+                    state.last_check.set(Some(fetched_at));
+                    *state.avail_release.get_mut() = Some(info);
+                }
+            }
+        }
+    }
+
     pub(super) fn last_check(&self) -> Option<DateTime<Utc>> {
         self.state.last_check.get()
     }
 
     pub(super) fn set_last_check(&self, t: DateTime<Utc>) {
         self.state.last_check.set(Some(t));
+        // Pick the delay of next check once per check so it doesn't change between invocations.
+        let jitter = if self.state.max_jitter > 0 {
+            rand::thread_rng().gen_range(0..=self.state.max_jitter)
+        } else {
+            0
+        };
+        self.state.check_jitter.set(jitter);
+    }
+
+    pub(super) fn set_max_jitter(&mut self, max: i64) {
+        self.state.max_jitter = max;
+    }
+
+    // Update interval plus the random delay picked at last check (which may have been picked
+    // with a larger maximum jitter).
+    pub(super) fn effective_interval(&self) -> i64 {
+        let jitter = self.state.check_jitter.get().min(self.state.max_jitter);
+        self.update_interval().saturating_add(jitter.max(0))
+    }
+
+    fn record_check_outcome(&self, success: bool) {
+        self.state.record_check_outcome(success);
+    }
+
+    // Save the outcome of a check made without the worker thread (see `AsyncUpdater::check()`)
+    // the same way as the worker's checks, and report whether an update is available.
+    #[cfg(feature = "updater-async")]
+    pub(super) fn record_check(&self, outcome: Result<UpdateInfo>) -> Result<bool> {
+        self.set_last_check(Utc::now());
+        *self.state.last_error.borrow_mut() = outcome.as_ref().err().map(ToString::to_string);
+        self.record_check_outcome(outcome.is_ok());
+        if !self.state.dry_run {
+            Self::add_to_history(
+                history::history_fn().ok().as_deref(),
+                outcome.as_ref().map(UpdateInfo::version),
+            );
+        }
+        let info = match outcome {
+            Ok(info) => info,
+            Err(e) => {
+                self.save()?;
+                return Err(e);
+            }
+        };
+        let update_avail = self.is_update(info.version());
+        let payload = Some(info);
+        if !self.state.dry_run {
+            let p = self
+                .data_fn()?
+                .with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
+            Self::write_last_check_status(&p, &payload)?;
+        }
+        *self.state.avail_release.borrow_mut() = payload;
+        self.save()?;
+        if update_avail {
+            self.announce_update()?;
+        }
+        Ok(update_avail)
+    }
+
+    // Time before which no check is made because of previous failed checks. The delay grows
+    // with the number of consecutive failures, so a dead repository or captive portal network
+    // doesn't slow down every invocation of the workflow.
+    pub(super) fn failure_backoff_until(&self) -> Option<DateTime<Utc>> {
+        let failed = self.state.failed_checks.get() as usize;
+        match self.state.last_failure.get() {
+            Some(dt) if failed > 0 => {
+                let backoff = FAILURE_BACKOFF[failed.min(FAILURE_BACKOFF.len()) - 1]
+                    .min(self.update_interval());
+                Some(dt + Duration::seconds(backoff))
+            }
+            _ => None,
+        }
     }
 
+    // Interval set by users in workflow's configuration takes precedence over the one set by
+    // the workflow. Invalid values are ignored.
     pub(super) fn update_interval(&self) -> i64 {
-        self.state.update_interval
+        std::env::var(UPDATE_INTERVAL_VAR)
+            .ok()
+            .and_then(|v| {
+                let interval = parse_interval(&v);
+                if interval.is_none() {
+                    debug!("  ignoring invalid {}: {:?}", UPDATE_INTERVAL_VAR, v);
+                }
+                interval
+            })
+            .unwrap_or(self.state.update_interval)
     }
 
     pub(super) fn set_update_interval(&mut self, t: i64) {
         self.state.update_interval = t;
     }
 
-    fn load() -> Result<UpdaterState> {
-        let data_file_path = Self::build_data_fn()?;
-        crate::Data::load_from_file(data_file_path)
-            .ok_or_else(|| anyhow!("cannot load cached state of updater"))
+    pub(super) fn set_notify_interval_secs(&mut self, t: i64) {
+        self.state.notify_interval = t;
+    }
+
+    // Returns `true`, and remembers the time, if a newer release is known and user hasn't been
+    // notified about an update in the last `notify_interval` seconds.
+    pub(super) fn notify_due(&self) -> Result<bool> {
+        if !self.checks_enabled() {
+            return Ok(false);
+        }
+        let update_known = self
+            .latest_avail_version()
+            .map_or(false, |v| self.is_update(&v));
+        let now = Utc::now();
+        let due = update_known
+            && self.state.last_notified.get().map_or(true, |dt| {
+                now.signed_duration_since(dt) >= Duration::seconds(self.state.notify_interval)
+            });
+        if due {
+            self.state.last_notified.set(Some(now));
+            self.save()?;
+        }
+        Ok(due)
+    }
+
+    pub(super) fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.state.retry_policy = policy;
+    }
+
+    pub(super) fn set_policy(&mut self, policy: UpdatePolicy) {
+        self.state.update_policy = policy;
+    }
+
+    pub(super) fn set_auto_install_mode(&mut self, auto_install: bool) {
+        self.state.auto_install = auto_install;
     }
 
-    // Save updater's state
+    pub(super) fn set_detached_check_mode(&mut self, detached: bool) {
+        self.state.detached_check = detached;
+    }
+
+    // Results of a detached check are only saved to files, which never happens in a dry run.
+    pub(super) fn detached_check(&self) -> bool {
+        self.state.detached_check && !self.state.dry_run
+    }
+
+    pub(super) fn set_dry_run_mode(&mut self, dry_run: bool) {
+        self.state.dry_run = dry_run;
+    }
+
+    pub(super) fn dry_run(&self) -> bool {
+        self.state.dry_run
+    }
+
+    // Whether this process was spawned by `spawn_detached_check()`.
+    pub(super) fn in_detached_check(&self) -> bool {
+        self.state.detached_check && std::env::var_os(DETACHED_CHECK_ENV).is_some()
+    }
+
+    // Run workflow's executable again, with the same arguments so that it reaches `init()` the
+    // same way, as a process that checks for updates and outlives current process. `init()`
+    // of that process exits once the check is done. Its output is discarded.
+    pub(super) fn spawn_detached_check() -> Result<()> {
+        let exe = std::env::current_exe()?;
+        debug!("  spawning detached check: {:?}", exe);
+        let mut command = Command::new(exe);
+        command
+            .args(std::env::args_os().skip(1))
+            .env(DETACHED_CHECK_ENV, "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // In its own session, the process isn't killed along with Alfred's process group.
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: `setsid` is async-signal-safe, as required between `fork` and `exec`.
+            unsafe {
+                command.pre_exec(|| {
+                    if libc::setsid() == -1 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        command.spawn()?;
+        Ok(())
+    }
+
+    // Whether releaser's server is clearly unreachable, see `Releaser::probe_url()`.
+    pub(super) fn offline(&self) -> bool {
+        self.releaser
+            .borrow()
+            .probe_url()
+            .map_or(false, |url| !self.network().reachable(&url))
+    }
+
+    // Whether update checks are enabled, i.e. not turned off by the user or the workflow.
+    pub(super) fn checks_enabled(&self) -> bool {
+        let disabled = std::env::var(UPDATE_DISABLED_VAR).map_or(false, |v| {
+            matches!(
+                v.trim().to_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+        !disabled && !self.state.checks_disabled
+    }
+
+    pub(super) fn set_checks_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.state.checks_disabled = !enabled;
+        self.save()
+    }
+
+    // Whether `v` is an update we should report for current version of workflow.
+    pub(super) fn is_update(&self, v: &Version) -> bool {
+        self.state.update_policy.allows(self.current_version(), v)
+    }
+
+    pub(super) fn network(&self) -> &NetworkSettings {
+        &self.state.network
+    }
+
+    #[cfg(feature = "updater-async")]
+    pub(super) fn retry_policy(&self) -> RetryPolicy {
+        self.state.retry_policy
+    }
+
+    // Change network settings and hand them over to releaser.
+    pub(super) fn update_network<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut NetworkSettings),
+    {
+        f(&mut self.state.network);
+        self.releaser
+            .get_mut()
+            .set_network_settings(&self.state.network);
+    }
+
+    fn load(location: &StateLocation) -> Result<UpdaterState> {
+        let data_file_path = location.path()?;
+        let _lock = FileLock::shared(&data_file_path)?;
+        Self::read_state(&data_file_path)
+    }
+
+    // States saved by older versions of the crate are migrated, see `migration::migrate`.
+    fn read_state(p: &Path) -> Result<UpdaterState> {
+        let saved = crate::Data::load_from_path(p)
+            .ok_or_else(|| anyhow!("cannot load cached state of updater"))?;
+        let fresh = UpdaterState::new(Version::new(0, 0, 0), StateLocation::default());
+        migration::migrate(saved, &fresh)
+    }
+
+    // Save updater's state. The file is locked from reading it to writing it, and results that
+    // other invocations of the workflow saved since the state was loaded are kept.
     pub(super) fn save(&self) -> Result<()> {
-        let data_file_path = Self::build_data_fn()?;
-        crate::Data::save_to_file(&data_file_path, &self.state).map_err(|e| {
+        if self.state.dry_run {
+            debug!("  dry run, not saving updater state");
+            return Ok(());
+        }
+        let data_file_path = self.data_fn()?;
+        let _lock = FileLock::exclusive(&data_file_path)?;
+        if let Ok(saved) = Self::read_state(&data_file_path) {
+            self.state.merge_newer(saved);
+        }
+        crate::Data::save_to_path(&data_file_path, &self.state).map_err(|e| {
             let _r = remove_file(data_file_path);
             e
         })
     }
 
+    // Forget everything learned from previous checks and delete the files it was saved to.
+    // Settings of the updater are kept.
+    pub(super) fn reset(&self) -> Result<()> {
+        self.state.last_check.set(None);
+        *self.state.avail_release.borrow_mut() = None;
+        *self.state.last_error.borrow_mut() = None;
+        self.state.failed_checks.set(0);
+        self.state.last_failure.set(None);
+        *self.state.announced_version.borrow_mut() = None;
+        self.state.check_jitter.set(0);
+        self.state.last_notified.set(None);
+        *self.state.worker_state.borrow_mut() = None;
+        if self.state.dry_run {
+            debug!("  dry run, not deleting updater files");
+            return Ok(());
+        }
+
+        let data_file_path = self.data_fn()?;
+        let files = [
+            data_file_path.with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC),
+            data_file_path.with_file_name(LATEST_UPDATE_INFO_CACHE_FN),
+            data_file_path.with_file_name(AUTO_INSTALL_FLAG_FN),
+            data_file_path,
+        ];
+        for p in &files {
+            if FileLock::remove(p)? {
+                debug!("  removed {:?}", p);
+            }
+        }
+        Ok(())
+    }
+
     pub(super) fn start_releaser_worker(
         &self,
         tx: mpsc::Sender<ReleasePayloadResult>,
@@ -186,36 +727,366 @@ where
         use std::thread;
 
         let releaser = (*self.releaser.borrow()).clone();
+        let retry_policy = self.state.retry_policy;
+        let dry_run = self.state.dry_run;
+        let auto_install = if self.state.auto_install && !dry_run {
+            let flag = p.with_file_name(AUTO_INSTALL_FLAG_FN);
+            Some((self.network().clone(), self.current_version().clone(), flag))
+        } else {
+            None
+        };
+        let update_policy = self.state.update_policy;
+        let (history_fn, state_fn) = if dry_run {
+            (None, None)
+        } else {
+            (history::history_fn().ok(), self.data_fn().ok())
+        };
 
         thread::Builder::new().spawn(move || {
             debug!("other thread: starting in updater thread");
-            let talk_to_mother = || -> Result<()> {
-                let (v, url) = releaser.latest_release()?;
-                let mut info = UpdateInfo::new(v, url);
+            let talk_to_mother = || -> Result<UpdateInfo> {
+                let release = retry_policy.run(|| releaser.fetch_latest_release())?;
+                let (v, url) = (release.version().clone(), release.url().clone());
+                if let Some((ref network, ref current, ref flag)) = auto_install {
+                    if update_policy.allows(current, &v) {
+                        // Failing to install shouldn't fail the check itself.
+                        let checksum = release.checksum();
+                        if let Err(e) = Self::auto_install(network, &v, &url, checksum, flag) {
+                            debug!("other thread: auto install failed: {}", e);
+                        }
+                    }
+                }
+                let mut info = UpdateInfo::new(&release);
                 info.set_fetched_at(Utc::now());
-                let payload = Some(info);
-                Self::write_last_check_status(&p, &payload)?;
-                tx.send(Ok(payload))?;
-                Ok(())
+                if !dry_run {
+                    Self::write_last_check_status(&p, &Some(info.clone()))?;
+                }
+                Ok(info)
             };
 
             let outcome = talk_to_mother();
             debug!("other thread: finished checking releaser status");
+            Self::add_to_history(
+                history_fn.as_deref(),
+                outcome.as_ref().map(UpdateInfo::version),
+            );
+            if let Some(ref state_fn) = state_fn {
+                if let Err(e) = Self::save_check_outcome(state_fn, outcome.as_ref()) {
+                    debug!("other thread: couldn't save outcome of check: {}", e);
+                }
+            }
 
-            if let Err(error) = outcome {
-                tx.send(Err(error))
-                    .expect("could not send error from thread");
+            // Results are persisted, the receiver may be gone by now if the updater was
+            // dropped (see `check_in_background()`).
+            match outcome {
+                Ok(info) => {
+                    let _ = tx.send(Ok(Some(info)));
+                }
+                Err(error) => {
+                    debug!("other thread: check failed: {}", error);
+                    let _ = tx.send(Err(error));
+                }
             }
         })?;
         Ok(())
     }
 
+    // Save the outcome of a check made by the worker thread to the state file `p` when the check
+    // finishes, as the updater may be gone by then or never receive it.
+    fn save_check_outcome(
+        p: &Path,
+        outcome: std::result::Result<&UpdateInfo, &anyhow::Error>,
+    ) -> Result<()> {
+        let _lock = FileLock::exclusive(p)?;
+        let state = Self::read_state(p)?;
+        let checked_at = outcome
+            .ok()
+            .and_then(|info| info.fetched_at().copied())
+            .unwrap_or_else(Utc::now);
+        state.last_check.set(Some(checked_at));
+        *state.last_error.borrow_mut() = outcome.err().map(ToString::to_string);
+        state.record_check_outcome(outcome.is_ok());
+        if let Ok(info) = outcome {
+            *state.avail_release.borrow_mut() = Some(info.clone());
+        }
+        crate::Data::save_to_path(p, &state)
+    }
+
+    // Record a check in the history file (if any). Failing to do so shouldn't fail the check.
+    fn add_to_history(p: Option<&Path>, outcome: std::result::Result<&Version, &anyhow::Error>) {
+        if let Some(p) = p {
+            if let Err(e) = history::append(p, CheckRecord::new(outcome)) {
+                debug!("  couldn't add check to history: {}", e);
+            }
+        }
+    }
+
+    // File in the updater's cache dir for saving a downloaded release. Without a `version` it
+    // is the file for the latest release.
+    pub(super) fn release_download_fn(version: Option<&Version>) -> Result<PathBuf> {
+        let workflow_name = Self::download_workflow_name();
+        let filename = match version {
+            None => ["latest_release_", &workflow_name, ".alfredworkflow"].concat(),
+            Some(v) => format!("release_{}_{}.alfredworkflow", workflow_name, v),
+        };
+        Ok(updater_cache_dir()?.join(filename))
+    }
+
+    fn download_workflow_name() -> String {
+        env::workflow_name()
+            .unwrap_or_else(|| "WhyUNoNameYourOwnWorkflow".to_string())
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>()
+    }
+
+    // Downloaded (or partially downloaded) releases in the updater's cache dir that are no longer needed:
+    // those older than `max_age` and those whose version isn't newer than the installed one.
+    // Versions in file names are read like the releaser reads them, so lenient ones are recognized.
+    pub(super) fn stale_downloads(&self, max_age: std::time::Duration) -> Result<Vec<PathBuf>> {
+        let latest_fn = Self::release_download_fn(None)?;
+        let cache_dir = latest_fn
+            .parent()
+            .ok_or_else(|| anyhow!("invalid cache dir"))?;
+        let workflow_name = Self::download_workflow_name();
+        let latest_prefix = ["latest_release_", &workflow_name, ".alfredworkflow"].concat();
+        let version_prefix = ["release_", &workflow_name, "_"].concat();
+        // The bundle saved for latest release is superseded once we know no update is available.
+        let latest_superseded = self
+            .latest_avail_version()
+            .map_or(false, |v| !self.is_update(&v));
+
+        let releaser = self.releaser.borrow();
+
+        let mut stale = Vec::new();
+        for entry in cache_dir.read_dir()? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let superseded = if name.starts_with(&latest_prefix) {
+                latest_superseded
+            } else if let Some(rest) = name.strip_prefix(&version_prefix) {
+                match rest.find(".alfredworkflow") {
+                    Some(idx) => releaser
+                        .parse_version(&rest[..idx])
+                        .map_or(false, |v| !self.is_update(&v)),
+                    None => continue,
+                }
+            } else {
+                continue;
+            };
+            let expired = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .map_or(false, |age| age >= max_age);
+            if superseded || expired {
+                stale.push(entry.path());
+            }
+        }
+        Ok(stale)
+    }
+
+    // File that holds a (possibly interrupted) download of `url` until it is complete.
+    // Its name depends on `url` so that a download is never resumed from a different release.
+    pub(super) fn partial_download_fn(p: &Path, url: &Url) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.as_str().hash(&mut hasher);
+        let mut name = p.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{:016x}.part", hasher.finish()));
+        p.with_file_name(name)
+    }
+
+    // Download release from `url` and save it to `p`. If `checksum` is known, the downloaded
+    // bundle must match it.
+    pub(super) fn download_release(
+        &self,
+        url: &Url,
+        checksum: Option<&str>,
+        p: PathBuf,
+    ) -> Result<PathBuf> {
+        if self.state.dry_run {
+            debug!("  dry run, not downloading {} to {:?}", url, p);
+            return Ok(p);
+        }
+        Self::download_with(self.network(), url, checksum, p, &validate_workflow_bundle)
+    }
+
+    // Where an asset downloaded from `url` is saved: the updater's cache dir, under asset's file
+    // name.
+    pub(super) fn asset_download_fn(url: &Url) -> Result<PathBuf> {
+        let name = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+            .ok_or_else(|| anyhow!("no file name in asset url {}", url))?;
+        Ok(updater_cache_dir()?.join(name))
+    }
+
+    // Download release asset `asset` and save it to `p`. Downloaded file must have the size and
+    // checksum that releaser reported and workflow bundles are validated the same as the release
+    // itself.
+    pub(super) fn download_release_asset(
+        &self,
+        asset: &ReleaseInfo,
+        p: PathBuf,
+    ) -> Result<PathBuf> {
+        if self.state.dry_run {
+            debug!("  dry run, not downloading {} to {:?}", asset.url(), p);
+            return Ok(p);
+        }
+        let is_bundle = p
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(false, |ext| {
+                ext.starts_with("alfred") && ext.ends_with("workflow")
+            });
+        let check = |partial: &Path| {
+            if let Some(size) = asset.size() {
+                let len = partial.metadata()?.len();
+                if len != size {
+                    bail!("{:?} has {} bytes, expected {}", partial, len, size);
+                }
+            }
+            if is_bundle {
+                validate_workflow_bundle(partial)?;
+            }
+            Ok(())
+        };
+        Self::download_with(self.network(), asset.url(), asset.checksum(), p, &check)
+    }
+
+    // Data is first written to a partial file that is kept if the download gets interrupted,
+    // so that the next download can resume from where it stopped. Downloaded file is verified
+    // against `checksum` (if known) and with `check` before moving it in place.
+    pub(super) fn download_with(
+        network: &NetworkSettings,
+        url: &Url,
+        checksum: Option<&str>,
+        p: PathBuf,
+        check: &dyn Fn(&Path) -> Result<()>,
+    ) -> Result<PathBuf> {
+        let partial = Self::partial_download_fn(&p, url);
+        let mut last_error = None;
+        // Try the release's url first, then the mirrors in the order they were added.
+        for source in network.download_urls(url)? {
+            match Self::fetch_file(network, &source, &partial, checksum, check) {
+                Ok(()) => {
+                    rename(&partial, &p).map_err(|e| {
+                        let _r = remove_file(&partial);
+                        e
+                    })?;
+                    return Ok(p);
+                }
+                Err(e) => {
+                    debug!("  downloading from {} failed: {}", source, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("no url to download from")))
+    }
+
+    // Download (or resume downloading) `url` into `partial`. The file is hashed as it is
+    // written, a resumed download's existing bytes are hashed first.
+    fn fetch_file(
+        network: &NetworkSettings,
+        url: &Url,
+        partial: &Path,
+        checksum: Option<&str>,
+        check: &dyn Fn(&Path) -> Result<()>,
+    ) -> Result<()> {
+        let offset = partial.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut resp = network.download_from(url, offset)?;
+        let mut digest = Sha256::new();
+        let fp = if resp.resumed() {
+            debug!("  resuming download from byte {}", offset);
+            if checksum.is_some() {
+                io::copy(&mut File::open(partial)?, &mut digest)?;
+            }
+            OpenOptions::new().append(true).open(partial)?
+        } else {
+            File::create(partial)?
+        };
+        let mut buf_writer = BufWriter::with_capacity(0x10_0000, fp);
+        io::copy(
+            &mut DigestReader::new(&mut resp, &mut digest),
+            &mut buf_writer,
+        )?;
+        buf_writer.flush()?;
+        let verified = match checksum {
+            Some(checksum) => digest.verify(checksum),
+            None => Ok(()),
+        };
+        // A server's error page saved in place of the file would silently break the install.
+        verified.and_then(|()| check(partial)).map_err(|e| {
+            let _r = remove_file(partial);
+            e
+        })
+    }
+
+    // Bundle id of the running Alfred, based on the version it reports in environment variables.
+    pub(super) fn alfred_bundle_id(alfred_version: Option<&str>) -> &'static str {
+        let major = alfred_version
+            .and_then(|v| v.split('.').next())
+            .and_then(|major| major.parse::<u32>().ok());
+        match major {
+            Some(3) => ALFRED_3_BUNDLE_ID,
+            _ => ALFRED_BUNDLE_ID,
+        }
+    }
+
+    // Ask Alfred to install the workflow bundle at `p`.
+    pub(super) fn open_in_alfred(p: &Path) -> Result<()> {
+        let bundle_id = Self::alfred_bundle_id(env::version().as_deref());
+        debug!("  opening {:?} with {}", p, bundle_id);
+        let status = Command::new("open")
+            .arg("-b")
+            .arg(bundle_id)
+            .arg(p)
+            .status()?;
+        if !status.success() {
+            return Err(anyhow!("couldn't open {:?} with Alfred: {}", p, status));
+        }
+        Ok(())
+    }
+
+    // Download release `v` (unless already downloaded) and open it with Alfred. A release is
+    // only installed automatically once: if the installed bundle doesn't report the new version
+    // (or the install is cancelled) we would otherwise keep installing it on every check.
+    pub(super) fn auto_install(
+        network: &NetworkSettings,
+        v: &Version,
+        url: &Url,
+        checksum: Option<&str>,
+        flag: &Path,
+    ) -> Result<()> {
+        let attempted: Option<Version> = crate::Data::load_from_path(flag).flatten();
+        if attempted.as_ref() == Some(v) {
+            debug!("  already tried to install {}, skipping", v);
+            return Ok(());
+        }
+        let p = Self::release_download_fn(Some(v))?;
+        let p = if p.exists() {
+            p
+        } else {
+            Self::download_with(network, url, checksum, p, &validate_workflow_bundle)?
+        };
+        crate::Data::save_to_path(flag, &Some(v))?;
+        Self::open_in_alfred(&p)
+    }
+
     // write version of latest avail. release (if any) to a cache file
     pub(super) fn write_last_check_status(
         p: &Path,
         updater_info: &Option<UpdateInfo>,
     ) -> Result<()> {
-        crate::Data::save_to_file(p, updater_info).map_err(|e| {
+        let _lock = FileLock::exclusive(p)?;
+        crate::Data::save_to_path(p, updater_info).map_err(|e| {
             let _r = remove_file(p);
             e
         })
@@ -223,33 +1094,25 @@ where
 
     // read version of latest avail. release (if any) from a cache file
     pub(super) fn read_last_check_status(p: &Path) -> Result<Option<UpdateInfo>> {
-        crate::Data::load_from_file(p).ok_or_else(|| anyhow!("no data in given path"))
+        let _lock = FileLock::shared(p)?;
+        crate::Data::load_from_path(p).ok_or_else(|| anyhow!("no data in given path"))
     }
 
-    pub(super) fn build_data_fn() -> Result<PathBuf> {
-        let workflow_name = env::workflow_name()
-            .unwrap_or_else(|| "YouForgotTo/フ:NameYourOwnWork}flowッ".to_string())
-            .chars()
-            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
-            .collect::<String>();
+    // Path of updater's state file.
+    pub(super) fn data_fn(&self) -> Result<PathBuf> {
+        self.state.location.path()
+    }
 
-        env::workflow_cache()
-            .ok_or_else(|| {
-                anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
-            })
-            .and_then(|mut data_path| {
-                env::workflow_uid()
-                    .ok_or_else(|| anyhow!("missing env variable for uid"))
-                    .map(|ref uid| {
-                        let filename = [uid, "-", workflow_name.as_str(), "-updater.json"].concat();
-                        data_path.push(filename);
-
-                        data_path
-                    })
-            })
+    // Path of updater's state file at the default location.
+    #[cfg(test)]
+    pub(super) fn build_data_fn() -> Result<PathBuf> {
+        StateLocation::default().path()
     }
 
-    pub(super) fn update_ready_async(&self, try_flag: bool) -> Result<bool> {
+    pub(super) fn update_ready_async(&self, wait: Wait) -> Result<bool> {
+        if !self.checks_enabled() {
+            return Ok(false);
+        }
         self.state
             .worker_state
             .borrow()
@@ -263,12 +1126,19 @@ where
                         .as_ref()
                         .ok_or_else(|| anyhow!("you need to use init() correctly!"))
                         .and_then(|rx| {
-                            let rr = if try_flag {
+                            let rr = match wait {
                                 // don't block while trying to receive
-                                rx.try_recv().map_err(|e| anyhow!(e.to_string()))
-                            } else {
+                                Wait::Poll => rx.try_recv().map_err(|e| anyhow!(e.to_string())),
                                 // block while waiting to receive
-                                rx.recv().map_err(|e| anyhow!(e.to_string()))
+                                Wait::Block => rx.recv().map_err(|e| anyhow!(e.to_string())),
+                                // block for a limited time, then fall back to cached info
+                                Wait::Timeout(timeout) => match rx.recv_timeout(timeout) {
+                                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                                        debug!("worker didn't respond in {:?}", timeout);
+                                        return Ok(());
+                                    }
+                                    rr => rr.map_err(|e| anyhow!(e.to_string())),
+                                },
                             };
                             rr.and_then(|msg| {
                                 let msg_status = msg.map(|update_info| {
@@ -287,6 +1157,9 @@ where
                                     });
                                     *mpsc.recvd_payload.borrow_mut() = Some(Ok(update_info));
                                 });
+                                *self.state.last_error.borrow_mut() =
+                                    msg_status.as_ref().err().map(ToString::to_string);
+                                self.record_check_outcome(msg_status.is_ok());
                                 // save state regardless of content of msg
                                 self.save()?;
                                 msg_status?;
@@ -296,12 +1169,36 @@ where
                 }
                 Ok(())
             })?;
-        Ok(self
+        let update_avail = self
             .state
             .avail_release
             .borrow()
             .as_ref()
-            .map_or(false, |release| *self.current_version() < release.version))
+            .map_or(false, |release| self.is_update(&release.version));
+        if update_avail {
+            self.announce_update()?;
+        }
+        Ok(update_avail)
+    }
+
+    // Run `on_update_available` callback if the available release hasn't been reported yet.
+    fn announce_update(&self) -> Result<()> {
+        let mut callback = self.on_update.borrow_mut();
+        let callback = match callback.as_mut() {
+            Some(callback) => callback,
+            None => return Ok(()),
+        };
+        let info = match self.state.avail_release.borrow().clone() {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        if self.state.announced_version.borrow().as_ref() == Some(info.version()) {
+            return Ok(());
+        }
+        *self.state.announced_version.borrow_mut() = Some(info.version().clone());
+        self.save()?;
+        callback(&info);
+        Ok(())
     }
 
     #[allow(dead_code)]
@@ -315,14 +1212,13 @@ where
             let rx_option = mpsc.rx.borrow();
             let rx = rx_option.as_ref().unwrap();
             let rr = rx.recv();
-            if rr.is_ok() {
-                let msg = rr.as_ref().unwrap();
-                if msg.is_ok() {
-                    let update_info = msg.as_ref().unwrap();
-                    *self.state.avail_release.borrow_mut() = update_info.clone();
-                    *mpsc.recvd_payload.borrow_mut() = Some(Ok(update_info.clone()));
-                } else {
-                    return Err(anyhow!(format!("{:?}", msg.as_ref().unwrap_err())));
+            if let Ok(ref msg) = rr {
+                match msg {
+                    Ok(update_info) => {
+                        *self.state.avail_release.borrow_mut() = update_info.clone();
+                        *mpsc.recvd_payload.borrow_mut() = Some(Ok(update_info.clone()));
+                    }
+                    Err(e) => return Err(anyhow!(format!("{:?}", e))),
                 }
                 self.save()?;
             } else {
@@ -331,7 +1227,7 @@ where
             }
         }
         if let Some(ref updater_info) = *self.state.avail_release.borrow() {
-            if *self.current_version() < updater_info.version {
+            if self.is_update(&updater_info.version) {
                 Ok(true)
             } else {
                 Ok(false)
@@ -348,20 +1244,18 @@ where
         // Thus we update last_check to now and just save the updater state without asking
         // Releaser to do a remote call/check for us since we assume that user just downloaded
         // the workflow.
-        const LATEST_UPDATE_INFO_CACHE_FN: &str = "last_check_status.json";
-
         // file for status of last update check
-        let p = Self::build_data_fn()?.with_file_name(LATEST_UPDATE_INFO_CACHE_FN);
+        let p = self.data_fn()?.with_file_name(LATEST_UPDATE_INFO_CACHE_FN);
 
         // make a network call to see if a newer version is avail.
         // save the result of call to cache file.
         let ask_releaser_for_update = || -> Result<bool> {
-            let (v, url) = self.releaser.borrow().latest_release()?;
-            let update_avail = *self.current_version() < v;
+            let release = self.releaser.borrow().fetch_latest_release()?;
+            let update_avail = self.is_update(release.version());
 
             let now = Utc::now();
             let payload = {
-                let mut info = UpdateInfo::new(v, url);
+                let mut info = UpdateInfo::new(&release);
                 info.set_fetched_at(now);
                 Some(info)
             };
@@ -386,7 +1280,7 @@ where
             Self::read_last_check_status(&p)
                 .map(|last_check_status| {
                     last_check_status.map_or(false, |last_update_info| {
-                        *self.current_version() < last_update_info.version
+                        self.is_update(&last_update_info.version)
                     })
                     // .unwrap_or(false)
                 })
@@ -0,0 +1,66 @@
+// Parse a human readable interval into seconds. An interval is one or more numbers, each
+// followed by a unit: `w` (weeks), `d` (days), `h` (hours), `m` (minutes) or `s` (seconds),
+// e.g. `7d`, `12h` or `1d12h`. A number without unit is in seconds.
+pub(super) fn parse_interval(interval: &str) -> Option<i64> {
+    let interval = interval.trim().to_lowercase();
+    if interval.is_empty() {
+        return None;
+    }
+    if let Ok(secs) = interval.parse::<i64>() {
+        return Some(secs).filter(|secs| *secs >= 0);
+    }
+
+    let mut total: i64 = 0;
+    let mut number = String::new();
+    for c in interval.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c.is_whitespace() && number.is_empty() {
+            continue;
+        }
+        let unit = match c {
+            'w' => 7 * 24 * 60 * 60,
+            'd' => 24 * 60 * 60,
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let n = number.parse::<i64>().ok()?;
+        total = total.checked_add(n.checked_mul(unit)?)?;
+        number.clear();
+    }
+    // A trailing number needs a unit too: `1d12` is most likely a typo.
+    if number.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_human_intervals() {
+        assert_eq!(Some(7 * 24 * 60 * 60), parse_interval("7d"));
+        assert_eq!(Some(12 * 60 * 60), parse_interval("12h"));
+        assert_eq!(Some(36 * 60 * 60), parse_interval("1d12h"));
+        assert_eq!(Some(36 * 60 * 60), parse_interval(" 1d 12H "));
+        assert_eq!(Some(14 * 24 * 60 * 60), parse_interval("2w"));
+        assert_eq!(Some(90), parse_interval("1m30s"));
+        assert_eq!(Some(3600), parse_interval("3600"));
+        assert_eq!(Some(0), parse_interval("0"));
+
+        assert_eq!(None, parse_interval(""));
+        assert_eq!(None, parse_interval("-5"));
+        assert_eq!(None, parse_interval("d"));
+        assert_eq!(None, parse_interval("1d12"));
+        assert_eq!(None, parse_interval("1y"));
+        assert_eq!(None, parse_interval("weekly"));
+        assert_eq!(None, parse_interval("99999999999999999999w"));
+    }
+}
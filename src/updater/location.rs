@@ -0,0 +1,90 @@
+use super::{anyhow, bail, env, PathBuf, Result};
+
+/// Folder where [`Updater`] saves its state and the status of update checks.
+///
+/// In the cache folder, files of the updater are kept in its `alfred_rs_updater` sub-folder,
+/// which [`Data::clear_cache()`] leaves alone.
+///
+/// See [`UpdaterBuilder::state_dir()`].
+///
+/// [`Updater`]: struct.Updater.html
+/// [`UpdaterBuilder::state_dir()`]: struct.UpdaterBuilder.html#method.state_dir
+/// [`Data::clear_cache()`]: ../data/struct.Data.html#method.clear_cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateDir {
+    /// Workflow's cache folder (default). Alfred (or the user) may purge it at any time.
+    Cache,
+    /// Workflow's data folder, which survives cache cleanups.
+    Data,
+}
+
+impl Default for StateDir {
+    fn default() -> Self {
+        StateDir::Cache
+    }
+}
+
+// Where the state file of updater lives. Status files of checks are saved next to it.
+#[derive(Debug, Clone, Default)]
+pub(super) struct StateLocation {
+    dir: StateDir,
+    file_name: Option<String>,
+}
+
+impl StateLocation {
+    pub(super) fn new(dir: StateDir, file_name: Option<String>) -> Result<Self> {
+        if let Some(ref name) = file_name {
+            if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+                bail!("invalid state file name: {:?}", name);
+            }
+        }
+        Ok(StateLocation { dir, file_name })
+    }
+
+    // Path of the state file.
+    pub(super) fn path(&self) -> Result<PathBuf> {
+        let name = self.file_name()?;
+        match self.dir {
+            StateDir::Cache => {
+                let p = updater_cache_dir()?.join(&name);
+                // Older versions kept the state file in the cache dir itself.
+                let legacy = crate::Data::cache_dir().map(|dir| dir.join(&name));
+                if let Some(legacy) = legacy.filter(|legacy| !p.exists() && legacy.exists()) {
+                    debug!("  moving updater state from {:?}", legacy);
+                    let _r = std::fs::rename(legacy, &p);
+                }
+                Ok(p)
+            }
+            StateDir::Data => crate::Data::data_dir()
+                .map(|dir| dir.join(name))
+                .ok_or_else(|| {
+                    anyhow!("missing env variable for data dir. forgot to set workflow bundle id?")
+                }),
+        }
+    }
+
+    // Name of the state file, workflow's uid and name unless one was set.
+    fn file_name(&self) -> Result<String> {
+        if let Some(ref name) = self.file_name {
+            return Ok(name.clone());
+        }
+        let workflow_name = env::workflow_name()
+            .unwrap_or_else(|| "YouForgotTo/フ:NameYourOwnWork}flowッ".to_string())
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let uid = env::workflow_uid().ok_or_else(|| anyhow!("missing env variable for uid"))?;
+        Ok([uid.as_str(), "-", workflow_name.as_str(), "-updater.json"].concat())
+    }
+}
+
+// Sub-directory of workflow's cache dir that holds the updater's files, created if needed.
+pub(super) fn updater_cache_dir() -> Result<PathBuf> {
+    let dir = crate::Data::cache_dir()
+        .ok_or_else(|| {
+            anyhow!("missing env variable for cache dir. forgot to set workflow bundle id?")
+        })?
+        .join(crate::data::UPDATER_CACHE_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
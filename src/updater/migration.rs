@@ -0,0 +1,106 @@
+use super::{bail, serde_json, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+// Version of the schema of updater's state file. Bump it, and add a step to `MIGRATIONS`,
+// when a change to the saved state can't be handled by `#[serde(default)]` alone (renamed or
+// retyped fields, ...).
+pub(super) const STATE_VERSION: u64 = 1;
+
+const STATE_VERSION_KEY: &str = "state_version";
+
+type Migration = fn(&mut Map<String, Value>);
+
+// Step `i` brings a state of version `i` to version `i + 1`.
+const MIGRATIONS: [Migration; STATE_VERSION as usize] = [unversioned_to_v1];
+
+// States written before the schema was versioned (alfred-rs 0.7 and earlier). Fields were
+// only ever added to them, with defaults, so they are read as they are.
+fn unversioned_to_v1(_state: &mut Map<String, Value>) {}
+
+// Bring a `saved` state written by any version of the crate up to date and read it.
+//
+// Fields that still can't be read after migrating are replaced with their values in `fresh`
+// one by one, so that the rest of the state (such as time of last check) survives.
+pub(super) fn migrate<S>(saved: Value, fresh: &S) -> Result<S>
+where
+    S: Serialize + DeserializeOwned,
+{
+    let mut state = match saved {
+        Value::Object(state) => state,
+        _ => bail!("saved state is not an object"),
+    };
+    let version = state
+        .get(STATE_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version < STATE_VERSION {
+        debug!("  migrating updater state from version {}", version);
+        for migration in MIGRATIONS.iter().skip(version as usize) {
+            migration(&mut state);
+        }
+        state.insert(STATE_VERSION_KEY.to_string(), Value::from(STATE_VERSION));
+    }
+    let saved = Value::Object(state);
+    if let Ok(state) = serde_json::from_value(saved.clone()) {
+        return Ok(state);
+    }
+
+    let mut salvaged = match serde_json::to_value(fresh)? {
+        Value::Object(fresh) => fresh,
+        _ => bail!("fresh state is not an object"),
+    };
+    if let Value::Object(saved) = saved {
+        for (key, value) in saved {
+            let previous = salvaged.insert(key.clone(), value);
+            if serde_json::from_value::<S>(Value::Object(salvaged.clone())).is_err() {
+                debug!("  dropping unreadable field of saved state: {}", key);
+                match previous {
+                    Some(previous) => salvaged.insert(key, previous),
+                    None => salvaged.remove(&key),
+                };
+            }
+        }
+    }
+    Ok(serde_json::from_value(Value::Object(salvaged))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct State {
+        #[serde(default)]
+        state_version: u64,
+        last_check: Option<String>,
+        count: u32,
+        #[serde(default)]
+        added: bool,
+    }
+
+    #[test]
+    fn it_migrates_saved_state() {
+        let fresh = State {
+            state_version: STATE_VERSION,
+            last_check: None,
+            count: 0,
+            added: false,
+        };
+
+        let saved = serde_json::json!({"last_check": "yesterday", "count": 3});
+        let state = migrate(saved, &fresh).unwrap();
+        assert_eq!(STATE_VERSION, state.state_version);
+        assert_eq!(Some("yesterday"), state.last_check.as_deref());
+        assert_eq!(3, state.count);
+
+        // Fields that can't be read are dropped, the rest is kept.
+        let saved = serde_json::json!({"last_check": "yesterday", "count": "three"});
+        let state = migrate(saved, &fresh).unwrap();
+        assert_eq!(Some("yesterday"), state.last_check.as_deref());
+        assert_eq!(0, state.count);
+
+        assert!(migrate(serde_json::json!([1, 2]), &fresh).is_err());
+    }
+}
@@ -17,26 +17,37 @@
 //!
 //! ## Notes:
 //! - The `github.com` hosted repository should have release items following `github`'s process.
-//! This can be done by tagging a commit and then manually building a release where you
-//! attach/upload `YourWorkflow.alfredworkflow` to the release page.
-//! You can easily create `YourWorkflow.alfredworkflow` file by using the [export feature] of
-//! Alfred in its preferences window.
+//!   This can be done by tagging a commit and then manually building a release where you
+//!   attach/upload `YourWorkflow.alfredworkflow` to the release page.
+//!   You can easily create `YourWorkflow.alfredworkflow` file by using the [export feature] of
+//!   Alfred in its preferences window.
 //!
 //! - The tag should follow all of the [semantic versioning] rules.
-//! The only exception to those rules is that you can prepend your
-//! semantic version tag with ASCII letter `v`: `v0.3.1` or `0.3.1`
+//!   The only exception to those rules is that you can prepend your
+//!   semantic version tag with ASCII letter `v`: `v0.3.1` or `0.3.1`.
+//!   Other prefixes and non-strict versions (such as `1.2`) can be enabled by
+//!   [`Updater::set_tag_prefix()`] and [`Updater::lenient_versions()`].
 //!
 //! # Note to workflow authors
 //! - Depending on network quality, checking if an update is available may take a long time.
-//! This module may spawn a worker thread so that the check does not block the main flow of your plugin.
-//! However given the limitations of Alfred's plugin architecture, the worker thread cannot outlive
-//! your plugin's executable. This means that you either have to wait/block for the worker thread,
-//! or if it is taking longer than a desirable time, you will have to abandon it.
-//! See the example for more details.
+//!   This module may spawn a worker thread so that the check does not block the main flow of your plugin.
+//!   However given the limitations of Alfred's plugin architecture, the worker thread cannot outlive
+//!   your plugin's executable. This means that you either have to wait/block for the worker thread,
+//!   or if it is taking longer than a desirable time, you will have to abandon it.
+//!   See the example for more details.
 //! - Workflow authors should make sure that _released_ workflow bundles have
-//! their version set in [Alfred's preferences window]. However, this module provides
-//! [`set_version()`] to set the version during runtime.
+//!   their version set in [Alfred's preferences window]. However, this module provides
+//!   [`set_version()`] to set the version during runtime.
+//! - Users can turn off update checks without any code changes by setting the
+//!   `alfred_workflow_update_disabled` variable to `1` in the workflow's configuration sheet.
+//!   [`init()`] and [`update_ready()`] then report that no update is available without
+//!   talking to the remote server. See [`is_enabled()`].
+//! - Users can also change how often updates are checked by setting the
+//!   `alfred_workflow_update_interval` variable to an interval such as `7d` or `12h`.
+//!   See [`set_interval()`].
 //!
+//! [`init()`]: struct.Updater.html#method.init
+//! [`is_enabled()`]: struct.Updater.html#method.is_enabled
 //! [`Releaser`]: trait.Releaser.html
 //! [`Updater`]: struct.Updater.html
 //! [`update_ready()`]: struct.Updater.html#method.update_ready
@@ -44,6 +55,8 @@
 //! [`download_latest()`]: struct.Updater.html#method.download_latest
 //! [`Updater::gh()`]: struct.Updater.html#method.gh
 //! [`Updater::new()`]: struct.Updater.html#method.new
+//! [`Updater::set_tag_prefix()`]: struct.Updater.html#method.set_tag_prefix
+//! [`Updater::lenient_versions()`]: struct.Updater.html#method.lenient_versions
 //! [semantic versioning]: https://semver.org
 //! [export feature]: https://www.alfredapp.com/help/workflows/advanced/sharing-workflows/
 //! [Alfred's preferences window]: https://www.alfredapp.com/help/workflows/advanced/variables/
@@ -121,19 +134,33 @@
 
 use super::{anyhow, bail, chrono, env_logger, semver, serde_json, url, Result};
 use crate::env;
+use alfred::{Item, ItemBuilder};
 use chrono::prelude::*;
 use chrono::Duration;
+#[cfg(feature = "updater")]
 use reqwest;
 use semver::Version;
 use std::cell::RefCell;
 use std::env as StdEnv;
-use std::fs::{remove_file, File};
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::fs::remove_file;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Receiver;
 use url::Url;
+#[cfg(feature = "updater-async")]
+mod asynchronous;
+mod builder;
+mod bundle;
+mod checksum;
+mod history;
+mod http;
 mod imp;
+mod interval;
+mod location;
+mod migration;
+mod policy;
 mod releaser;
+mod retry;
+mod version;
 
 #[cfg(test)]
 mod tests;
@@ -144,8 +171,20 @@ mod tests;
 /// [`set_interval()`]: struct.Updater.html#method.set_interval
 pub const UPDATE_INTERVAL: i64 = 24 * 60 * 60;
 
+#[cfg(feature = "updater-async")]
+pub use self::asynchronous::AsyncUpdater;
+pub use self::builder::UpdaterBuilder;
+pub use self::history::CheckRecord;
+pub use self::http::NetworkSettings;
+pub use self::http::RedirectPolicy;
+pub use self::imp::UpdateInfo;
+pub use self::location::StateDir;
+pub use self::policy::UpdatePolicy;
 pub use self::releaser::GithubReleaser;
+pub use self::releaser::ReleaseInfo;
 pub use self::releaser::Releaser;
+pub use self::retry::RetryPolicy;
+use self::version::parse_lenient;
 
 /// Struct to check for & download the latest release of workflow from a remote server.
 pub struct Updater<T>
@@ -154,8 +193,11 @@ where
 {
     state: imp::UpdaterState,
     releaser: RefCell<T>,
+    on_update: RefCell<Option<UpdateCallback>>,
 }
 
+type UpdateCallback = Box<dyn FnMut(&UpdateInfo) + Send>;
+
 impl Updater<GithubReleaser> {
     /// Create an `Updater` object that will interface with a `github` repository.
     ///
@@ -199,6 +241,197 @@ impl Updater<GithubReleaser> {
 
         Self::load_or_new(releaser)
     }
+
+    /// Create a builder for an `Updater` that will interface with a `github` repository.
+    ///
+    /// The builder lets all settings be configured before the updater's state is saved for the
+    /// first time. See [`UpdaterBuilder`] for an example.
+    ///
+    /// [`UpdaterBuilder`]: struct.UpdaterBuilder.html
+    pub fn builder<S: Into<String>>(repo_name: S) -> UpdaterBuilder<GithubReleaser> {
+        UpdaterBuilder::new(repo_name)
+    }
+
+    /// Consider pre-release versions (such as `v1.2.0-beta.1`) when checking for the latest
+    /// release.
+    ///
+    /// By default only the release marked as *latest* on `github.com` is considered, which
+    /// excludes pre-releases. When enabled, all published releases are listed and the one with
+    /// the highest semantic version is reported, so authors can ship betas to their testers.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.include_prereleases(true);
+    /// # }
+    /// ```
+    pub fn include_prereleases(&mut self, include: bool) {
+        self.releaser.get_mut().set_include_prereleases(include);
+    }
+
+    /// Follow a release channel: `"stable"` (the default) or the name of a pre-release channel
+    /// such as `"beta"`.
+    ///
+    /// Following a pre-release channel is similar to [`include_prereleases(true)`] except only
+    /// pre-releases whose version's pre-release identifier starts with the channel's name
+    /// are considered, e.g. `1.3.0-beta.2` for `"beta"` but not `1.3.0-alpha.1`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_channel("beta");
+    /// # }
+    /// ```
+    ///
+    /// [`include_prereleases(true)`]: struct.Updater.html#method.include_prereleases
+    pub fn set_channel(&mut self, channel: &str) {
+        self.releaser.get_mut().set_channel(channel);
+    }
+
+    /// Set the prefix that release tags start with, e.g. `workflow-v` or `alfred/`.
+    ///
+    /// By default an optional leading `v` is stripped from tags before parsing them as
+    /// semantic versions. When a prefix is set, it is stripped instead and releases whose tags
+    /// don't start with it are ignored. This lets repositories that tag several artifacts
+    /// (such as `cli-v1.0.0` and `workflow-v2.1.0`) still be used for updating the workflow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// // Tags look like `workflow-v1.2.3`
+    /// updater.set_tag_prefix("workflow-v");
+    /// # }
+    /// ```
+    pub fn set_tag_prefix<S: Into<String>>(&mut self, prefix: S) {
+        self.releaser.get_mut().set_tag_prefix(prefix.into());
+    }
+
+    /// Accept release tags that aren't strictly semantic versions.
+    ///
+    /// By default releases whose tags (after removing the prefix) aren't valid
+    /// [semantic versions] are ignored. When enabled, common non-strict forms are normalized:
+    /// missing components become zero (`1.2` is `1.2.0`), leading zeros are dropped
+    /// (`2024.05.01` is `2024.5.1`) and components after the third are kept as build metadata
+    /// (`1.2.3.4` is `1.2.3+4`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// // Tags look like `v2024.05.01`
+    /// updater.lenient_versions(true);
+    /// # }
+    /// ```
+    ///
+    /// [semantic versions]: https://semver.org
+    pub fn lenient_versions(&mut self, lenient: bool) {
+        self.releaser.get_mut().set_lenient_versions(lenient);
+    }
+
+    /// Only consider release assets whose file name matches the glob `pattern`.
+    ///
+    /// In the pattern `*` matches any sequence of characters and `?` matches a single
+    /// character. This lets a repository that releases several workflows (or extra binaries)
+    /// direct each workflow's `Updater` to its own `.alfredworkflow` asset. The asset still
+    /// needs to be a workflow bundle, the pattern only narrows down the candidates.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-workflows").expect("cannot initiate Updater");
+    /// updater.set_asset_pattern("pinboard-*.alfredworkflow");
+    /// # }
+    /// ```
+    pub fn set_asset_pattern<S: Into<String>>(&mut self, pattern: S) {
+        self.releaser.get_mut().set_asset_pattern(pattern.into());
+    }
+
+    /// Query the latest release through `github.com`'s GraphQL api, authorized by `token`.
+    ///
+    /// Instead of the full `releases/latest` document of the REST api, a single small query
+    /// fetches only the release's tag and the names and urls of its files. This reduces the
+    /// payload and, being authenticated, checks count against the token owner's (much higher)
+    /// rate limit instead of the limit shared by everyone behind the same IP address. Release
+    /// notes are not fetched, so [`release_notes()`] reports none.
+    ///
+    /// `token` is a personal access token that can read the repository; GitHub doesn't accept
+    /// unauthenticated GraphQL queries. It is only kept in memory, never saved to disk. Listing
+    /// releases, pre-releases (see [`include_prereleases()`]) and looking past a latest release
+    /// without a workflow bundle still use the REST api.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// // Token set by user in workflow's configuration sheet
+    /// if let Ok(token) = env::var("github_token") {
+    ///     updater.use_graphql(token);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`release_notes()`]: struct.Updater.html#method.release_notes
+    /// [`include_prereleases()`]: struct.Updater.html#method.include_prereleases
+    pub fn use_graphql<S: Into<String>>(&mut self, token: S) {
+        self.releaser.get_mut().set_graphql_token(token.into());
+    }
 }
 
 impl<T> Updater<T>
@@ -225,7 +458,7 @@ where
     /// use semver::Version;
     ///
     /// use alfred_rs::Updater;
-    /// use alfred_rs::updater::Releaser;
+    /// use alfred_rs::updater::{Releaser, ReleaseInfo};
     /// # use std::env;
     /// # fn main() {
     ///
@@ -234,17 +467,14 @@ where
     ///
     /// // You need to actually implement the trait, following is just a mock.
     /// impl Releaser for MyPrivateHost {
-    ///     type SemVersion = Version;
-    ///     type DownloadLink = Url;
-    ///
     ///     fn new<S: Into<String>>(project_id: S) -> Self {
     ///         MyPrivateHost {}
     ///     }
     ///
-    ///     fn fetch_latest_release(&self) -> Result<(Version, Url)> {
+    ///     fn fetch_latest_release(&self) -> Result<ReleaseInfo> {
     ///         let version = Version::new(1, 0, 12);
     ///         let url = Url::parse("https://ci.remote.cc/release/latest")?;
-    ///         Ok((version, url))
+    ///         Ok(ReleaseInfo::new(version, url).with_notes("Bug fixes"))
     ///     }
     /// }
     ///
@@ -284,12 +514,12 @@ where
     /// Initializes `Updater` to fetch latest release information.
     ///
     /// - If it has been more than [`UPDATE_INTERVAL`] seconds (see [`set_interval()`]) since last check,
-    /// the method will spawn a worker thread.
-    /// In the background, the spawned thread will attempt to make a network call to fetch metadata of releases
-    /// *only if* `UPDATE_INTERVAL` seconds has passed since the last network call.
+    ///   the method will spawn a worker thread.
+    ///   In the background, the spawned thread will attempt to make a network call to fetch metadata of releases
+    ///   *only if* `UPDATE_INTERVAL` seconds has passed since the last network call.
     ///
     /// - All calls, which happen before the `UPDATE_INTERVAL` seconds, will initialize the `Updater`
-    /// by using a local cache to report metadata about a release.
+    ///   by using a local cache to report metadata about a release.
     ///
     /// For `Updater`s talking to `github.com`, the worker thread will only fetch a small
     /// metadata information to extract the version of the latest release.
@@ -297,6 +527,15 @@ where
     /// To check on status of worker thread and to get latest release status, use either of
     /// [`update_ready()`] or [`try_update_ready()`] methods.
     ///
+    /// If [`set_detached_check()`] is enabled, a detached process is spawned instead of the
+    /// worker thread. In that detached process, this method makes the check and exits the
+    /// process instead of returning.
+    ///
+    /// Before starting a check, a quick connection to the [`Releaser`]'s server is attempted
+    /// (see [`Releaser::probe_url()`]). If it fails, no check is started and the status cached
+    /// since last check is reported, as the network is clearly down. The probe, name lookup
+    /// included, takes half a second at most (less if [`set_network_timeout()`] is shorter).
+    ///
     /// # Example
     ///
     /// ```rust,no_run
@@ -340,11 +579,15 @@ where
     ///
     /// # Errors
     /// Followings can cause the method return an error:
-    /// - A worker thread cannot be spawned
+    /// - A worker thread (or detached process) cannot be spawned
     /// - Alfred environment variable error
     /// - File IO error
     ///
     /// [`set_interval()`]: struct.Updater.html#method.set_interval
+    /// [`set_detached_check()`]: struct.Updater.html#method.set_detached_check
+    /// [`set_network_timeout()`]: struct.Updater.html#method.set_network_timeout
+    /// [`Releaser`]: trait.Releaser.html
+    /// [`Releaser::probe_url()`]: trait.Releaser.html#method.probe_url
     /// [`update_ready()`]: struct.Updater.html#method.update_ready
     /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
     /// [`UPDATE_INTERVAL`]: constant.UPDATE_INTERVAL.html
@@ -357,34 +600,77 @@ where
         debug!("entering init");
 
         // file for status of last update check
-        let p = Self::build_data_fn()?.with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
+        let p = self
+            .data_fn()?
+            .with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
 
         let (tx, rx) = mpsc::channel();
 
-        if self.last_check().is_none() {
-            self.set_last_check(Utc::now());
-            self.save()?;
+        if !self.checks_enabled() {
+            debug!("  update checks are disabled");
             // This send is always successful
             tx.send(Ok(None)).unwrap();
-            debug!("  last check was set to now()");
-        } else if self.due_to_check() {
-            // it's time to talk to remote server
-            debug!(" past UPDATE_INTERVAL, calling start_releaser_worker");
+            *self.state.borrow_worker_mut() = Some(imp::MPSCState::new(rx));
+            return Ok(());
+        }
+
+        if self.in_detached_check() {
+            // We are the detached process, check now and wait for the result since our
+            // executable may exit as soon as this method returns.
+            debug!("  running as detached check, calling start_releaser_worker");
             self.start_releaser_worker(tx, p)?;
-        } else {
-            debug!("  not past UPDATE_INTERVAL yet, calling read_last_check_status");
-            let status = Self::read_last_check_status(&p)
+            *self.state.borrow_worker_mut() = Some(imp::MPSCState::new(rx));
+            if let Err(e) = self.update_ready_async(imp::Wait::Block) {
+                debug!("  detached check failed: {}", e);
+            }
+            // Nothing else is to be done in the detached process, don't run the rest of the
+            // workflow's code.
+            if cfg!(not(test)) {
+                std::process::exit(0);
+            }
+            return Ok(());
+        }
+
+        let cached_status = || {
+            Self::read_last_check_status(&p)
                 .map(|last_check| {
                     last_check.and_then(|info| {
                         debug!("  read last_check_status: {:?}", info);
-                        if self.current_version() < info.version() {
+                        if self.is_update(info.version()) {
                             Some(info)
                         } else {
                             None
                         }
                     })
                 })
-                .or(Ok(None));
+                .or(Ok(None))
+        };
+
+        if self.last_check().is_none() && !self.dry_run() {
+            self.set_last_check(Utc::now());
+            self.save()?;
+            // This send is always successful
+            tx.send(Ok(None)).unwrap();
+            debug!("  last check was set to now()");
+        } else if self.due_to_check() && self.offline() {
+            // Don't let the check wait for network timeouts, report what we had.
+            debug!(" past UPDATE_INTERVAL but offline, calling read_last_check_status");
+            tx.send(cached_status()).unwrap();
+        } else if self.due_to_check() && self.detached_check() {
+            // Results of the detached process are picked up by next invocations, meanwhile
+            // report what we had and don't spawn another one until next interval.
+            debug!(" past UPDATE_INTERVAL, calling spawn_detached_check");
+            Self::spawn_detached_check()?;
+            self.set_last_check(Utc::now());
+            self.save()?;
+            tx.send(cached_status()).unwrap();
+        } else if self.due_to_check() {
+            // it's time to talk to remote server
+            debug!(" past UPDATE_INTERVAL, calling start_releaser_worker");
+            self.start_releaser_worker(tx, p)?;
+        } else {
+            debug!("  not past UPDATE_INTERVAL yet, calling read_last_check_status");
+            let status = cached_status();
             debug!("  status: {:?}", status);
             tx.send(status).unwrap();
         }
@@ -393,6 +679,64 @@ where
         Ok(())
     }
 
+    /// Returns `false` if update checks are turned off.
+    ///
+    /// Users can turn off update checks by setting the `alfred_workflow_update_disabled`
+    /// workflow variable to `1` (or `true`), and workflows can turn them off with
+    /// [`set_enabled()`]. While disabled, [`init()`] doesn't talk to the
+    /// remote server, and [`update_ready()`], [`try_update_ready()`],
+    /// [`check_in_background()`] and [`should_notify()`] return `false`.
+    ///
+    /// [`set_enabled()`]: struct.Updater.html#method.set_enabled
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
+    /// [`check_in_background()`]: struct.Updater.html#method.check_in_background
+    /// [`should_notify()`]: struct.Updater.html#method.should_notify
+    pub fn is_enabled(&self) -> bool {
+        self.checks_enabled()
+    }
+
+    /// Turn update checks on or off.
+    ///
+    /// The setting is saved to the updater's state file right away and kept by later
+    /// invocations of the workflow, so a workflow can offer a *Disable automatic update checks*
+    /// command. While disabled, [`init()`] and [`update_ready()`] return immediately without
+    /// talking to the remote server (see [`is_enabled()`]). Checks are enabled by default.
+    ///
+    /// Turning checks on doesn't override users who turned them off through the
+    /// `alfred_workflow_update_disabled` workflow variable.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// # let cmd_line_disable_flag = true;
+    /// if cmd_line_disable_flag {
+    ///     updater.set_enabled(false)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Method fails if the updater's state cannot be saved.
+    ///
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`is_enabled()`]: struct.Updater.html#method.is_enabled
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        self.set_checks_enabled(enabled)
+    }
+
     /// Checks if a new update is available by waiting for the background thread to finish
     /// fetching release info (blocking).
     ///
@@ -412,11 +756,11 @@ where
     /// # Note
     ///
     /// - Since this method may block the current thread until a response is received from remote server,
-    /// workflow authors should consider scenarios where network connection is poor and the block can
-    /// take a long time (>1 second), and devise their workflow around it. An alternative to
-    /// this method is the non-blocking [`try_update_ready()`].
+    ///   workflow authors should consider scenarios where network connection is poor and the block can
+    ///   take a long time (>1 second), and devise their workflow around it. An alternative to
+    ///   this method is the non-blocking [`try_update_ready()`].
     /// - The *very first* call to this method will always return false since it is assumed that
-    /// user has just downloaded and installed the workflow.
+    ///   user has just downloaded and installed the workflow.
     ///
     /// # Example
     ///
@@ -452,7 +796,7 @@ where
         if self.state.borrow_worker().is_none() {
             bail!("update_ready_sync is deprecated. use init()");
         }
-        self.update_ready_async(false)
+        self.update_ready_async(imp::Wait::Block)
     }
 
     /// Try to get release info from background worker and see if a new update is available (non-blocking).
@@ -472,9 +816,9 @@ where
     /// # Note
     ///
     /// - To wait for the worker thread to deliver its release information you can use the blocking
-    /// [`update_ready()`] method.
+    ///   [`update_ready()`] method.
     /// - The *very first* call to this method will always return false since it is assumed that
-    /// user has just downloaded and installed the workflow.
+    ///   user has just downloaded and installed the workflow.
     ///
     /// # Example
     ///
@@ -516,7 +860,120 @@ where
         if self.state.borrow_worker().is_none() {
             bail!("update_ready_sync is deprecated. use init()");
         }
-        self.update_ready_async(true)
+        self.update_ready_async(imp::Wait::Poll)
+    }
+
+    /// Wait at most `timeout` for the worker thread to deliver release info and see if a new
+    /// update is available.
+    ///
+    /// This is a middle ground between the blocking [`update_ready()`] and the non-blocking
+    /// [`try_update_ready()`]. If the worker thread doesn't respond before the deadline, the
+    /// update status cached since last successful check is returned instead of an error, so
+    /// the workflow stays responsive on slow networks. The worker is not abandoned: a later
+    /// call can still receive its result.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() {
+    /// let updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.init().expect("cannot start the worker thread");
+    ///
+    /// // Perform other workflow related tasks...
+    ///
+    /// let is_ready = updater
+    ///     .update_ready_timeout(Duration::from_millis(300))
+    ///     .expect("cannot get update information");
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Same as [`update_ready()`], except that running out of time is not an error.
+    ///
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
+    pub fn update_ready_timeout(&self, timeout: std::time::Duration) -> Result<bool> {
+        if self.state.borrow_worker().is_none() {
+            bail!("update_ready_sync is deprecated. use init()");
+        }
+        self.update_ready_async(imp::Wait::Timeout(timeout))
+    }
+
+    /// Start a check for the latest release in background and report the update status known
+    /// from previous checks (non-blocking).
+    ///
+    /// This is an alternative to [`init()`] followed by [`update_ready()`], suited to how
+    /// Script Filters are run: many short-lived invocations. If a check is due, a worker
+    /// thread is spawned which, once the check finishes, saves its results (or its error) to
+    /// updater's files; the method doesn't wait for it. Results of the check are then picked up
+    /// by subsequent invocations of the workflow.
+    ///
+    /// The worker thread ends with the process: if the current invocation exits before the
+    /// check finishes, the check is lost. Use [`init()`] with [`set_detached_check()`] for
+    /// checks that outlive the workflow's process.
+    ///
+    /// The time of the check is saved when it starts, so no new check is started until the
+    /// update interval passes, even if the check is lost.
+    /// Same as [`update_ready()`], the *very first* call returns `false`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// if updater.check_in_background()? {
+    ///     // Add an item to let user know a new version is available.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// An error is returned if the worker thread cannot be spawned, or on Alfred environment
+    /// variable and file errors.
+    ///
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`set_detached_check()`]: struct.Updater.html#method.set_detached_check
+    pub fn check_in_background(&self) -> Result<bool> {
+        use self::imp::LATEST_UPDATE_INFO_CACHE_FN_ASYNC;
+        use std::sync::mpsc;
+
+        if !self.checks_enabled() {
+            return Ok(false);
+        }
+        if self.last_check().is_none() {
+            self.set_last_check(Utc::now());
+            self.save()?;
+            return Ok(false);
+        }
+        if self.due_to_check() && !self.offline() {
+            debug!(" past UPDATE_INTERVAL, checking in background");
+            let p = self
+                .data_fn()?
+                .with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
+            self.set_last_check(Utc::now());
+            self.save()?;
+            let (tx, rx) = mpsc::channel();
+            self.start_releaser_worker(tx, p)?;
+            *self.state.borrow_worker_mut() = Some(imp::MPSCState::new(rx));
+        }
+        Ok(self
+            .latest_avail_version()
+            .map_or(false, |v| self.is_update(&v)))
     }
 
     /// Set workflow's version to `version`.
@@ -549,11 +1006,14 @@ where
     ///
     /// [Alfred's preferences window]: https://www.alfredapp.com/help/workflows/advanced/variables/
     ///
+    /// Versions that aren't strictly semantic versions, such as `1.2` or `2024.05.01`, are
+    /// normalized (`1.2.0` and `2024.5.1` respectively).
+    ///
     /// # Panics
     /// The method will panic if the passed value `version` cannot be parsed as a semantic version compatible string.
     pub fn set_version<S: AsRef<str>>(&mut self, version: S) {
-        let v = Version::parse(version.as_ref())
-            .expect("version should follow semantic version rules.");
+        let v =
+            parse_lenient(version.as_ref()).expect("version should follow semantic version rules.");
         self.state.set_version(v);
 
         StdEnv::set_var("alfred_workflow_version", version.as_ref());
@@ -561,7 +1021,14 @@ where
 
     /// Set the interval between checks for a newer release (in seconds)
     ///
-    /// [Default value][`UPDATE_INTERVAL`] is 86,400 seconds (24 hrs).
+    /// [Default value][`UPDATE_INTERVAL`] is 86,400 seconds (24 hrs). See also
+    /// [`set_interval_duration()`].
+    ///
+    /// Users can override the interval without any code changes by setting the
+    /// `alfred_workflow_update_interval` variable in the workflow's configuration sheet to a
+    /// number of seconds or to numbers followed by units: `w` (weeks), `d` (days), `h` (hours),
+    /// `m` (minutes) or `s` (seconds), such as `7d`, `12h` or `1d12h`. Invalid values are
+    /// ignored.
     ///
     /// # Example
     /// Set interval to be 7 days
@@ -581,29 +1048,873 @@ where
     /// # }
     /// ```
     /// [`UPDATE_INTERVAL`]: constant.UPDATE_INTERVAL.html
+    /// [`set_interval_duration()`]: struct.Updater.html#method.set_interval_duration
     pub fn set_interval(&mut self, tick: i64) {
         self.set_update_interval(tick);
     }
 
+    /// Set the interval between checks for a newer release.
+    ///
+    /// Same as [`set_interval()`], taking a [`Duration`] rather than a number of seconds.
+    /// Sub-second precision is ignored.
+    ///
+    /// # Example
+    /// Set interval to be 12 hours
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// use std::time::Duration;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_interval_duration(Duration::from_secs(12 * 60 * 60));
+    /// # }
+    /// ```
+    /// [`set_interval()`]: struct.Updater.html#method.set_interval
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_interval_duration(&mut self, interval: std::time::Duration) {
+        let secs = interval.as_secs().min(i64::MAX as u64) as i64;
+        self.set_update_interval(secs);
+    }
+
+    /// Add a random delay of up to `max` seconds to the update interval.
+    ///
+    /// Without jitter, thousands of installs of a popular workflow that checked right after a
+    /// release keep hitting the release server at the same wall-clock cadence, which can run
+    /// into rate limits. With jitter, a new random delay between zero and `max` seconds is
+    /// picked (and saved) every time a check is made, and the next check waits for the update
+    /// interval plus that delay.
+    ///
+    /// Disabled (zero) by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// // Check every 24 to 27 hours.
+    /// updater.set_interval_jitter(3 * 60 * 60);
+    /// # }
+    /// ```
+    pub fn set_interval_jitter(&mut self, max: i64) {
+        self.set_max_jitter(max);
+    }
+
+    /// Set the minimum number of seconds between two update prompts, see [`should_notify()`].
+    ///
+    /// Default is 24 hours, same as [`UPDATE_INTERVAL`].
+    ///
+    /// [`should_notify()`]: struct.Updater.html#method.should_notify
+    /// [`UPDATE_INTERVAL`]: constant.UPDATE_INTERVAL.html
+    pub fn set_notify_interval(&mut self, tick: i64) {
+        self.set_notify_interval_secs(tick);
+    }
+
+    /// Set how the worker thread retries a failed check for the latest release.
+    ///
+    /// By default only a single attempt is made, so a transient network error fails the whole
+    /// check. See [`RetryPolicy`] for details.
+    ///
+    /// # Example
+    /// Make up to 3 attempts, waiting 250ms and then 500ms (with random jitter) between them.
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// use alfred_rs::updater::RetryPolicy;
+    /// use std::time::Duration;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_retry(RetryPolicy::new(3, Duration::from_millis(250)).jitter(true));
+    /// # }
+    /// ```
+    /// [`RetryPolicy`]: struct.RetryPolicy.html
+    pub fn set_retry(&mut self, policy: RetryPolicy) {
+        self.set_retry_policy(policy);
+    }
+
+    /// Set which newer releases are reported as available updates.
+    ///
+    /// By default ([`UpdatePolicy::All`]) any newer release is reported. Use
+    /// [`UpdatePolicy::MinorOnly`] or [`UpdatePolicy::PatchOnly`] to avoid prompting users to
+    /// upgrade across major (or minor) versions, e.g. when such releases need manual migration.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// use alfred_rs::updater::UpdatePolicy;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_update_policy(UpdatePolicy::MinorOnly);
+    /// # }
+    /// ```
+    ///
+    /// [`UpdatePolicy::All`]: enum.UpdatePolicy.html#variant.All
+    /// [`UpdatePolicy::MinorOnly`]: enum.UpdatePolicy.html#variant.MinorOnly
+    /// [`UpdatePolicy::PatchOnly`]: enum.UpdatePolicy.html#variant.PatchOnly
+    pub fn set_update_policy(&mut self, policy: UpdatePolicy) {
+        self.set_policy(policy);
+    }
+
+    /// Automatically download and install new releases.
+    ///
+    /// When enabled, the worker thread started by [`init()`] downloads a newly found release
+    /// (that is allowed by the [update policy]) and opens it with Alfred, which asks the user to
+    /// confirm the upgrade. This happens before [`update_ready()`] reports the new release, so
+    /// calling it waits for the install to start.
+    ///
+    /// Each release is installed automatically only once. This prevents install loops when, for
+    /// example, the installed bundle doesn't report the new version or the user declines
+    /// installing it. Errors during installing are ignored, the release is still reported by
+    /// [`update_ready()`].
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.set_auto_install(true);
+    /// updater.init()?;
+    /// // ...
+    /// updater.update_ready()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [update policy]: struct.Updater.html#method.set_update_policy
+    pub fn set_auto_install(&mut self, auto_install: bool) {
+        self.set_auto_install_mode(auto_install);
+    }
+
+    /// Check for updates in a detached process that outlives the workflow's executable.
+    ///
+    /// A worker thread spawned by [`init()`] dies with the workflow's process, so a slow check
+    /// is wasted if Alfred's Script Filter exits before it finishes. When this mode is enabled
+    /// and a check is due, [`init()`] instead runs the workflow's executable again (with the same
+    /// arguments, in a new session and with its output discarded) as a detached process. The
+    /// detached process performs the check in its own call to [`init()`], blocking until it
+    /// finishes, saves the results and exits: [`init()`] never returns to the workflow's code
+    /// in that process. These results are then picked up by later invocations of the workflow,
+    /// while the current one reports the status cached since last check.
+    ///
+    /// The detached process runs the workflow's code up to [`init()`], so this mode has to be
+    /// enabled before calling [`init()`], and the updater should be set up and initialized
+    /// before the workflow does anything else. Use [`is_detached_check()`] to skip work that
+    /// shouldn't be repeated by the detached process before [`init()`].
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.set_detached_check(true);
+    /// // The detached process exits here, once the check is done.
+    /// updater.init()?;
+    /// // ...
+    /// updater.update_ready()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`is_detached_check()`]: struct.Updater.html#method.is_detached_check
+    pub fn set_detached_check(&mut self, detached: bool) {
+        self.set_detached_check_mode(detached);
+    }
+
+    /// Returns `true` if the current process is the detached process spawned to check for
+    /// updates, see [`set_detached_check()`].
+    ///
+    /// [`set_detached_check()`]: struct.Updater.html#method.set_detached_check
+    pub fn is_detached_check(&self) -> bool {
+        self.in_detached_check()
+    }
+
+    /// Check for updates without writing any files or downloading releases.
+    ///
+    /// In a dry run the updater talks to [`Releaser`] as usual, so [`update_ready()`] and the
+    /// other methods report what the server returns, but it never saves its state or the
+    /// status of checks to the workflow's data and cache folders. Methods that download a
+    /// release (such as [`download_latest()`] and [`update_item()`]) return the path the file
+    /// would be saved to without downloading it, and nothing is installed (neither by
+    /// [`install_latest()`] nor [automatically]). Checks are never made in a
+    /// [detached process], since its results could not be saved.
+    ///
+    /// This lets authors exercise their update UI during development without touching the
+    /// real files or hitting the server's rate limits with a check on every run: saved state is
+    /// still read, and a check is made by [`init()`] once it is due. Unlike a freshly installed
+    /// workflow, a dry run without saved state checks right away. Note that [`Updater::gh()`]
+    /// saves the initial state of the updater before this method can be called, use
+    /// [`UpdaterBuilder::dry_run()`] to avoid that.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::builder("spamwax/alfred-pinboard-rs")
+    ///     .dry_run(cfg!(debug_assertions))
+    ///     .build()?;
+    /// updater.init()?;
+    /// if updater.update_ready()? {
+    ///     // Show the update item, nothing is downloaded in debug builds.
+    ///     let item = updater.update_item()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`Releaser`]: trait.Releaser.html
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    /// [`update_item()`]: struct.Updater.html#method.update_item
+    /// [`install_latest()`]: struct.Updater.html#method.install_latest
+    /// [automatically]: struct.Updater.html#method.set_auto_install
+    /// [detached process]: struct.Updater.html#method.set_detached_check
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`UpdaterBuilder::dry_run()`]: struct.UpdaterBuilder.html#method.dry_run
+    /// [`Updater::gh()`]: struct.Updater.html#method.gh
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.set_dry_run_mode(dry_run);
+    }
+
+    /// Set the timeout for network operations of the updater.
+    ///
+    /// The timeout applies both to establishing a connection and to the whole request, for
+    /// checking the latest release as well as for [`download_latest()`]. Without it the HTTP
+    /// client's defaults are used, which may block a workflow for 30 seconds or more.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// use std::time::Duration;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_network_timeout(Duration::from_secs(5));
+    /// # }
+    /// ```
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    pub fn set_network_timeout(&mut self, timeout: std::time::Duration) {
+        self.update_network(|settings| settings.set_timeout(timeout));
+    }
+
+    /// Send all network requests of the updater through the proxy at `url`.
+    ///
+    /// Supported schemes are `http`, `https`, `socks5` and `socks5h`. The proxy is used both for
+    /// checking the latest release and for [`download_latest()`].
+    ///
+    /// When no proxy is set, the standard `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY`
+    /// environment variables are respected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater
+    ///     .set_proxy("socks5://127.0.0.1:1080")
+    ///     .expect("invalid proxy url");
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// An error is returned if `url` cannot be parsed or has an unsupported scheme.
+    ///
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    pub fn set_proxy<S: AsRef<str>>(&mut self, url: S) -> Result<()> {
+        let proxy = Url::parse(url.as_ref())?;
+        let mut result = Ok(());
+        self.update_network(|settings| result = settings.set_proxy(proxy));
+        result
+    }
+
+    /// Add a mirror to download releases from when downloading from the release's own url
+    /// fails.
+    ///
+    /// `template` is the mirror's url where `{url}` is replaced by the full url of the release
+    /// and `{path}` by the path of that url. Mirrors are tried in the order they are added, which
+    /// helps users in regions where the release host (e.g. `github.com`'s asset CDN) is blocked.
+    /// Once download hosts are pinned (see [`pin_download_host()`]), only mirrors on those hosts
+    /// are used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn run() -> Result<()> {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// // A proxy that takes the original url
+    /// updater.add_download_mirror("https://gh-proxy.example.com/{url}")?;
+    /// // A mirror of github.com's release files
+    /// updater.add_download_mirror("https://mirror.example.com/github{path}")?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Method fails if `template` contains neither `{url}` nor `{path}`, or doesn't produce a
+    /// valid url.
+    ///
+    /// [`pin_download_host()`]: struct.Updater.html#method.pin_download_host
+    pub fn add_download_mirror<S: Into<String>>(&mut self, template: S) -> Result<()> {
+        let mut result = Ok(());
+        self.update_network(|settings| result = settings.add_mirror(template.into()));
+        result
+    }
+
+    /// Set which redirects are followed when checking for and downloading releases.
+    ///
+    /// By default ([`RedirectPolicy::Any`]) all redirects are followed. Workflows that install
+    /// updates automatically may want to make sure a request never ends up somewhere
+    /// unexpected: [`RedirectPolicy::HttpsOnly`] refuses plain `http` urls (whether a request
+    /// starts at one or is redirected to one) and [`RedirectPolicy::SameHost`] refuses
+    /// redirects to other hosts. Requests that go against the policy fail.
+    ///
+    /// Note that `github.com` redirects downloads of release files to its CDN on another host,
+    /// so `SameHost` can't be used to download releases hosted on `github.com`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// use alfred_rs::updater::RedirectPolicy;
+    /// # fn main() {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater =
+    ///     Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// updater.set_redirect_policy(RedirectPolicy::HttpsOnly);
+    /// # }
+    /// ```
+    /// [`RedirectPolicy::Any`]: enum.RedirectPolicy.html#variant.Any
+    /// [`RedirectPolicy::HttpsOnly`]: enum.RedirectPolicy.html#variant.HttpsOnly
+    /// [`RedirectPolicy::SameHost`]: enum.RedirectPolicy.html#variant.SameHost
+    pub fn set_redirect_policy(&mut self, policy: RedirectPolicy) {
+        self.update_network(|settings| settings.set_redirect_policy(policy));
+    }
+
+    /// Only download releases whose url is on `host`. Can be called multiple times to allow
+    /// several hosts.
+    ///
+    /// Protects workflows (especially ones that [install updates automatically]) against a
+    /// compromised or misconfigured release entry that points to a file somewhere else: such
+    /// releases are still reported as available, but downloading them fails. By default
+    /// releases can be downloaded from any host.
+    ///
+    /// The release's url and every url its download is redirected to are checked. Note that
+    /// `github.com` redirects downloads of release files to its CDN, so the CDN's hosts have to
+    /// be pinned too. Mirrors added by [`add_download_mirror()`] that are not on a pinned host
+    /// are skipped, and the rest of the redirects are governed by [`set_redirect_policy()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// # use alfred_rs::Updater;
+    /// # use std::env;
+    /// # fn run() -> Result<()> {
+    /// # env::set_var("alfred_workflow_uid", "abcdef");
+    /// # env::set_var("alfred_workflow_data", env::temp_dir());
+    /// # env::set_var("alfred_workflow_cache", env::temp_dir());
+    /// # env::set_var("alfred_workflow_version", "0.0.0");
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.pin_download_host("github.com")?;
+    /// updater.pin_download_host("objects.githubusercontent.com")?;
+    /// updater.pin_download_host("release-assets.githubusercontent.com")?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Method fails if `host` is not a valid host name (it must not contain a scheme, port or
+    /// path).
+    ///
+    /// [install updates automatically]: struct.Updater.html#method.set_auto_install
+    /// [`add_download_mirror()`]: struct.Updater.html#method.add_download_mirror
+    /// [`set_redirect_policy()`]: struct.Updater.html#method.set_redirect_policy
+    pub fn pin_download_host<S: AsRef<str>>(&mut self, host: S) -> Result<()> {
+        let mut result = Ok(());
+        self.update_network(|settings| result = settings.pin_download_host(host.as_ref()));
+        result
+    }
+
     /// Check if it is time to ask remote server for latest updates.
     ///
-    /// It returns `true` if it has been more than [`UPDATE_INTERVAL`] seconds since we last
-    /// checked with server (i.e. ran [`update_ready()`]), otherwise returns false.
+    /// It returns `true` if it has been more than [`UPDATE_INTERVAL`] seconds (plus the delay
+    /// picked by [`set_interval_jitter()`], if enabled) since we last checked with server
+    /// (i.e. ran [`update_ready()`]), otherwise returns false.
+    ///
+    /// After a failed check the updater waits before trying again, so a dead repository or an
+    /// offline network doesn't cause a slow network call on every invocation of the workflow.
+    /// The wait grows with consecutive failures (1 hour, 6 hours, then 24 hours) but never
+    /// exceeds the update interval, and is reset by a successful check.
+    ///
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`set_interval_jitter()`]: struct.Updater.html#method.set_interval_jitter
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// # use alfred_rs::Updater;
+    /// # fn run() -> Result<()> {
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    ///
+    /// // Assuming it is has been UPDATE_INTERVAL seconds since last time we ran the
+    /// // `update_ready()` and there actually exists a new release:
+    /// assert_eq!(true, updater.due_to_check());
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`UPDATE_INTERVAL`]: constant.UPDATE_INTERVAL.html
+    pub fn due_to_check(&self) -> bool {
+        self.last_check().map_or(true, |dt| {
+            debug!("last check: {}", dt);
+            Utc::now().signed_duration_since(dt) > Duration::seconds(self.effective_interval())
+        }) && self
+            .failure_backoff_until()
+            .map_or(true, |dt| Utc::now() > dt)
+    }
+
+    /// Returns `true` if user should be prompted about the available update.
+    ///
+    /// Once a newer release is known, [`update_ready()`] returns `true` on every invocation of the
+    /// workflow, which can nag users when it is shown on every keystroke in a Script Filter.
+    /// This method returns `true` at most once per notify interval (24 hours by default, see
+    /// [`set_notify_interval()`]) and `false` otherwise, or when no newer release is known.
+    ///
+    /// Time of the prompt is saved in updater's state so the throttling works across invocations.
+    /// Like [`update_item()`], the method only looks at release info that was already received,
+    /// so it should be called after [`update_ready()`] or [`try_update_ready()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.init()?;
+    /// // Perform other workflow related tasks...
+    /// if updater.update_ready()? && updater.should_notify()? {
+    ///     // Add an item to let user know a new version is available.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// An error is returned if updater's state cannot be saved.
+    ///
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
+    /// [`update_item()`]: struct.Updater.html#method.update_item
+    /// [`set_notify_interval()`]: struct.Updater.html#method.set_notify_interval
+    pub fn should_notify(&self) -> Result<bool> {
+        self.notify_due()
+    }
+
+    /// Register a callback that is invoked the first time a newer release is observed.
+    ///
+    /// The callback runs on the calling thread from within [`update_ready()`] or
+    /// [`try_update_ready()`] once they find a newer release, and receives the release's
+    /// [`UpdateInfo`]. The reported version is saved in updater's state, so each release is
+    /// reported only once across invocations of the workflow. This can be used to post a macOS
+    /// notification or log the event.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.on_update_available(|info| {
+    ///     eprintln!("version {} is available", info.version());
+    /// });
+    /// updater.init()?;
+    /// // Perform other workflow related tasks...
+    /// updater.update_ready()?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
+    /// [`UpdateInfo`]: struct.UpdateInfo.html
+    pub fn on_update_available<F>(&mut self, callback: F)
+    where
+        F: FnMut(&UpdateInfo) + Send + 'static,
+    {
+        *self.on_update.get_mut() = Some(Box::new(callback));
+    }
+
+    /// Time of the last check with remote server, `None` if the workflow has not checked yet.
+    pub fn last_checked(&self) -> Option<DateTime<Utc>> {
+        self.last_check()
+    }
+
+    /// Time after which the next check with remote server will be made.
+    ///
+    /// It is the time of last check plus the update interval (see [`set_interval()`]) and its
+    /// jitter (see [`set_interval_jitter()`]), or later
+    /// if recent checks failed (see [`due_to_check()`]). If no check has been made yet, current
+    /// time is returned.
+    ///
+    /// Together with [`last_checked()`] it can be used to show something like
+    /// "last checked 2h ago, next check in 22h" to users.
+    ///
+    /// [`set_interval()`]: struct.Updater.html#method.set_interval
+    /// [`set_interval_jitter()`]: struct.Updater.html#method.set_interval_jitter
+    /// [`due_to_check()`]: struct.Updater.html#method.due_to_check
+    /// [`last_checked()`]: struct.Updater.html#method.last_checked
+    pub fn next_check(&self) -> DateTime<Utc> {
+        let next = self.last_check().map_or_else(Utc::now, |dt| {
+            dt + Duration::seconds(self.effective_interval())
+        });
+        self.failure_backoff_until()
+            .map_or(next, |backoff| next.max(backoff))
+    }
+
+    /// Error message of the last check for the latest release, if it failed.
+    ///
+    /// The error is saved in updater's state, so workflows can tell users why the previous
+    /// (automatic) check failed. It is cleared by the next successful check.
+    ///
+    /// The message is returned as an owned `String` rather than borrowed from the updater, as
+    /// checks (such as [`update_ready()`]) replace it without needing `&mut self`.
+    ///
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    pub fn last_error(&self) -> Option<String> {
+        self.state.last_error()
+    }
+
+    /// Method to download and save the latest release into workflow's cache dir.
+    ///
+    /// If the download and save operations are both successful, it returns name of file in which the
+    /// downloaded Alfred workflow bundle is saved.
+    ///
+    /// The downloaded workflow will be saved in the `alfred_rs_updater` sub-folder of the
+    /// workflow's cache folder, and it will be always renamed to
+    /// `latest_release_WORKFLOW-NAME.alfredworkflow`
+    ///
+    /// To install the downloaded release, your workflow needs to somehow open the saved file.
+    ///
+    /// Within shell, it can be installed by issuing something like:
+    /// ```bash
+    /// open -b com.runningwithcrayons.Alfred latest_release_WORKFLOW-NAME.alfredworkflow
+    /// ```
+    ///
+    /// Or you can add "Run script" object to your workflow and use environment variables set by
+    /// Alfred to automatically open the downloaded release:
+    /// ```bash
+    /// open -b com.runningwithcrayons.Alfred "$alfred_workflow_cache/latest_release_$alfred_workflow_name.alfredworkflow"
+    /// ```
+    ///
+    /// # Note
+    ///
+    /// The method may take longer than other Alfred-based actions to complete. Workflow authors using this crate
+    /// should implement strategies to prevent unpleasant long blocks of user's typical work flow.
+    ///
+    /// One option to initiate the download and upgrade process is to invoke your executable with a
+    /// different argument. The following snippet can be tied to a dedicated Alfred **Hotkey**
+    /// or **Script Filter** so that it is only executed when user explicitly asks for it:
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred;
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// # use std::io;
+    /// use alfred_rs::Updater;
+    /// use alfred::{ItemBuilder, json};
+    ///
+    /// # fn main() {
+    /// # let updater =
+    /// #    Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
+    /// # let cmd_line_download_flag = true;
+    /// if cmd_line_download_flag && updater.update_ready().unwrap() {
+    ///     match updater.download_latest() {
+    ///         Ok(downloaded_fn) => {
+    ///             json::write_items(io::stdout(), &[
+    ///                 ItemBuilder::new("New version of workflow is available!")
+    ///                              .subtitle("Click to upgrade!")
+    ///                              .arg(downloaded_fn.to_str().unwrap())
+    ///                              .variable("update_ready", "yes")
+    ///                              .valid(true)
+    ///                              .into_item()
+    ///             ]);
+    ///         },
+    ///         Err(e) => {
+    ///             // Show an error message to user or log it.
+    ///         }
+    ///     }
+    /// }
+    /// #    else {
+    /// #    }
+    /// # }
+    /// ```
+    ///
+    /// For the above example to automatically work, you then need to connect the output of the script
+    /// to an **Open File** action so that Alfred can install/upgrade the new version.
+    ///
+    /// As suggested in above example, you can add an Alfred variable to the item so that your workflow
+    /// can use it for further processing.
+    ///
+    /// If a previous download was interrupted, the partially downloaded file is kept in the
+    /// cache folder and the next call resumes from where it stopped (as long as the server
+    /// supports HTTP range requests, otherwise the download starts over).
+    ///
+    /// # Errors
+    /// Downloading latest workflow can fail if network error, file error or Alfred environment variable
+    /// errors happen, or if [`Releaser`] cannot produce a usable download url.
+    ///
+    /// The downloaded file is also checked to be a workflow bundle (a zip archive containing
+    /// `info.plist`). If it isn't, for example when a server's error page was received, the file
+    /// is deleted and an error is returned. The same happens if the [`Releaser`] reported a
+    /// [checksum] for the bundle (`github.com` does) and the downloaded file doesn't match it.
+    ///
+    /// [`Releaser`]: trait.Releaser.html
+    /// [checksum]: struct.ReleaseInfo.html#method.checksum
+    pub fn download_latest(&self) -> Result<PathBuf> {
+        let url = self
+            .state
+            .download_url()
+            .ok_or_else(|| anyhow!("no release info avail yet"))?;
+        let p = Self::release_download_fn(None)?;
+        self.download_release(&url, self.state.download_checksum().as_deref(), p)
+    }
+
+    /// Download the latest available release and save it to `path`.
+    ///
+    /// Works the same as [`download_latest()`] except that the bundle is saved to the given
+    /// file path rather than the workflow's cache folder. This way authors can place the bundle
+    /// where their install automation expects it, or keep multiple versions side by side.
+    /// Missing parent folders are not created.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// if updater.update_ready()? {
+    ///     let version = updater.latest_avail_version().unwrap();
+    ///     let saved = updater.download_latest_to(format!("/tmp/pinboard-{}.alfredworkflow", version))?;
+    ///     println!("saved to {}", saved.display());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Same as [`download_latest()`].
+    ///
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    pub fn download_latest_to<P: AsRef<Path>>(&self, path: P) -> Result<PathBuf> {
+        let url = self
+            .state
+            .download_url()
+            .ok_or_else(|| anyhow!("no release info avail yet"))?;
+        let checksum = self.state.download_checksum();
+        self.download_release(&url, checksum.as_deref(), path.as_ref().to_path_buf())
+    }
+
+    /// Download the latest available release (if not already downloaded) and install it.
+    ///
+    /// The bundle is saved in the workflow's cache folder and then opened with Alfred, which
+    /// asks the user to confirm installing the new version. The right Alfred application is
+    /// picked based on the version Alfred reports in its environment variables
+    /// (`com.runningwithcrayons.Alfred-3` for Alfred 3, `com.runningwithcrayons.Alfred`
+    /// otherwise). This does the same as the shell commands shown in [`download_latest()`].
+    ///
+    /// Path of the installed bundle is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.init()?;
+    /// if updater.update_ready()? {
+    ///     updater.install_latest()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
     ///
+    /// # Errors
+    /// Besides errors listed in [`download_latest()`], the method fails if no newer release is
+    /// known (see [`update_ready()`]) or if the bundle cannot be opened.
+    ///
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
     /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    pub fn install_latest(&self) -> Result<PathBuf> {
+        let version = self
+            .latest_avail_version()
+            .filter(|v| self.is_update(v))
+            .ok_or_else(|| anyhow!("no newer release available"))?;
+        // Downloads are moved in place only when complete, so an existing file can be reused.
+        let p = Self::release_download_fn(Some(&version))?;
+        let p = if p.exists() {
+            p
+        } else {
+            self.download_latest_to(p)?
+        };
+        if !self.dry_run() {
+            Self::open_in_alfred(&p)?;
+        }
+        Ok(p)
+    }
+
+    /// Ready-made Alfred item announcing the latest available release.
+    ///
+    /// The release is downloaded (if not already downloaded) to the workflow's cache folder and an
+    /// item titled "Version X.Y.Z is available" is returned, with its `arg` set to the path of
+    /// the downloaded bundle and an `update_ready` variable set to `yes`. Connecting the Script
+    /// Filter to an **Open File** action is then enough to let user install the new version,
+    /// as described in [`download_latest()`].
+    ///
+    /// `None` is returned if no newer release is known, so the method is meant to be called
+    /// after [`update_ready()`] or [`try_update_ready()`].
     ///
     /// # Example
     ///
     /// ```rust,no_run
+    /// # extern crate alfred;
     /// # extern crate alfred_rs;
     /// # use anyhow::Result;
-    /// # use alfred_rs::Updater;
-    /// # fn run() -> Result<()> {
-    /// let mut updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// # use std::io;
+    /// use alfred::json;
+    /// use alfred_rs::Updater;
     ///
-    /// // Assuming it is has been UPDATE_INTERVAL seconds since last time we ran the
-    /// // `update_ready()` and there actually exists a new release:
-    /// assert_eq!(true, updater.due_to_check());
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.init()?;
+    /// let mut items = Vec::new();
+    /// if updater.update_ready()? {
+    ///     items.extend(updater.update_item()?);
+    /// }
+    /// json::write_items(io::stdout(), &items)?;
     /// # Ok(())
     /// # }
     /// # fn main() {
@@ -611,135 +1922,334 @@ where
     /// # }
     /// ```
     ///
-    /// [`UPDATE_INTERVAL`]: constant.UPDATE_INTERVAL.html
-    pub fn due_to_check(&self) -> bool {
-        self.last_check().map_or(true, |dt| {
-            debug!("last check: {}", dt);
-            Utc::now().signed_duration_since(dt) > Duration::seconds(self.update_interval())
-        })
+    /// # Errors
+    /// Same as [`download_latest()`].
+    ///
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    /// [`update_ready()`]: struct.Updater.html#method.update_ready
+    /// [`try_update_ready()`]: struct.Updater.html#method.try_update_ready
+    pub fn update_item<'a>(&self) -> Result<Option<Item<'a>>> {
+        if !self.checks_enabled() {
+            return Ok(None);
+        }
+        let version = match self.latest_avail_version().filter(|v| self.is_update(v)) {
+            Some(version) => version,
+            None => return Ok(None),
+        };
+        let p = Self::release_download_fn(Some(&version))?;
+        let p = if p.exists() {
+            p
+        } else {
+            self.download_latest_to(p)?
+        };
+        let item = ItemBuilder::new(format!("Version {} is available", version))
+            .subtitle("Press ↵ to update")
+            .arg(p.to_string_lossy().into_owned())
+            .variable("update_ready", "yes")
+            .valid(true)
+            .into_item();
+        Ok(Some(item))
     }
 
-    /// Method to download and save the latest release into workflow's cache dir.
+    /// Delete downloaded releases that are no longer needed from the workflow's cache folder.
     ///
-    /// If the download and save operations are both successful, it returns name of file in which the
-    /// downloaded Alfred workflow bundle is saved.
+    /// Bundles saved by [`download_latest()`], [`download_version()`] or [`install_latest()`]
+    /// (including partial downloads) are deleted if they are at least `max_age` old, or if their
+    /// version is not newer than the workflow's current version. Files passed to
+    /// [`download_latest_to()`] are left alone.
     ///
-    /// The downloaded workflow will be saved in dedicated cache folder of the workflow, and it
-    /// will be always renamed to `latest_release_WORKFLOW-NAME.alfredworkflow`
+    /// Paths of deleted files are returned.
     ///
-    /// To install the downloaded release, your workflow needs to somehow open the saved file.
+    /// # Example
     ///
-    /// Within shell, it can be installed by issuing something like:
-    /// ```bash
-    /// open -b com.runningwithcrayons.Alfred latest_release_WORKFLOW-NAME.alfredworkflow
-    /// ```
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    /// use std::time::Duration;
     ///
-    /// Or you can add "Run script" object to your workflow and use environment variables set by
-    /// Alfred to automatically open the downloaded release:
-    /// ```bash
-    /// open -b com.runningwithcrayons.Alfred "$alfred_workflow_cache/latest_release_$alfred_workflow_name.alfredworkflow"
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// // Remove downloads older than 30 days
+    /// updater.cleanup(Duration::from_secs(30 * 24 * 60 * 60))?;
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
     /// ```
     ///
-    /// # Note
+    /// # Errors
+    /// Method fails if the cache folder cannot be read or a file cannot be deleted.
     ///
-    /// The method may take longer than other Alfred-based actions to complete. Workflow authors using this crate
-    /// should implement strategies to prevent unpleasant long blocks of user's typical work flow.
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    /// [`download_version()`]: struct.Updater.html#method.download_version
+    /// [`install_latest()`]: struct.Updater.html#method.install_latest
+    /// [`download_latest_to()`]: struct.Updater.html#method.download_latest_to
+    pub fn cleanup(&self, max_age: std::time::Duration) -> Result<Vec<PathBuf>> {
+        let stale = self.stale_downloads(max_age)?;
+        for p in &stale {
+            debug!("  removing stale download: {:?}", p);
+            remove_file(p)?;
+        }
+        Ok(stale)
+    }
+
+    /// Path of the file the updater's state is saved to.
     ///
-    /// One option to initiate the download and upgrade process is to invoke your executable with a
-    /// different argument. The following snippet can be tied to a dedicated Alfred **Hotkey**
-    /// or **Script Filter** so that it is only executed when user explicitly asks for it:
+    /// Status files of update checks are saved in the same folder. The location can be changed
+    /// with [`UpdaterBuilder::state_dir()`] and [`UpdaterBuilder::state_file_name()`].
+    ///
+    /// # Errors
+    /// An error is returned if Alfred's environment variables for the workflow's folders are
+    /// missing.
+    ///
+    /// [`UpdaterBuilder::state_dir()`]: struct.UpdaterBuilder.html#method.state_dir
+    /// [`UpdaterBuilder::state_file_name()`]: struct.UpdaterBuilder.html#method.state_file_name
+    pub fn state_file(&self) -> Result<PathBuf> {
+        self.data_fn()
+    }
+
+    /// Forget the results of previous update checks and delete the files the updater saved them
+    /// to.
+    ///
+    /// The updater's state file (see [`state_file()`]) and the status files of checks
+    /// (`last_check_status*.json`) next to it are deleted along with their `.lock` files, and
+    /// the updater behaves as if the workflow was just installed: no release is known and [`init()`] waits
+    /// for a full interval before checking (call [`init()`] again after resetting). Settings
+    /// such as the update interval are kept. Downloaded releases are left alone, see
+    /// [`cleanup()`].
+    ///
+    /// Useful for *reset* commands of a workflow and for tests.
     ///
     /// # Example
     ///
     /// ```rust,no_run
-    /// # extern crate alfred;
     /// # extern crate alfred_rs;
     /// # use anyhow::Result;
-    /// # use std::io;
     /// use alfred_rs::Updater;
-    /// use alfred::{ItemBuilder, json};
     ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// # let cmd_line_reset_flag = true;
+    /// if cmd_line_reset_flag {
+    ///     updater.reset_state()?;
+    /// }
+    /// # Ok(())
+    /// # }
     /// # fn main() {
-    /// # let updater =
-    /// #    Updater::gh("spamwax/alfred-pinboard-rs").expect("cannot initiate Updater");
-    /// # let cmd_line_download_flag = true;
-    /// if cmd_line_download_flag && updater.update_ready().unwrap() {
-    ///     match updater.download_latest() {
-    ///         Ok(downloaded_fn) => {
-    ///             json::write_items(io::stdout(), &[
-    ///                 ItemBuilder::new("New version of workflow is available!")
-    ///                              .subtitle("Click to upgrade!")
-    ///                              .arg(downloaded_fn.to_str().unwrap())
-    ///                              .variable("update_ready", "yes")
-    ///                              .valid(true)
-    ///                              .into_item()
-    ///             ]);
-    ///         },
-    ///         Err(e) => {
-    ///             // Show an error message to user or log it.
-    ///         }
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Method fails if a file cannot be deleted. In a [dry run] no file is deleted.
+    ///
+    /// [`state_file()`]: struct.Updater.html#method.state_file
+    /// [`init()`]: struct.Updater.html#method.init
+    /// [`cleanup()`]: struct.Updater.html#method.cleanup
+    /// [dry run]: struct.Updater.html#method.set_dry_run
+    pub fn reset_state(&self) -> Result<()> {
+        self.reset()
+    }
+
+    /// Returns the most recent update checks, oldest first.
+    ///
+    /// Every check made by the updater (in the background, detached or through
+    /// [`AsyncUpdater`]) is recorded in a file in workflow's data folder with its time, the
+    /// latest version it found or the error that made it fail. The last 50 checks are kept.
+    /// Useful for debugging reports of updates never showing up: a workflow can show the
+    /// history to its users or dump it to a log. Checks made in a [dry run] are not recorded.
+    ///
+    /// The history is not cleared by [`reset_state()`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// for check in updater.check_history()? {
+    ///     match check.version() {
+    ///         Some(v) => println!("{}: found {}", check.checked_at(), v),
+    ///         None => println!("{}: failed, {:?}", check.checked_at(), check.error()),
     ///     }
     /// }
-    /// #    else {
-    /// #    }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
     /// # }
     /// ```
     ///
-    /// For the above example to automatically work, you then need to connect the output of the script
-    /// to an **Open File** action so that Alfred can install/upgrade the new version.
+    /// # Errors
+    /// An error is returned if Alfred's environment variable for the workflow's data folder is
+    /// missing.
+    ///
+    /// [`AsyncUpdater`]: struct.AsyncUpdater.html
+    /// [`reset_state()`]: struct.Updater.html#method.reset_state
+    /// [dry run]: struct.Updater.html#method.set_dry_run
+    pub fn check_history(&self) -> Result<Vec<CheckRecord>> {
+        history::read(&history::history_fn()?)
+    }
+
+    /// Returns information about recent releases of the workflow, newest first.
     ///
-    /// As suggested in above example, you can add an Alfred variable to the item so that your workflow
-    /// can use it for further processing.
+    /// This method makes a blocking network call. Together with [`download_version()`] it
+    /// enables flows like offering an "install previous version" item when a new release turns
+    /// out to be broken.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// for release in updater.list_releases()? {
+    ///     println!("{} -> {}", release.version(), release.url());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
     ///
     /// # Errors
-    /// Downloading latest workflow can fail if network error, file error or Alfred environment variable
-    /// errors happen, or if [`Releaser`] cannot produce a usable download url.
+    /// Network errors or errors reported by [`Releaser`] are returned.
     ///
+    /// [`download_version()`]: struct.Updater.html#method.download_version
     /// [`Releaser`]: trait.Releaser.html
-    pub fn download_latest(&self) -> Result<PathBuf> {
-        let url = self
-            .state
-            .download_url()
-            .ok_or_else(|| anyhow!("no release info avail yet"))?;
-        let client = reqwest::blocking::Client::new();
-
-        client
-            .get(url)
-            .send()?
-            .error_for_status()
-            .map_err(Into::into)
-            .and_then(|mut resp| {
-                // Get workflow's dedicated cache folder & build a filename
-                let workflow_name = env::workflow_name()
-                    .unwrap_or_else(|| "WhyUNoNameYourOwnWorkflow".to_string())
-                    .chars()
-                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
-                    .collect::<String>();
-                let latest_release_downloaded_fn = env::workflow_cache()
-                    .ok_or_else(|| {
-                        anyhow!(
-                            "missing env variable for cache dir. forgot to set workflow bundle id?",
-                        )
-                    })
-                    .map(|mut cache_dir| {
-                        cache_dir
-                            .push(["latest_release_", &workflow_name, ".alfredworkflow"].concat());
-                        cache_dir
-                    })?;
-                // Save the file
-                File::create(&latest_release_downloaded_fn)
-                    .map_err(Into::into)
-                    .and_then(|fp| {
-                        let mut buf_writer = BufWriter::with_capacity(0x10_0000, fp);
-                        resp.copy_to(&mut buf_writer)?;
-                        Ok(())
-                    })
-                    .map_err(|e: anyhow::Error| {
-                        let _r = remove_file(&latest_release_downloaded_fn);
-                        e
-                    })?;
-                Ok(latest_release_downloaded_fn)
+    pub fn list_releases(&self) -> Result<Vec<ReleaseInfo>> {
+        self.releaser.borrow().fetch_releases()
+    }
+
+    /// Returns release notes of the latest available release, if any.
+    ///
+    /// For `github.com` hosted workflows this is the body of the release page. Like
+    /// [`latest_available()`], the method does not perform any network or disk IO.
+    ///
+    /// [`latest_available()`]: struct.Updater.html#method.latest_available
+    pub fn release_notes(&self) -> Option<String> {
+        self.latest_available()
+            .and_then(|info| info.notes().map(String::from))
+    }
+
+    /// Aggregate release notes of all releases newer than the current version, up to the
+    /// latest available one, so users can see everything that changed before they upgrade.
+    ///
+    /// Releases are listed newest first, each under a `## VERSION` heading followed by its
+    /// notes (if any). An empty string is returned if there are no newer releases.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.init()?;
+    /// if updater.update_ready()? {
+    ///     println!("{}", updater.changelog()?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Same as [`list_releases()`].
+    ///
+    /// [`list_releases()`]: struct.Updater.html#method.list_releases
+    pub fn changelog(&self) -> Result<String> {
+        let latest = self.latest_avail_version();
+        let changelog = self
+            .list_releases()?
+            .iter()
+            .filter(|r| self.is_update(r.version()))
+            .filter(|r| latest.as_ref().map_or(true, |latest| r.version() <= latest))
+            .map(|r| match r.notes() {
+                Some(notes) => format!("## {}\n\n{}", r.version(), notes.trim()),
+                None => format!("## {}", r.version()),
             })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Ok(changelog)
+    }
+
+    /// Download a specific `version` of the workflow into workflow's cache dir.
+    ///
+    /// The release is looked up in [`list_releases()`] and saved as
+    /// `release_WORKFLOW-NAME_VERSION.alfredworkflow`. It can be installed the same way as a
+    /// release downloaded by [`download_latest()`], which allows downgrading the workflow.
+    ///
+    /// # Errors
+    /// An error is returned if `version` is not among the available releases, or on network
+    /// and file errors.
+    ///
+    /// [`list_releases()`]: struct.Updater.html#method.list_releases
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    pub fn download_version(&self, version: &Version) -> Result<PathBuf> {
+        let release = self
+            .list_releases()?
+            .into_iter()
+            .find(|r| r.version() == version)
+            .ok_or_else(|| anyhow!("no release available for version {}", version))?;
+        let p = Self::release_download_fn(Some(version))?;
+        self.download_release(release.url(), release.checksum(), p)
+    }
+
+    /// Download a file attached to the latest release, whose name matches `name_pattern`, into
+    /// the `alfred_rs_updater` sub-folder of workflow's cache dir.
+    ///
+    /// Workflows that ship helper binaries, models or other data next to the workflow bundle can
+    /// use this method to fetch them. `name_pattern` is a glob where `*` matches any sequence of
+    /// characters and `?` matches a single character. The file is saved under its own name and
+    /// is downloaded the same way as the workflow bundle: interrupted downloads are resumed and
+    /// [download mirrors] are tried if the release's url fails. File permissions are not changed,
+    /// so binaries have to be made executable by the caller.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// let helper = updater.download_asset("x86_64-apple-darwin-*.tar.gz")?;
+    /// println!("helper saved to {}", helper.display());
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Besides network and file errors, an error is returned if the latest release has no file
+    /// matching `name_pattern` (or [`Releaser`] doesn't provide such files). The downloaded file
+    /// must have the size reported by [`Releaser`] and, if it's a workflow bundle, is checked
+    /// the same as in [`download_latest()`]. Otherwise it's deleted and an error is returned.
+    ///
+    /// [download mirrors]: struct.Updater.html#method.add_download_mirror
+    /// [`Releaser`]: trait.Releaser.html
+    /// [`download_latest()`]: struct.Updater.html#method.download_latest
+    pub fn download_asset(&self, name_pattern: &str) -> Result<PathBuf> {
+        let asset = self.releaser.borrow().fetch_latest_asset(name_pattern)?;
+        let p = Self::asset_download_fn(asset.url())?;
+        self.download_release_asset(&asset, p)
     }
 
     /// Returns the version for the latest downloadable workflow from [`Releaser`].
@@ -760,6 +2270,39 @@ where
         self.state.latest_avail_version()
     }
 
+    /// Returns information about the latest release fetched from [`Releaser`], such as its
+    /// version, download url, publish date and size of the workflow bundle.
+    /// `None` is returned if no release info has yet been fetched from server.
+    ///
+    /// Same as [`latest_avail_version()`], this method does not perform any network or disk IO.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate alfred_rs;
+    /// # use anyhow::Result;
+    /// use alfred_rs::Updater;
+    ///
+    /// # fn run() -> Result<()> {
+    /// let updater = Updater::gh("spamwax/alfred-pinboard-rs")?;
+    /// updater.init()?;
+    /// if updater.update_ready()? {
+    ///     let info = updater.latest_available().unwrap();
+    ///     println!("new version {} is available", info.version());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {
+    /// # run();
+    /// # }
+    /// ```
+    ///
+    /// [`Releaser`]: trait.Releaser.html
+    /// [`latest_avail_version()`]: struct.Updater.html#method.latest_avail_version
+    pub fn latest_available(&self) -> Option<UpdateInfo> {
+        self.state.latest_available()
+    }
+
     /// Get workflow's current version
     pub fn current_version(&self) -> &Version {
         self.state.current_version()
@@ -0,0 +1,64 @@
+use super::Version;
+
+/// Policy deciding which newer releases are reported as available updates.
+///
+/// Conservative workflow authors can use it to avoid prompting users to jump to a new major
+/// (or minor) version automatically, e.g. when such a release requires manual migration.
+/// Versions are compared according to [semantic versioning] rules.
+///
+/// See [`Updater::set_update_policy()`].
+///
+/// [semantic versioning]: https://semver.org
+/// [`Updater::set_update_policy()`]: struct.Updater.html#method.set_update_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdatePolicy {
+    /// Report any newer release (default).
+    All,
+    /// Only report newer releases that share the major version with the current version.
+    MinorOnly,
+    /// Only report newer releases that share the major and minor versions with the current
+    /// version.
+    PatchOnly,
+}
+
+impl Default for UpdatePolicy {
+    fn default() -> Self {
+        UpdatePolicy::All
+    }
+}
+
+impl UpdatePolicy {
+    /// Returns `true` if upgrading from `current` to `candidate` is allowed by this policy.
+    pub fn allows(self, current: &Version, candidate: &Version) -> bool {
+        if candidate <= current {
+            return false;
+        }
+        match self {
+            UpdatePolicy::All => true,
+            UpdatePolicy::MinorOnly => candidate.major == current.major,
+            UpdatePolicy::PatchOnly => {
+                candidate.major == current.major && candidate.minor == current.minor
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_limits_upgrades_by_policy() {
+        let current = Version::parse("1.2.3").unwrap();
+        let patch = Version::parse("1.2.4").unwrap();
+        let minor = Version::parse("1.3.0").unwrap();
+        let major = Version::parse("2.0.0").unwrap();
+
+        assert!(UpdatePolicy::All.allows(&current, &major));
+        assert!(UpdatePolicy::MinorOnly.allows(&current, &minor));
+        assert!(!UpdatePolicy::MinorOnly.allows(&current, &major));
+        assert!(UpdatePolicy::PatchOnly.allows(&current, &patch));
+        assert!(!UpdatePolicy::PatchOnly.allows(&current, &minor));
+        assert!(!UpdatePolicy::All.allows(&current, &current));
+    }
+}
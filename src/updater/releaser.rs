@@ -1,13 +1,21 @@
-use super::{anyhow, reqwest, semver, serde_json, url, Result};
+use super::{
+    anyhow, env, parse_lenient, semver, serde_json, url, DateTime, NetworkSettings, Result, Utc,
+};
 #[cfg(test)]
 use mockito;
 use semver::Version;
 use std::cell::RefCell;
 use url::Url;
 
+mod graphql;
+
 #[cfg(not(test))]
 const GITHUB_API_URL: &str = "https://api.github.com/repos/";
 const GITHUB_LATEST_RELEASE_ENDPOINT: &str = "/releases/latest";
+const GITHUB_RELEASES_ENDPOINT: &str = "/releases";
+const GITHUB_RELEASES_PER_PAGE: usize = 100;
+// Upper limit on pages of releases we go through looking for a usable release.
+const GITHUB_RELEASES_MAX_PAGES: usize = 10;
 
 #[cfg(test)]
 #[allow(deprecated)]
@@ -23,52 +31,243 @@ pub const MOCK_RELEASER_REPO_NAME: &str = "MockZnVja29mZg==fd850fc2e63511e79f720
 ///
 /// [`GithubReleaser`]: struct.GithubReleaser.html
 pub trait Releaser: Clone {
-    /// Typte that represents semantic compatible identifier of a release.
-    type SemVersion: Into<Version>;
-
-    /// Type that represents a url to the latest release resource.
-    type DownloadLink: Into<Url>;
-
     /// Creates a new `Releaser` instance that is identified as `name`
     fn new<S: Into<String>>(name: S) -> Self;
 
-    /// Performs necessary communications to obtain release info in form of
-    /// `SemVersion` and `DownloadLink` types.
+    /// Performs necessary communications to obtain information about the latest release.
     ///
-    /// Returned tuple consists of semantic version compatible identifier of the release and
-    /// a download link/url that can be used to fetch the release.
+    /// Returned [`ReleaseInfo`] holds the semantic version compatible identifier of the
+    /// release and a download link/url that can be used to fetch the release, along with any
+    /// other details (publish date, size, release notes, checksum) the server provides.
     ///
     /// Implementors are strongly encouraged to get the meta-data about the latest release without
     /// performing a full download of the workflow.
     ///
     /// # Errors
     /// Method returns `Err(Error)` on file or network error.
-    fn fetch_latest_release(&self) -> Result<(Self::SemVersion, Self::DownloadLink)>;
+    ///
+    /// [`ReleaseInfo`]: struct.ReleaseInfo.html
+    fn fetch_latest_release(&self) -> Result<ReleaseInfo>;
 
-    /// Returns the latest release information that is available from server.
+    /// Returns information about recent releases, newest first.
+    ///
+    /// [`Updater::list_releases()`] uses this method to enable flows such as installing a
+    /// previous version of the workflow. The default implementation only reports the release
+    /// returned by [`fetch_latest_release()`].
     ///
     /// # Errors
     /// Method returns `Err(Error)` on file or network error.
-    fn latest_release(&self) -> Result<(Version, Url)> {
-        let (v, url) = self.fetch_latest_release()?;
-        Ok((v.into(), url.into()))
+    ///
+    /// [`Updater::list_releases()`]: struct.Updater.html#method.list_releases
+    /// [`fetch_latest_release()`]: #tymethod.fetch_latest_release
+    fn fetch_releases(&self) -> Result<Vec<ReleaseInfo>> {
+        Ok(vec![self.fetch_latest_release()?])
+    }
+
+    /// Returns information about a file attached to the latest release whose name matches
+    /// `name_pattern`, such as a helper binary that a workflow ships alongside its bundle.
+    ///
+    /// `name_pattern` is a glob pattern where `*` matches any sequence of characters and `?`
+    /// matches a single character. [`Updater::download_asset()`] uses this method, the default
+    /// implementation reports that the releaser doesn't provide such files.
+    ///
+    /// # Errors
+    /// Method returns `Err(Error)` on file or network error, or if no file matches the pattern.
+    ///
+    /// [`Updater::download_asset()`]: struct.Updater.html#method.download_asset
+    fn fetch_latest_asset(&self, name_pattern: &str) -> Result<ReleaseInfo> {
+        Err(anyhow!(
+            "releaser doesn't provide assets, can't find one matching {}",
+            name_pattern
+        ))
+    }
+
+    /// Applies the network settings (timeouts, ...) configured on [`Updater`].
+    ///
+    /// `Updater` calls this method when it is created and every time its network settings
+    /// change. The settings carry a shared HTTP client, so connections can be reused between
+    /// the release check and the download. The default implementation ignores them,
+    /// implementors that perform network operations should honor them.
+    ///
+    /// [`Updater`]: struct.Updater.html
+    fn set_network_settings(&mut self, _settings: &NetworkSettings) {}
+
+    /// Url of the server that [`fetch_latest_release()`] talks to.
+    ///
+    /// Before starting a check, [`Updater`] tries to open a TCP connection to this server and
+    /// skips the check if it can't, so a workflow doesn't wait for long network timeouts when
+    /// there is clearly no network. The default implementation returns `None`, which disables
+    /// this probe.
+    ///
+    /// [`fetch_latest_release()`]: #tymethod.fetch_latest_release
+    /// [`Updater`]: struct.Updater.html
+    fn probe_url(&self) -> Option<Url> {
+        None
+    }
+
+    /// Parses a version the way this releaser reads the versions of its releases.
+    ///
+    /// [`Updater`] uses this method to recognize the versions of downloaded releases when
+    /// cleaning up its cache. The default implementation only accepts strict semantic versions.
+    ///
+    /// [`Updater`]: struct.Updater.html
+    fn parse_version(&self, version: &str) -> Option<Version> {
+        Version::parse(version).ok()
+    }
+}
+
+/// Information about a single release of a workflow.
+///
+/// Only the version and download url are required, [`Releaser`]s fill in the other details
+/// when the server provides them.
+///
+/// # Example
+/// ```rust
+/// # extern crate alfred_rs;
+/// # extern crate semver;
+/// # extern crate url;
+/// use alfred_rs::updater::ReleaseInfo;
+/// use semver::Version;
+/// use url::Url;
+///
+/// let url = Url::parse("https://ci.remote.cc/release/1.0.12/my.alfredworkflow").unwrap();
+/// let release = ReleaseInfo::new(Version::new(1, 0, 12), url)
+///     .with_size(2_811_073)
+///     .with_notes("Faster search");
+/// assert_eq!(Some("Faster search"), release.notes());
+/// ```
+///
+/// See [`Updater::list_releases()`].
+///
+/// [`Releaser`]: trait.Releaser.html
+/// [`Updater::list_releases()`]: struct.Updater.html#method.list_releases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseInfo {
+    version: Version,
+    url: Url,
+    published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    checksum: Option<String>,
+}
+
+impl ReleaseInfo {
+    /// Create release information for `version` that can be downloaded from `url`.
+    pub fn new(version: Version, url: Url) -> Self {
+        ReleaseInfo {
+            version,
+            url,
+            published_at: None,
+            size: None,
+            notes: None,
+            checksum: None,
+        }
+    }
+
+    /// Set the time the release was published.
+    #[must_use]
+    pub fn with_published_at(mut self, published_at: DateTime<Utc>) -> Self {
+        self.published_at = Some(published_at);
+        self
+    }
+
+    /// Set the size of the downloadable workflow bundle (in bytes).
+    #[must_use]
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Set the release notes.
+    #[must_use]
+    pub fn with_notes<S: Into<String>>(mut self, notes: S) -> Self {
+        self.notes = Some(notes.into());
+        self
+    }
+
+    /// Set the checksum of the downloadable workflow bundle, such as `sha256:<hex digest>`.
+    ///
+    /// Downloads of the release are verified against it: a downloaded file that doesn't match
+    /// is deleted and the download fails. Only sha256 digests (with or without the `sha256:`
+    /// prefix) are supported, downloading a release with another kind of checksum fails.
+    #[must_use]
+    pub fn with_checksum<S: Into<String>>(mut self, checksum: S) -> Self {
+        self.checksum = Some(checksum.into());
+        self
+    }
+
+    /// Version of the release.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// Url to download the release from.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Time the release was published, if known.
+    pub fn published_at(&self) -> Option<&DateTime<Utc>> {
+        self.published_at.as_ref()
+    }
+
+    /// Size of the downloadable workflow bundle in bytes, if known.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Release notes, if any.
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// Checksum of the downloadable workflow bundle, if known.
+    ///
+    /// See [`with_checksum()`].
+    ///
+    /// [`with_checksum()`]: struct.ReleaseInfo.html#method.with_checksum
+    pub fn checksum(&self) -> Option<&str> {
+        self.checksum.as_deref()
     }
 }
 
 /// Struct to handle checking and finding release files from `github.com`
 ///
-/// This implementation of `Releaser` will favor files that end with `alfred3workflow`
-/// over `alfredworkflow`. If there are multiple `alfred3workflow`s or `alfredworkflow`s, the first
-/// one returned by `github.com` will be used.
+/// This implementation of `Releaser` picks the workflow bundle that suits the running Alfred:
+/// files that end with `alfred5workflow` are favored on Alfred 5, then `alfred4workflow`,
+/// `alfred3workflow` and finally the generic `alfredworkflow`. Bundles built for a newer Alfred
+/// than the running one are skipped. If multiple files are equally suitable, the first one
+/// returned by `github.com` will be used.
+///
+/// By default only the release that `github.com` marks as *latest* is considered, which never
+/// is a pre-release. Use [`Updater::include_prereleases()`] to also consider pre-releases.
 ///
 /// See [`updater::gh()`] for how to use this.
 ///
 /// [`updater::gh()`]: struct.Updater.html#method.gh
+/// [`Updater::include_prereleases()`]: struct.Updater.html#method.include_prereleases
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GithubReleaser {
     repo: String,
     latest_release: RefCell<Option<ReleaseItem>>,
+    #[serde(default)]
+    include_prereleases: bool,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    tag_prefix: Option<String>,
+    #[serde(default)]
+    lenient_versions: bool,
+    #[serde(default)]
+    asset_pattern: Option<String>,
+    #[serde(skip)]
+    graphql_token: Option<graphql::Token>,
+    #[serde(skip)]
+    network: NetworkSettings,
 }
 
 // Struct to store information about a single release point.
@@ -78,57 +277,319 @@ pub struct GithubReleaser {
 pub struct ReleaseItem {
     /// name of release that should hold a semver compatible identifier.
     pub tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    body: Option<String>,
     assets: Vec<ReleaseAsset>,
 }
 
+impl ReleaseItem {
+    // Pick the workflow bundle to download. Bundles built for the running Alfred version
+    // (`alfred5workflow` on Alfred 5) are favored, then bundles for older Alfred versions (newest
+    // first) and then generic `alfredworkflow` ones. Bundles for newer Alfred versions are
+    // skipped. If the running version is unknown, generic bundles are favored.
+    // When `name_pattern` is given, only assets whose name matches it are considered.
+    // Assets that are still being uploaded (or are empty) are ignored, so that users aren't
+    // prompted to download a release that isn't ready yet.
+    fn workflow_asset(
+        &self,
+        name_pattern: Option<&str>,
+        alfred_major: Option<u64>,
+    ) -> Result<&ReleaseAsset> {
+        let assets = self
+            .assets
+            .iter()
+            .filter(|asset| {
+                asset.is_uploaded() && name_pattern.map_or(true, |p| glob_match(p, &asset.name))
+            })
+            .filter_map(|asset| {
+                let rank = match (
+                    bundle_alfred_version(&asset.browser_download_url)?,
+                    alfred_major,
+                ) {
+                    (Some(v), Some(major)) if v > major => return None,
+                    (Some(v), Some(major)) => (0, major - v),
+                    (None, Some(_)) | (None, None) => (1, 0),
+                    (Some(v), None) => (2, u64::MAX - v),
+                };
+                Some((rank, asset))
+            })
+            .collect::<Vec<_>>();
+        debug!(
+            "  collected release urls: {:?}",
+            assets
+                .iter()
+                .map(|(_, asset)| &asset.browser_download_url)
+                .collect::<Vec<_>>()
+        );
+        // Keep the first of equally ranked assets.
+        assets
+            .iter()
+            .enumerate()
+            .min_by_key(|(idx, (rank, _))| (*rank, *idx))
+            .map(|(_, (_, asset))| *asset)
+            .ok_or_else(|| anyhow!("no usable download url"))
+    }
+}
+
+// Alfred version that a bundle at `url` is built for: `Some(None)` for generic
+// `.alfredworkflow` bundles, `Some(Some(n))` for `.alfrednworkflow` ones and `None` if it is
+// not a workflow bundle.
+fn bundle_alfred_version(url: &str) -> Option<Option<u64>> {
+    let ext = url
+        .rsplit('.')
+        .next()?
+        .strip_prefix("alfred")?
+        .strip_suffix("workflow")?;
+    if ext.is_empty() {
+        Some(None)
+    } else {
+        ext.parse().ok().map(Some)
+    }
+}
+
+// Major version of the running Alfred, from the variables it sets for workflows.
+fn alfred_major_version() -> Option<u64> {
+    env::version()?.split('.').next()?.trim().parse().ok()
+}
+
+// Match `name` against a glob `pattern` where `*` matches any sequence of characters and `?`
+// matches a single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // Position of last `*` in pattern and the position in name it was matched at.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character.
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 /// A single downloadable asset.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct ReleaseAsset {
     url: String,
     name: String,
     state: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    digest: Option<String>,
     browser_download_url: String,
 }
 
+impl ReleaseAsset {
+    fn is_uploaded(&self) -> bool {
+        self.state == "uploaded" && self.size > 0
+    }
+}
+
 impl GithubReleaser {
-    fn latest_release_data(&self) -> Result<()> {
-        debug!("starting latest_release_data");
-        let client = reqwest::blocking::Client::builder()
-            .user_agent(concat!(
-                env!("CARGO_PKG_NAME"),
-                "/",
-                env!("CARGO_PKG_VERSION")
-            ))
-            .build()?;
+    pub(super) fn set_include_prereleases(&mut self, include: bool) {
+        self.include_prereleases = include;
+        *self.latest_release.borrow_mut() = None;
+    }
 
+    pub(super) fn set_channel(&mut self, channel: &str) {
+        if channel == "stable" {
+            self.include_prereleases = false;
+            self.channel = None;
+        } else {
+            self.include_prereleases = true;
+            self.channel = Some(channel.to_string());
+        }
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    // Whether release `r` (with version `v`) should be considered. Pre-releases are only
+    // considered when asked for, and if a channel is set they have to belong to it.
+    fn wants(&self, r: &ReleaseItem, v: &Version) -> bool {
+        if !r.prerelease {
+            return true;
+        }
+        self.include_prereleases
+            && self
+                .channel
+                .as_ref()
+                .map_or(true, |c| v.pre.as_str().starts_with(c.as_str()))
+    }
+
+    pub(super) fn set_tag_prefix(&mut self, prefix: String) {
+        self.tag_prefix = Some(prefix);
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    pub(super) fn set_lenient_versions(&mut self, lenient: bool) {
+        self.lenient_versions = lenient;
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    pub(super) fn set_asset_pattern(&mut self, pattern: String) {
+        self.asset_pattern = Some(pattern);
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    pub(super) fn set_graphql_token(&mut self, token: String) {
+        self.graphql_token = Some(graphql::Token(token));
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    // Make the next `fetch_latest_release()` ask the server again.
+    #[cfg(feature = "updater-async")]
+    pub(super) fn forget_latest_release(&self) {
+        *self.latest_release.borrow_mut() = None;
+    }
+
+    fn release_url(&self, release: &ReleaseItem) -> Result<Url> {
+        let asset =
+            release.workflow_asset(self.asset_pattern.as_deref(), alfred_major_version())?;
+        Ok(Url::parse(&asset.browser_download_url)?)
+    }
+
+    fn release_info(&self, version: Version, release: &ReleaseItem) -> Result<ReleaseInfo> {
+        let asset =
+            release.workflow_asset(self.asset_pattern.as_deref(), alfred_major_version())?;
+        let mut info = ReleaseInfo::new(version, Url::parse(&asset.browser_download_url)?);
+        info.published_at = release.published_at;
+        info.size = Some(asset.size);
+        info.notes = release.body.clone();
+        info.checksum = asset.digest.clone();
+        Ok(info)
+    }
+
+    // Version of a release from its tag. Without a configured prefix an optional leading `v` is
+    // stripped, otherwise tags that don't start with the prefix belong to other artifacts.
+    fn tag_version(&self, tag: &str) -> Option<Version> {
+        let version = match self.tag_prefix {
+            Some(ref prefix) => tag.strip_prefix(prefix.as_str())?,
+            None => tag.strip_prefix('v').unwrap_or(tag),
+        };
+        self.parse_version(version)
+    }
+
+    fn endpoint_url(&self, endpoint: &str) -> String {
         #[cfg(test)]
-        let url = format!("{}{}", MOCKITO_URL, GITHUB_LATEST_RELEASE_ENDPOINT);
+        let url = format!("{}{}", MOCKITO_URL, endpoint);
 
         #[cfg(not(test))]
-        let url = format!(
-            "{}{}{}",
-            GITHUB_API_URL, self.repo, GITHUB_LATEST_RELEASE_ENDPOINT
-        );
-        debug!("  url is: {:?}", url);
+        let url = format!("{}{}{}", GITHUB_API_URL, self.repo, endpoint);
+        url
+    }
 
-        client
-            .get(&url)
-            .send()?
-            .error_for_status()
-            .map_err(Into::into)
-            .and_then(|resp| {
-                let mut latest: ReleaseItem = serde_json::from_reader(resp)?;
-                if latest.tag_name.starts_with('v') {
-                    latest.tag_name.remove(0);
-                }
-                debug!("  release item: {:?}", latest);
-                *self.latest_release.borrow_mut() = Some(latest);
-                Ok(())
-            })
+    fn graphql_url(&self) -> String {
+        #[cfg(test)]
+        let url = format!("{}/graphql", MOCKITO_URL);
+
+        #[cfg(not(test))]
+        let url = graphql::GITHUB_GRAPHQL_URL.to_string();
+        url
+    }
+
+    // The release that github marks as latest, queried through GraphQL when a token is set.
+    fn marked_latest_release(&self) -> Result<ReleaseItem> {
+        match self.graphql_token {
+            Some(ref token) => {
+                let query = graphql::latest_release_query(&self.repo)?;
+                debug!("  querying latest release through GraphQL");
+                let resp = self
+                    .network
+                    .post_json(&self.graphql_url(), &token.0, &query)?;
+                graphql::latest_release(resp)
+            }
+            None => {
+                let url = self.endpoint_url(GITHUB_LATEST_RELEASE_ENDPOINT);
+                debug!("  url is: {:?}", url);
+                let resp = self.network.get(&url)?;
+                Ok(serde_json::from_reader(resp)?)
+            }
+        }
+    }
+
+    fn latest_release_data(&self) -> Result<()> {
+        debug!("starting latest_release_data");
+        let latest = if self.include_prereleases {
+            match self.latest_of(self.releases_page(1)?) {
+                Some(r) => r,
+                None => self.newest_usable_release()?,
+            }
+        } else {
+            let latest = self.marked_latest_release()?;
+            if self.usable(&latest) {
+                latest
+            } else {
+                debug!("  latest release is not usable: {}", latest.tag_name);
+                self.newest_usable_release()?
+            }
+        };
+        debug!("  release item: {:?}", latest);
+        *self.latest_release.borrow_mut() = Some(latest);
+        Ok(())
+    }
+
+    // Whether release `r` has a semver tag, is wanted and has a workflow asset.
+    fn usable(&self, r: &ReleaseItem) -> bool {
+        self.tag_version(&r.tag_name)
+            .map_or(false, |v| self.wants(r, &v))
+            && self.release_url(r).is_ok()
+    }
+
+    // `/releases/latest` never reports pre-releases, so go through the list of recent releases
+    // and pick the one with highest version. Releases with non-semver tags or without a
+    // workflow asset are ignored.
+    fn latest_of(&self, releases: Vec<ReleaseItem>) -> Option<ReleaseItem> {
+        releases
+            .into_iter()
+            .filter(|r| self.release_url(r).is_ok())
+            .filter_map(|r| Some((self.tag_version(&r.tag_name)?, r)))
+            .filter(|(v, r)| self.wants(r, v))
+            .max_by(|(v1, _), (v2, _)| v1.cmp(v2))
+            .map(|(_, r)| r)
+    }
+
+    // Walk the list of releases (newest first), page by page, until a release with a semver tag
+    // and a workflow asset is found.
+    fn newest_usable_release(&self) -> Result<ReleaseItem> {
+        for page in 1..=GITHUB_RELEASES_MAX_PAGES {
+            let releases = self.releases_page(page)?;
+            let last_page = releases.len() < GITHUB_RELEASES_PER_PAGE;
+            if let Some(r) = releases.into_iter().find(|r| self.usable(r)) {
+                return Ok(r);
+            }
+            if last_page {
+                break;
+            }
+        }
+        Err(anyhow!("no release with a usable download url"))
     }
 
     // This implementation of Releaser will favor urls that end with `alfredworkflow`
-    // over `alfredworkflow`
+    // over `alfred3workflow`
+    #[cfg(test)]
     fn downloadable_url(&self) -> Result<Url> {
         debug!("starting download_url");
         self.latest_release
@@ -139,29 +600,25 @@ impl GithubReleaser {
                 "no release item available, did you first get version by calling latest_version?",
             )
             })
-            .and_then(|r| {
-                let urls = r
-                    .assets
-                    .iter()
-                    .filter(|asset| {
-                        asset.state == "uploaded"
-                            && (asset.browser_download_url.ends_with("alfredworkflow")
-                                || asset.browser_download_url.ends_with("alfred3workflow")
-                                || asset.browser_download_url.ends_with("alfred4workflow"))
-                    })
-                    .map(|asset| &asset.browser_download_url)
-                    .collect::<Vec<&String>>();
-                debug!("  collected release urls: {:?}", urls);
-                match urls.len() {
-                    0 => Err(anyhow!("no usable download url")),
-                    1 => Ok(Url::parse(urls[0])?),
-                    _ => {
-                        let url = urls.iter().find(|item| item.ends_with("alfredworkflow"));
-                        let u = url.unwrap_or(&urls[0]);
-                        Ok(Url::parse(u)?)
-                    }
-                }
-            })
+            .and_then(|r| self.release_url(r))
+    }
+
+    // A page of published (non-draft) releases as listed by github, newest first.
+    fn releases_page(&self, page: usize) -> Result<Vec<ReleaseItem>> {
+        let url = self.releases_page_url(page);
+        debug!("  url is: {:?}", url);
+        let resp = self.network.get(&url)?;
+        let releases: Vec<ReleaseItem> = serde_json::from_reader(resp)?;
+        Ok(releases.into_iter().filter(|r| !r.draft).collect())
+    }
+
+    fn releases_page_url(&self, page: usize) -> String {
+        format!(
+            "{}?per_page={}&page={}",
+            self.endpoint_url(GITHUB_RELEASES_ENDPOINT),
+            GITHUB_RELEASES_PER_PAGE,
+            page
+        )
     }
 
     fn latest_version(&self) -> Result<Version> {
@@ -174,32 +631,100 @@ impl GithubReleaser {
             .latest_release
             .borrow()
             .as_ref()
-            .map(|r| Version::parse(&r.tag_name).ok())
-            .ok_or_else(|| anyhow!("Couldn't parse fetched version."))?
-            .unwrap();
+            .and_then(|r| self.tag_version(&r.tag_name))
+            .ok_or_else(|| anyhow!("Couldn't parse fetched version."))?;
         debug!("  latest version: {:?}", latest_version);
         Ok(latest_version)
     }
 }
 
 impl Releaser for GithubReleaser {
-    type SemVersion = Version;
-    type DownloadLink = Url;
-
     fn new<S: Into<String>>(repo_name: S) -> GithubReleaser {
         GithubReleaser {
             repo: repo_name.into(),
             latest_release: RefCell::new(None),
+            include_prereleases: false,
+            channel: None,
+            tag_prefix: None,
+            lenient_versions: false,
+            asset_pattern: None,
+            graphql_token: None,
+            network: NetworkSettings::default(),
         }
     }
 
-    fn fetch_latest_release(&self) -> Result<(Version, Url)> {
+    fn fetch_latest_release(&self) -> Result<ReleaseInfo> {
         if self.latest_release.borrow().is_none() {
             self.latest_release_data()?;
         }
         let version = self.latest_version()?;
-        let link = self.downloadable_url()?;
-        Ok((version, link))
+        let latest = self.latest_release.borrow();
+        let release = latest
+            .as_ref()
+            .ok_or_else(|| anyhow!("no release item available"))?;
+        self.release_info(version, release)
+    }
+
+    // Assets are looked up in the same release that `fetch_latest_release()` reports.
+    fn fetch_latest_asset(&self, name_pattern: &str) -> Result<ReleaseInfo> {
+        if self.latest_release.borrow().is_none() {
+            self.latest_release_data()?;
+        }
+        let version = self.latest_version()?;
+        let latest = self.latest_release.borrow();
+        let release = latest
+            .as_ref()
+            .ok_or_else(|| anyhow!("no release item available"))?;
+        let asset = release
+            .assets
+            .iter()
+            .find(|asset| asset.is_uploaded() && glob_match(name_pattern, &asset.name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "release {} has no asset matching {}",
+                    release.tag_name,
+                    name_pattern
+                )
+            })?;
+        let mut info = ReleaseInfo::new(version, Url::parse(&asset.browser_download_url)?);
+        info.published_at = release.published_at;
+        info.size = Some(asset.size);
+        info.checksum = asset.digest.clone();
+        Ok(info)
+    }
+
+    // Releases with non-semver tags or without a usable asset are skipped. Pre-releases are
+    // only included if asked for.
+    fn fetch_releases(&self) -> Result<Vec<ReleaseInfo>> {
+        let mut releases = self
+            .releases_page(1)?
+            .into_iter()
+            .filter_map(|r| {
+                let version = self.tag_version(&r.tag_name)?;
+                if !self.wants(&r, &version) {
+                    return None;
+                }
+                self.release_info(version, &r).ok()
+            })
+            .collect::<Vec<_>>();
+        releases.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(releases)
+    }
+
+    fn set_network_settings(&mut self, settings: &NetworkSettings) {
+        self.network = settings.clone();
+    }
+
+    fn probe_url(&self) -> Option<Url> {
+        Url::parse(&self.endpoint_url(GITHUB_LATEST_RELEASE_ENDPOINT)).ok()
+    }
+
+    fn parse_version(&self, version: &str) -> Option<Version> {
+        if self.lenient_versions {
+            parse_lenient(version)
+        } else {
+            Version::parse(version).ok()
+        }
     }
 }
 
@@ -227,14 +752,263 @@ pub mod tests {
                    releaser.downloadable_url().unwrap().as_str());
     }
 
-    pub fn setup_mock_server(status_code: usize) -> Mock {
-        mock(
-            "GET",
-            Matcher::Regex(r"^/releases/(latest|download).*$".to_string()),
-        )
-        .with_status(status_code)
-        .with_header("content-type", "application/json")
-        .with_body(include_str!("../../tests/latest.json"))
-        .create()
+    #[test]
+    fn it_reports_release_details() {
+        let _m = setup_mock_server(200);
+        let releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+
+        let release = releaser
+            .fetch_latest_release()
+            .expect("couldn't fetch latest release");
+        assert_eq!(&Version::new(0, 11, 1), release.version());
+        assert_eq!(Some(2_811_073), release.size());
+        assert!(release.published_at().is_some());
+        assert_eq!(
+            Some("- Search tags faster\r\n- Fix crash when offline"),
+            release.notes()
+        );
+        assert!(release.checksum().unwrap().starts_with("sha256:"));
+    }
+
+    #[test]
+    fn it_picks_latest_prerelease() {
+        let _m = setup_mock_releases_server(200);
+        let mut releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+        releaser.set_include_prereleases(true);
+
+        // Drafts are ignored, pre-releases are not.
+        assert_eq!(
+            Version::parse("0.12.0-beta.1").unwrap(),
+            releaser
+                .latest_version()
+                .expect("couldn't do latest_version")
+        );
+        assert_eq!("http://127.0.0.1:1234/releases/download/v0.12.0-beta.1/alfred-pinboard-rust-v0.12.0-beta.1.alfredworkflow",
+                   releaser.downloadable_url().unwrap().as_str());
+    }
+
+    #[test]
+    fn it_follows_release_channel() {
+        let _m = setup_mock_releases_server(200);
+        let mut releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+
+        releaser.set_channel("beta");
+        let release = releaser.fetch_latest_release().unwrap();
+        assert_eq!(&Version::parse("0.12.0-beta.1").unwrap(), release.version());
+
+        // No release candidates, latest stable release is picked.
+        releaser.set_channel("rc");
+        let release = releaser.fetch_latest_release().unwrap();
+        assert_eq!(&Version::new(0, 11, 1), release.version());
+    }
+
+    #[test]
+    fn it_lists_releases() {
+        let _m = setup_mock_releases_server(200);
+        let releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+
+        let versions = releaser
+            .fetch_releases()
+            .expect("couldn't list releases")
+            .iter()
+            .map(|r| r.version().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["0.11.1", "0.11.0"], versions);
+    }
+
+    #[test]
+    fn it_skips_releases_without_workflow_asset() {
+        let _m = mock("GET", GITHUB_LATEST_RELEASE_ENDPOINT)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../../tests/latest_no_asset.json"))
+            .create();
+        let _r = setup_mock_releases_server(200);
+        let releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+
+        // 0.12.0 has no workflow asset and 0.12.0-beta.1 is a pre-release
+        let release = releaser
+            .fetch_latest_release()
+            .expect("couldn't find a usable release");
+        let (version, url) = (release.version().clone(), release.url());
+        assert_eq!(Version::new(0, 11, 1), version);
+        assert!(url.as_str().ends_with("v0.11.1.alfredworkflow"));
+    }
+
+    #[test]
+    fn it_parses_tags_with_prefix() {
+        let _m = setup_mock_server(200);
+        let _r = setup_mock_releases_server(200);
+        let mut releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+        releaser.set_tag_prefix("workflow-v".to_string());
+
+        // The release marked as latest (v0.11.1) doesn't have the prefix and is skipped.
+        let release = releaser
+            .fetch_latest_release()
+            .expect("couldn't find a release with prefix");
+        let (version, url) = (release.version().clone(), release.url());
+        assert_eq!(Version::new(0, 10, 0), version);
+        assert!(url.as_str().ends_with("workflow-v0.10.0.alfredworkflow"));
+
+        let releases = releaser.fetch_releases().expect("couldn't list releases");
+        assert_eq!(1, releases.len());
+        assert!(releaser.tag_version("v0.11.1").is_none());
+    }
+
+    #[test]
+    fn it_parses_non_strict_tags_when_lenient() {
+        let mut releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+        assert!(releaser.tag_version("v2024.05.01").is_none());
+
+        releaser.set_lenient_versions(true);
+        assert_eq!(
+            Some(Version::new(2024, 5, 1)),
+            releaser.tag_version("v2024.05.01")
+        );
+        assert_eq!(Some(Version::new(1, 2, 0)), releaser.tag_version("1.2"));
+    }
+
+    #[test]
+    fn it_queries_latest_release_through_graphql() {
+        let response = serde_json::json!({"data": {"repository": {"latestRelease": {
+            "tagName": "v0.11.1",
+            "publishedAt": "2019-01-22T05:12:45Z",
+            "releaseAssets": {"nodes": [
+                {"name": "x.zip", "size": 12, "downloadUrl": "https://github.com/x.zip"},
+                {
+                    "name": "x.alfredworkflow",
+                    "size": 2_811_073,
+                    "downloadUrl": "https://github.com/x.alfredworkflow"
+                },
+            ]},
+        }}}});
+        let _m = mock("POST", "/graphql")
+            .match_header("authorization", "Bearer secret")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "variables": {"owner": "spamwax", "name": "alfred-pinboard-rs"}
+            })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(response.to_string())
+            .create();
+        let mut releaser = GithubReleaser::new("spamwax/alfred-pinboard-rs");
+        releaser.set_graphql_token("secret".to_string());
+        assert!(!format!("{:?}", releaser).contains("secret"));
+
+        let release = releaser
+            .fetch_latest_release()
+            .expect("couldn't fetch latest release");
+        assert_eq!(&Version::new(0, 11, 1), release.version());
+        assert_eq!(
+            "https://github.com/x.alfredworkflow",
+            release.url().as_str()
+        );
+        assert_eq!(Some(2_811_073), release.size());
+        assert!(release.published_at().is_some());
+
+        let _m = mock("POST", "/graphql")
+            .with_status(200)
+            .with_body(r#"{"data": null, "errors": [{"message": "Bad credentials"}]}"#)
+            .create();
+        releaser.set_graphql_token("expired".to_string());
+        let err = releaser.fetch_latest_release().unwrap_err();
+        assert!(err.to_string().contains("Bad credentials"));
+    }
+
+    #[test]
+    fn it_selects_assets_by_name() {
+        assert!(glob_match("*.alfredworkflow", "pinboard.alfredworkflow"));
+        assert!(glob_match("pin*-v?.*", "pinboard-v1.alfredworkflow"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("pin*-v?.*", "pinboard-v10.alfredworkflow"));
+        assert!(!glob_match("other-*", "pinboard.alfredworkflow"));
+
+        let _m = setup_mock_server(200);
+        let mut releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+        releaser.set_asset_pattern("alfred-pinboard-rust-*".to_string());
+        assert!(releaser.fetch_latest_release().is_ok());
+
+        let _r = setup_mock_releases_server(200);
+        releaser.set_asset_pattern("another-workflow-*.alfredworkflow".to_string());
+        assert!(releaser.fetch_latest_release().is_err());
+    }
+
+    #[test]
+    fn it_ignores_assets_not_fully_uploaded() {
+        let latest = include_str!("../../tests/latest.json");
+        for body in &[
+            latest.replace(r#""state": "uploaded""#, r#""state": "open""#),
+            latest.replace(r#""size": 2811073"#, r#""size": 0"#),
+        ] {
+            let _m = mock("GET", GITHUB_LATEST_RELEASE_ENDPOINT)
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(body)
+                .create();
+            let releaser = GithubReleaser::new(MOCK_RELEASER_REPO_NAME);
+            assert!(releaser.fetch_latest_release().is_err());
+        }
+    }
+
+    #[test]
+    fn it_picks_assets_for_running_alfred() {
+        let asset = |ext: &str| {
+            format!(
+                r#"{{"url": "u", "name": "wf.{0}", "state": "uploaded", "size": 1,
+                    "browser_download_url": "http://127.0.0.1/wf.{0}"}}"#,
+                ext
+            )
+        };
+        let release: ReleaseItem = serde_json::from_str(&format!(
+            r#"{{"tag_name": "v1.0.0", "assets": [{}, {}, {}, {}, {}]}}"#,
+            asset("zip"),
+            asset("alfred4workflow"),
+            asset("alfredworkflow"),
+            asset("alfred5workflow"),
+            asset("alfred3workflow"),
+        ))
+        .unwrap();
+        let picked = |major| {
+            release
+                .workflow_asset(None, major)
+                .map(|a| a.name.as_str())
+                .unwrap()
+        };
+
+        assert_eq!("wf.alfred5workflow", picked(Some(5)));
+        assert_eq!("wf.alfred5workflow", picked(Some(6)));
+        assert_eq!("wf.alfred4workflow", picked(Some(4)));
+        assert_eq!("wf.alfred3workflow", picked(Some(3)));
+        assert_eq!("wf.alfredworkflow", picked(Some(2)));
+        assert_eq!("wf.alfredworkflow", picked(None));
+        assert!(release
+            .workflow_asset(Some("*.alfred5workflow"), Some(4))
+            .is_err());
+    }
+
+    pub fn setup_mock_releases_server(status_code: usize) -> Mock {
+        mock("GET", Matcher::Regex(r"^/releases(\?.*)?$".to_string()))
+            .with_status(status_code)
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../../tests/releases.json"))
+            .create()
+    }
+
+    pub fn setup_mock_server(status_code: usize) -> Vec<Mock> {
+        vec![
+            mock("GET", GITHUB_LATEST_RELEASE_ENDPOINT)
+                .with_status(status_code)
+                .with_header("content-type", "application/json")
+                .with_body(include_str!("../../tests/latest.json"))
+                .create(),
+            mock(
+                "GET",
+                Matcher::Regex(r"^/releases/download/.*$".to_string()),
+            )
+            .with_status(status_code)
+            .with_header("content-type", "application/octet-stream")
+            .with_body(&include_bytes!("../../tests/workflow.alfredworkflow")[..])
+            .create(),
+        ]
     }
 }
@@ -0,0 +1,129 @@
+use super::{anyhow, serde_json, DateTime, ReleaseAsset, ReleaseItem, Result, Utc};
+
+#[cfg(not(test))]
+pub(super) const GITHUB_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+// Most assets of a release fetched, enough for any workflow's bundles.
+const MAX_ASSETS: usize = 50;
+
+// Asks only for what's needed to pick and download the workflow bundle. Unlike the REST api,
+// release notes and the details of assets are left out.
+const LATEST_RELEASE_QUERY: &str = "query($owner: String!, $name: String!, $assets: Int!) {
+  repository(owner: $owner, name: $name) {
+    latestRelease {
+      tagName
+      publishedAt
+      releaseAssets(first: $assets) { nodes { name size downloadUrl } }
+    }
+  }
+}";
+
+// Personal access token that authorizes GraphQL queries. Kept out of debug output.
+#[derive(Clone)]
+pub(super) struct Token(pub(super) String);
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Token(***)")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    data: Option<Data>,
+    #[serde(default)]
+    errors: Vec<Error>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Error {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+    repository: Option<Repository>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    latest_release: Option<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Release {
+    tag_name: String,
+    published_at: Option<DateTime<Utc>>,
+    release_assets: Assets,
+}
+
+#[derive(Debug, Deserialize)]
+struct Assets {
+    nodes: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Asset {
+    name: String,
+    size: u64,
+    download_url: String,
+}
+
+impl From<Release> for ReleaseItem {
+    // GraphQL only reports assets that are fully uploaded, and `latestRelease` is never a
+    // draft or a pre-release.
+    fn from(release: Release) -> Self {
+        ReleaseItem {
+            tag_name: release.tag_name,
+            prerelease: false,
+            draft: false,
+            published_at: release.published_at,
+            body: None,
+            assets: release
+                .release_assets
+                .nodes
+                .into_iter()
+                .map(|asset| ReleaseAsset {
+                    url: asset.download_url.clone(),
+                    name: asset.name,
+                    state: "uploaded".to_string(),
+                    size: asset.size,
+                    digest: None,
+                    browser_download_url: asset.download_url,
+                })
+                .collect(),
+        }
+    }
+}
+
+// Body of the query for the latest release of `repo` (`owner/name`).
+pub(super) fn latest_release_query(repo: &str) -> Result<String> {
+    let (owner, name) = repo
+        .split_once('/')
+        .ok_or_else(|| anyhow!("repository should be in owner/name form: {}", repo))?;
+    Ok(serde_json::json!({
+        "query": LATEST_RELEASE_QUERY,
+        "variables": {"owner": owner, "name": name, "assets": MAX_ASSETS},
+    })
+    .to_string())
+}
+
+// Latest release from the response to `latest_release_query()`.
+pub(super) fn latest_release(resp: impl std::io::Read) -> Result<ReleaseItem> {
+    parse(serde_json::from_reader(resp)?)
+}
+
+fn parse(resp: Response) -> Result<ReleaseItem> {
+    if let Some(error) = resp.errors.first() {
+        return Err(anyhow!("GraphQL query failed: {}", error.message));
+    }
+    resp.data
+        .and_then(|data| data.repository)
+        .ok_or_else(|| anyhow!("repository not found"))?
+        .latest_release
+        .map(ReleaseItem::from)
+        .ok_or_else(|| anyhow!("repository has no release"))
+}
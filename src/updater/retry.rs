@@ -0,0 +1,109 @@
+use super::Result;
+use rand::Rng;
+use std::convert::TryFrom;
+use std::thread;
+use std::time::Duration;
+
+/// Policy for retrying a failed check for the latest release.
+///
+/// The worker thread spawned by [`Updater::init()`] uses this policy when talking to the
+/// remote server. Each failed attempt is followed by a pause that doubles every time, starting
+/// from `base_delay`. When jitter is enabled the pause is picked randomly between zero and
+/// that value so that many workflows failing at the same moment do not retry in lockstep.
+///
+/// The default policy makes a single attempt (no retries).
+///
+/// # Example
+/// ```rust
+/// # extern crate alfred_rs;
+/// use alfred_rs::updater::RetryPolicy;
+/// use std::time::Duration;
+///
+/// // Up to 3 attempts, waiting ~200ms and then ~400ms between them.
+/// let policy = RetryPolicy::new(3, Duration::from_millis(200)).jitter(true);
+/// assert_eq!(3, policy.attempts());
+/// ```
+///
+/// [`Updater::init()`]: struct.Updater.html#method.init
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 1,
+            base_delay: Duration::from_millis(0),
+            jitter: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy that makes at most `attempts` tries, pausing `base_delay` after the first
+    /// failure and doubling the pause after each subsequent one.
+    ///
+    /// An `attempts` value of zero is treated as one.
+    pub fn new(attempts: u32, base_delay: Duration) -> Self {
+        RetryPolicy {
+            attempts: attempts.max(1),
+            base_delay,
+            jitter: false,
+        }
+    }
+
+    /// Enable or disable random jitter on the pause between attempts.
+    #[must_use]
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Maximum number of attempts that will be made.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    // Pause to take after `retry`-th failure (zero based).
+    pub(super) fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .checked_mul(1 << retry.min(16))
+            .unwrap_or(self.base_delay);
+        if self.jitter {
+            let millis = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+            Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+        } else {
+            delay
+        }
+    }
+
+    // Run `f` until it succeeds or we run out of attempts, in which case the last error
+    // is returned.
+    pub(super) fn run<F, R>(&self, mut f: F) -> Result<R>
+    where
+        F: FnMut() -> Result<R>,
+    {
+        let mut retry = 0;
+        loop {
+            match f() {
+                Ok(r) => return Ok(r),
+                Err(e) if retry + 1 >= self.attempts => return Err(e),
+                Err(e) => {
+                    let delay = self.delay(retry);
+                    debug!(
+                        "  attempt {} failed ({}), retrying in {:?}",
+                        retry + 1,
+                        e,
+                        delay
+                    );
+                    thread::sleep(delay);
+                    retry += 1;
+                }
+            }
+        }
+    }
+}
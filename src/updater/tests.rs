@@ -1,22 +1,32 @@
-use self::releaser::tests::setup_mock_server;
+use self::releaser::tests::{setup_mock_releases_server, setup_mock_server};
 // #[cfg(not(feature = "ci"))]
 use self::releaser::GithubReleaser;
 use self::releaser::MOCK_RELEASER_REPO_NAME;
 use super::*;
+use mockito::{mock, Matcher};
 use std::ffi::OsStr;
 use std::{thread, time};
 use tempfile::Builder;
 const VERSION_TEST: &str = "0.10.5";
 const VERSION_TEST_NEW: &str = "0.11.1"; // should match what the mock server replies for new version.
 
+// Digest of tests/workflow.alfredworkflow, the same as the bundle's in tests/latest.json
+const WORKFLOW_SHA256: &str =
+    "sha256:1fdf9e12bca8665b81b8aea6ef4ef6775219349c9d45a21fed046c7974c99926";
+
 #[test]
 fn it_tests_settings_filename() {
-    setup_workflow_env_vars(true);
+    let cache_dir = setup_workflow_env_vars(true);
+    let name = "workflow.B0AC54EC-601C-YouForgotTo___Name_Your_Own_Work_flow_-updater.json";
+    // State files saved in the cache dir itself by older versions are moved.
+    std::fs::write(cache_dir.join(name), "{}").unwrap();
     let updater_state_fn = Updater::<GithubReleaser>::build_data_fn().unwrap();
     assert_eq!(
-        "workflow.B0AC54EC-601C-YouForgotTo___Name_Your_Own_Work_flow_-updater.json",
-        updater_state_fn.file_name().unwrap().to_str().unwrap()
+        cache_dir.join(crate::data::UPDATER_CACHE_DIR).join(name),
+        updater_state_fn
     );
+    assert!(updater_state_fn.exists());
+    assert!(!cache_dir.join(name).exists());
 }
 
 #[test]
@@ -289,8 +299,1195 @@ fn it_tests_async_updates_2() {
     assert!(updater.update_ready().expect("couldn't check for update"));
 }
 
+#[allow(deprecated)]
+#[test]
+fn it_retries_failed_checks() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+
+    let m = mock("GET", Matcher::Regex(r"^/releases/latest$".to_string()))
+        .with_status(503)
+        .expect(3)
+        .create();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.set_retry(RetryPolicy::new(3, time::Duration::from_millis(5)));
+    updater.init().expect("couldn't init worker");
+
+    // All attempts fail, so the error of the last one is reported.
+    assert!(updater.update_ready().is_err());
+    m.assert();
+}
+
+#[test]
+fn it_reports_prereleases_when_asked() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let _m = setup_mock_releases_server(200);
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.include_prereleases(true);
+    updater.init().expect("couldn't init worker");
+
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert_eq!(
+        Some(Version::parse("0.12.0-beta.1").unwrap()),
+        updater.latest_avail_version()
+    );
+}
+
+#[test]
+fn it_respects_update_policy() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    // Mock server offers 0.11.1 while we are at 0.10.5
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.set_update_policy(UpdatePolicy::PatchOnly);
+    updater.init().expect("couldn't init worker");
+    assert!(!updater.update_ready().expect("couldn't check for update"));
+
+    updater.set_update_policy(UpdatePolicy::MinorOnly);
+    assert!(updater.update_ready().expect("couldn't check for update"));
+}
+
+#[test]
+fn it_downloads_specific_version() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    let _r = setup_mock_releases_server(200);
+    first_check_after_installing_workflow();
+
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let releases = updater.list_releases().expect("couldn't list releases");
+    assert_eq!(2, releases.len());
+    assert!(releases[0].published_at().is_some());
+
+    let old_version = Version::parse("0.11.0").unwrap();
+    let download_fn = updater
+        .download_version(&old_version)
+        .expect("couldn't download old version");
+    assert_eq!(
+        "release_YouForgotTo___Name_Your_Own_Work_flow__0.11.0.alfredworkflow",
+        download_fn.file_name().unwrap().to_str().unwrap()
+    );
+    assert!(updater
+        .download_version(&Version::parse("0.9.0").unwrap())
+        .is_err());
+}
+
+#[test]
+fn it_resumes_interrupted_download() {
+    let cache_dir = setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let body = &include_bytes!("../../tests/workflow.alfredworkflow")[..];
+
+    // Server honors the range request.
+    let url = Url::parse(&format!(
+        "{}/dl/resumed.alfredworkflow",
+        mockito::server_url()
+    ))
+    .unwrap();
+    let download_fn = cache_dir.join("resumed.alfredworkflow");
+    let partial = Updater::<GithubReleaser>::partial_download_fn(&download_fn, &url);
+    std::fs::write(&partial, &body[..10]).unwrap();
+    let _m = mock("GET", "/dl/resumed.alfredworkflow")
+        .match_header("range", "bytes=10-")
+        .with_status(206)
+        .with_header(
+            "content-range",
+            &format!("bytes 10-{}/{}", body.len() - 1, body.len()),
+        )
+        .with_body(&body[10..])
+        .create();
+    let p = updater
+        .download_release(&url, Some(WORKFLOW_SHA256), download_fn)
+        .expect("couldn't resume download");
+    assert_eq!(body, &std::fs::read(&p).unwrap()[..]);
+    assert!(!partial.exists());
+
+    // Server ignores the range request and sends everything.
+    let url = Url::parse(&format!(
+        "{}/dl/restarted.alfredworkflow",
+        mockito::server_url()
+    ))
+    .unwrap();
+    let download_fn = cache_dir.join("restarted.alfredworkflow");
+    let partial = Updater::<GithubReleaser>::partial_download_fn(&download_fn, &url);
+    std::fs::write(&partial, "stale bytes").unwrap();
+    let _m = mock("GET", "/dl/restarted.alfredworkflow")
+        .with_status(200)
+        .with_body(body)
+        .create();
+    let p = updater
+        .download_release(&url, Some(WORKFLOW_SHA256), download_fn)
+        .expect("couldn't download");
+    assert_eq!(body, &std::fs::read(&p).unwrap()[..]);
+    assert!(!partial.exists());
+}
+
+#[test]
+fn it_downloads_latest_to_given_path() {
+    let cache_dir = setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert!(updater.download_latest_to(cache_dir.join("x")).is_err());
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+
+    let target = cache_dir.join("pinboard-side-by-side.alfredworkflow");
+    let saved = updater
+        .download_latest_to(&target)
+        .expect("couldn't download latest release");
+    assert_eq!(target, saved);
+    assert!(target.exists());
+}
+
+#[test]
+fn it_builds_update_item() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater
+        .update_item()
+        .expect("couldn't build item")
+        .is_none());
+    assert!(updater.update_ready().expect("couldn't check for update"));
+
+    let item = updater
+        .update_item()
+        .expect("couldn't build item")
+        .expect("update item is missing");
+    assert_eq!(
+        format!("Version {} is available", VERSION_TEST_NEW),
+        item.title
+    );
+    let arg = item.arg.expect("item's arg is not set");
+    assert!(Path::new(arg.as_ref()).exists());
+    assert_eq!(
+        Some("yes"),
+        item.variables.get("update_ready").map(AsRef::as_ref)
+    );
+}
+
+#[test]
+fn it_throttles_update_notifications() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.init().expect("couldn't init worker");
+        assert!(!updater
+            .should_notify()
+            .expect("couldn't check notification"));
+        assert!(updater.update_ready().expect("couldn't check for update"));
+        assert!(updater
+            .should_notify()
+            .expect("couldn't check notification"));
+        assert!(!updater
+            .should_notify()
+            .expect("couldn't check notification"));
+    }
+
+    // Throttling survives across invocations of the workflow.
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(!updater
+        .should_notify()
+        .expect("couldn't check notification"));
+
+    updater.set_notify_interval(0);
+    assert!(updater
+        .should_notify()
+        .expect("couldn't check notification"));
+}
+
+#[test]
+fn it_reports_available_update_once() {
+    use std::sync::{Arc, Mutex};
+
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let build_updater = || {
+        let reported = Arc::clone(&reported);
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.on_update_available(move |info| {
+            reported.lock().unwrap().push(info.version().to_string());
+        });
+        updater.init().expect("couldn't init worker");
+        updater
+    };
+
+    {
+        let updater = build_updater();
+        assert!(updater.update_ready().expect("couldn't check for update"));
+        assert!(updater.update_ready().expect("couldn't check for update"));
+        assert_eq!(vec![VERSION_TEST_NEW], *reported.lock().unwrap());
+    }
+
+    // Next invocation of workflow doesn't report the same release again.
+    let updater = build_updater();
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert_eq!(1, reported.lock().unwrap().len());
+}
+
+#[test]
+fn it_exposes_latest_release_info() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.latest_available().is_none());
+    assert!(updater.update_ready().expect("couldn't check for update"));
+
+    let info = updater.latest_available().expect("no release info");
+    assert_eq!(VERSION_TEST_NEW, info.version().to_string());
+    assert!(info
+        .url()
+        .as_str()
+        .ends_with("alfred-pinboard-rust-v0.11.1.alfredworkflow"));
+    assert!(info.fetched_at().is_some());
+    assert_eq!(
+        "2018-04-14T19:57:26+00:00",
+        info.published_at().unwrap().to_rfc3339()
+    );
+    assert_eq!(Some(2_811_073), info.size());
+}
+
+#[test]
+fn it_aggregates_release_notes() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    let _r = setup_mock_releases_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.release_notes().is_none());
+    assert!(updater.update_ready().expect("couldn't check for update"));
+
+    assert_eq!(
+        Some("- Search tags faster\r\n- Fix crash when offline"),
+        updater.release_notes().as_deref()
+    );
+    assert_eq!(
+        "## 0.11.1\n\n- Search tags faster\r\n- Fix crash when offline\n\n\
+         ## 0.11.0\n\n- Add support for Alfred 4",
+        updater.changelog().expect("couldn't build changelog")
+    );
+}
+
+#[derive(Clone)]
+struct SlowReleaser;
+
+impl Releaser for SlowReleaser {
+    fn new<S: Into<String>>(_name: S) -> Self {
+        SlowReleaser
+    }
+
+    fn fetch_latest_release(&self) -> Result<ReleaseInfo> {
+        thread::sleep(time::Duration::from_millis(1000));
+        let url = Url::parse("http://127.0.0.1:1234/releases/download/v0.10.5/w.alfredworkflow")?;
+        Ok(ReleaseInfo::new(Version::parse(VERSION_TEST)?, url))
+    }
+}
+
+#[test]
+fn it_waits_for_worker_with_timeout() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().expect("couldn't check for update"));
+    }
+
+    let mut updater =
+        Updater::<SlowReleaser>::new(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    // Worker is still busy, status of previous check is reported.
+    assert!(updater
+        .update_ready_timeout(time::Duration::from_millis(50))
+        .expect("couldn't check for update"));
+    // Worker's result is still delivered afterwards.
+    assert!(!updater
+        .update_ready_timeout(time::Duration::from_secs(10))
+        .expect("couldn't check for update"));
+}
+
+#[test]
+fn it_reports_check_schedule_and_last_error() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        assert!(updater.last_error().is_none());
+        let last_check = updater.last_checked().expect("no last check");
+        assert_eq!(
+            last_check + chrono::Duration::seconds(UPDATE_INTERVAL),
+            updater.next_check()
+        );
+
+        let _m = setup_mock_server(400);
+        updater.set_interval(0);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().is_err());
+    }
+
+    // Error is remembered across invocations, until the next successful check.
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let error = updater.last_error().expect("last error is missing");
+    assert!(error.contains("400 Bad Request"));
+
+    let _m = setup_mock_server(200);
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(updater.last_error().is_none());
+}
+
+#[test]
+fn it_checks_synchronously_in_detached_process() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    StdEnv::set_var("ALFRED_RS_UPDATER_DETACHED_CHECK", "1");
+    {
+        let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        // Only updaters with detached checks enabled act as the detached process.
+        assert!(!updater.is_detached_check());
+    }
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.set_detached_check(true);
+    assert!(updater.is_detached_check());
+    updater.init().expect("couldn't init worker");
+    StdEnv::remove_var("ALFRED_RS_UPDATER_DETACHED_CHECK");
+
+    // Check is already done when init() returns.
+    assert!(updater
+        .try_update_ready()
+        .expect("couldn't check for update"));
+
+    // Next invocation of workflow picks up the results.
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert_eq!(
+        VERSION_TEST_NEW,
+        updater.latest_avail_version().unwrap().to_string()
+    );
+    assert!(!updater.due_to_check());
+}
+
+#[test]
+fn it_checks_in_background() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    {
+        let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        // First check after installing the workflow never reports an update.
+        assert!(!updater.check_in_background().expect("couldn't check"));
+    }
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        // Nothing is known yet, worker's results are never received by this updater.
+        assert!(!updater.check_in_background().expect("couldn't check"));
+    }
+    thread::sleep(time::Duration::from_millis(500));
+
+    let _m = setup_mock_server(400);
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert!(!updater.due_to_check());
+    assert!(updater.check_in_background().expect("couldn't check"));
+    assert_eq!(
+        VERSION_TEST_NEW,
+        updater.latest_avail_version().unwrap().to_string()
+    );
+}
+
+#[test]
+fn it_saves_failed_background_checks() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let _m = setup_mock_server(503);
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        assert!(!updater.check_in_background().expect("couldn't check"));
+    }
+    // The worker saves the failure itself, its results are never received.
+    thread::sleep(time::Duration::from_millis(500));
+
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert!(updater.last_error().is_some());
+    assert!(updater.failure_backoff_until().is_some());
+}
+
+#[test]
+fn it_backs_off_after_failed_checks() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+
+    let hours_until = |updater: &Updater<GithubReleaser>| {
+        let until = updater.failure_backoff_until().expect("no backoff");
+        (until - Utc::now() + chrono::Duration::seconds(30)).num_hours()
+    };
+    let failed_check = |interval| {
+        let _m = setup_mock_server(503);
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().is_err());
+        updater.set_interval(interval);
+        updater
+    };
+
+    let updater = failed_check(12 * 60 * 60);
+    assert_eq!(1, hours_until(&updater));
+    assert!(!updater.due_to_check());
+    let updater = failed_check(12 * 60 * 60);
+    assert_eq!(6, hours_until(&updater));
+    // Backoff never exceeds the update interval.
+    let updater = failed_check(2 * 60 * 60);
+    assert_eq!(2, hours_until(&updater));
+
+    // A successful check resets the backoff.
+    let _m = setup_mock_server(200);
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(updater.failure_backoff_until().is_none());
+}
+
+#[derive(Clone)]
+struct OfflineReleaser;
+
+impl Releaser for OfflineReleaser {
+    fn new<S: Into<String>>(_name: S) -> Self {
+        OfflineReleaser
+    }
+
+    fn fetch_latest_release(&self) -> Result<ReleaseInfo> {
+        Err(anyhow!("releaser shouldn't be asked while offline"))
+    }
+
+    fn probe_url(&self) -> Option<Url> {
+        // Nothing listens on this port.
+        Url::parse("http://127.0.0.1:1/releases/latest").ok()
+    }
+}
+
+#[test]
+fn it_skips_checks_when_offline() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+    {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().expect("couldn't check for update"));
+    }
+
+    let mut updater =
+        Updater::<OfflineReleaser>::new(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    assert!(updater.due_to_check());
+    updater.init().expect("couldn't init worker");
+    // Status of previous check is reported without asking releaser.
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(updater.last_error().is_none());
+    assert!(updater.due_to_check());
+}
+
+#[test]
+fn it_adds_jitter_to_check_interval() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let max_jitter = 60 * 60;
+    let next_check = {
+        let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+        updater.set_interval(0);
+        updater.set_interval_jitter(max_jitter);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().expect("couldn't check for update"));
+
+        updater.set_interval(60);
+        let delay = updater.next_check() - updater.last_checked().unwrap();
+        assert!(delay >= chrono::Duration::seconds(60));
+        assert!(delay <= chrono::Duration::seconds(60 + max_jitter));
+        updater.next_check()
+    };
+
+    // Delay picked at last check is kept across invocations of the workflow.
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(60);
+    updater.set_interval_jitter(max_jitter);
+    assert_eq!(next_check, updater.next_check());
+
+    updater.set_interval_jitter(0);
+    assert_eq!(
+        updater.last_checked().unwrap() + chrono::Duration::seconds(60),
+        updater.next_check()
+    );
+}
+
+#[test]
+fn it_respects_disabled_updates_variable() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    StdEnv::set_var("alfred_workflow_update_disabled", "1");
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    assert!(!updater.is_enabled());
+    updater.init().expect("couldn't init worker");
+    assert!(!updater.update_ready().expect("couldn't check for update"));
+    assert!(!updater.check_in_background().expect("couldn't check"));
+    assert!(updater.latest_avail_version().is_none());
+    StdEnv::remove_var("alfred_workflow_update_disabled");
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    assert!(updater.is_enabled());
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+}
+
+#[test]
+fn it_respects_update_interval_variable() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let last_check = updater.last_checked().expect("no last check");
+    updater.set_interval_duration(std::time::Duration::from_secs(12 * 60 * 60));
+    assert_eq!(last_check + Duration::hours(12), updater.next_check());
+
+    updater.set_interval(0);
+    assert!(updater.due_to_check());
+    StdEnv::set_var("alfred_workflow_update_interval", "1w");
+    assert!(!updater.due_to_check());
+    assert_eq!(last_check + Duration::weeks(1), updater.next_check());
+    StdEnv::set_var("alfred_workflow_update_interval", "weekly");
+    assert!(updater.due_to_check());
+    StdEnv::remove_var("alfred_workflow_update_interval");
+    assert!(updater.due_to_check());
+}
+
+#[test]
+fn it_persists_disabled_updates() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_enabled(false).expect("couldn't save state");
+    assert!(!updater.is_enabled());
+
+    // Setting is kept by later invocations of the workflow.
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    assert!(!updater.is_enabled());
+    updater.init().expect("couldn't init worker");
+    assert!(!updater.update_ready().expect("couldn't check for update"));
+    assert!(updater.latest_avail_version().is_none());
+
+    updater.set_enabled(true).expect("couldn't save state");
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    assert!(updater.is_enabled());
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+}
+
+#[test]
+fn it_downloads_release_assets() {
+    let cache_dir = setup_workflow_env_vars(true);
+    let bundle = include_bytes!("../../tests/workflow.alfredworkflow");
+    let latest = include_str!("../../tests/latest.json")
+        .replace(
+            "https://github.com/spamwax/alfred-pinboard-rs",
+            &mockito::server_url(),
+        )
+        .replace(
+            r#""size": 2734175"#,
+            &format!(r#""size": {}"#, bundle.len()),
+        );
+    let _m = mock("GET", "/releases/latest")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(&latest)
+        .create();
+    let _d = mock(
+        "GET",
+        Matcher::Regex(r"^/releases/download/.*$".to_string()),
+    )
+    .with_status(200)
+    .with_header("content-type", "application/octet-stream")
+    .with_body(&bundle[..])
+    .create();
+    first_check_after_installing_workflow();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+
+    let helper = updater
+        .download_asset("i686-apple-darwin-*.tar.gz")
+        .expect("couldn't download asset");
+    assert_eq!(
+        cache_dir
+            .join(crate::data::UPDATER_CACHE_DIR)
+            .join("i686-apple-darwin-alfred-pinboard-rs-v0.11.1.tar.gz"),
+        helper
+    );
+    assert_eq!(bundle.len() as u64, helper.metadata().unwrap().len());
+
+    // Release reports a different size for the workflow asset than what's downloaded.
+    assert!(updater.download_asset("*.alfredworkflow").is_err());
+    assert!(!helper
+        .with_file_name("alfred-pinboard-rust-v0.11.1.alfredworkflow")
+        .exists());
+
+    assert!(updater.download_asset("*.zip").is_err());
+}
+
+#[cfg(feature = "updater-async")]
+#[test]
+fn it_checks_and_downloads_async() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_version(VERSION_TEST);
+    // Checks are made regardless of the update interval.
+    assert!(!updater.due_to_check());
+    assert!(rt
+        .block_on(updater.as_async().check())
+        .expect("couldn't check for update"));
+    assert_eq!(
+        Some(Version::parse(VERSION_TEST_NEW).unwrap()),
+        updater.latest_avail_version()
+    );
+    assert!(updater.last_error().is_none());
+
+    let download_fn = rt
+        .block_on(updater.as_async().download_latest())
+        .expect("couldn't download latest release");
+    assert!(download_fn.exists());
+
+    // A fresh updater picks up the result of the check.
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_version(VERSION_TEST);
+    assert_eq!(
+        Some(Version::parse(VERSION_TEST_NEW).unwrap()),
+        updater.latest_avail_version()
+    );
+}
+
+#[cfg(feature = "updater-async")]
+#[test]
+fn it_records_failed_async_checks() {
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(503);
+    first_check_after_installing_workflow();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert!(rt.block_on(updater.as_async().check()).is_err());
+    assert!(updater.last_error().is_some());
+    assert!(rt.block_on(updater.as_async().download_latest()).is_err());
+}
+
+#[test]
+fn it_doesnt_write_files_in_dry_run() {
+    use self::imp::LATEST_UPDATE_INFO_CACHE_FN_ASYNC;
+
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    let state_fn = Updater::<GithubReleaser>::build_data_fn().unwrap();
+    let status_fn = state_fn.with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
+
+    let updater = Updater::builder(MOCK_RELEASER_REPO_NAME)
+        .dry_run(true)
+        .build()
+        .expect("cannot build Updater");
+    assert!(!state_fn.exists());
+
+    // Without saved state, a dry run checks right away.
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(!state_fn.exists());
+    assert!(!status_fn.exists());
+
+    let download_fn = updater.download_latest().expect("couldn't download");
+    assert!(!download_fn.exists());
+    let item = updater.update_item().unwrap().expect("update item missing");
+    let item_fn = PathBuf::from(item.arg.unwrap().as_ref());
+    assert!(!item_fn.exists());
+}
+
+#[test]
+fn it_keeps_checks_saved_by_other_invocations() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let other = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let later = updater.last_check().unwrap() + Duration::seconds(60);
+    other.set_last_check(later);
+    other.save().unwrap();
+
+    // `updater` was loaded before `other` saved its check.
+    updater.save().unwrap();
+    assert_eq!(Some(later), updater.last_check());
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert_eq!(Some(later), updater.last_check());
+}
+
+#[test]
+fn it_resets_state() {
+    use self::imp::LATEST_UPDATE_INFO_CACHE_FN_ASYNC;
+
+    setup_workflow_env_vars(true);
+    let _m = setup_mock_server(200);
+    first_check_after_installing_workflow();
+    let state_fn = Updater::<GithubReleaser>::build_data_fn().unwrap();
+    let status_fn = state_fn.with_file_name(LATEST_UPDATE_INFO_CACHE_FN_ASYNC);
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(state_fn.exists());
+    assert!(status_fn.exists());
+
+    updater.reset_state().expect("couldn't reset state");
+    assert!(updater.latest_avail_version().is_none());
+    assert!(updater.last_checked().is_none());
+    assert!(!state_fn.exists());
+    assert!(!status_fn.exists());
+    assert!(!state_fn.with_extension("json.lock").exists());
+    assert!(!status_fn.with_extension("json.lock").exists());
+    assert!(updater.update_ready().is_err());
+
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert!(updater.latest_avail_version().is_none());
+    updater.init().expect("couldn't init worker");
+    assert!(!updater.update_ready().expect("couldn't check for update"));
+}
+
+#[test]
+fn it_saves_state_to_chosen_location() {
+    use self::imp::LATEST_UPDATE_INFO_CACHE_FN_ASYNC;
+
+    let cache_dir = setup_workflow_env_vars(true);
+    let data_dir = cache_dir.join("data");
+    std::fs::create_dir(&data_dir).unwrap();
+    StdEnv::set_var("alfred_workflow_data", &data_dir);
+    let _m = setup_mock_server(200);
+    let build = || {
+        Updater::builder(MOCK_RELEASER_REPO_NAME)
+            .state_dir(StateDir::Data)
+            .state_file_name("updater-state.json")
+            .interval(Duration::seconds(0))
+            .build()
+            .expect("cannot build Updater")
+    };
+
+    let updater = build();
+    let state_fn = data_dir.join("updater-state.json");
+    assert_eq!(state_fn, updater.state_file().unwrap());
+    assert!(state_fn.exists());
+    updater.init().expect("couldn't init worker");
+    assert!(!updater.update_ready().expect("couldn't check for update"));
+
+    let updater = build();
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+    assert!(data_dir.join(LATEST_UPDATE_INFO_CACHE_FN_ASYNC).exists());
+    assert!(!cache_dir.join(LATEST_UPDATE_INFO_CACHE_FN_ASYNC).exists());
+    assert!(!Updater::<GithubReleaser>::build_data_fn().unwrap().exists());
+
+    assert!(Updater::builder(MOCK_RELEASER_REPO_NAME)
+        .state_file_name("../updater.json")
+        .build()
+        .is_err());
+}
+
+#[test]
+fn it_migrates_state_of_older_versions() {
+    let _cache_dir = setup_workflow_env_vars(true);
+    let state_fn = Updater::<GithubReleaser>::build_data_fn().unwrap();
+    let last_check = Utc::now() - Duration::days(2);
+    let legacy = |avail_release: serde_json::Value| {
+        serde_json::json!({
+            "current_version": VERSION_TEST,
+            "last_check": last_check,
+            "avail_release": avail_release,
+        })
+    };
+
+    // State saved before it had a version.
+    let avail_release = serde_json::json!({
+        "version": VERSION_TEST_NEW,
+        "fetched_at": last_check,
+        "downloadable_url": "https://github.com/spamwax/alfred-pinboard-rs/releases/download/0.11.1/x.alfredworkflow",
+    });
+    crate::Data::save_to_path(&state_fn, &legacy(avail_release)).unwrap();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert_eq!(Some(last_check), updater.last_checked());
+    assert_eq!(
+        Some(Version::parse(VERSION_TEST_NEW).unwrap()),
+        updater.latest_avail_version()
+    );
+
+    // Fields that can't be read anymore are dropped, the rest is kept.
+    crate::Data::save_to_path(&state_fn, &legacy(serde_json::json!(42))).unwrap();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    assert_eq!(Some(last_check), updater.last_checked());
+    assert_eq!(None, updater.latest_avail_version());
+}
+
+#[test]
+fn it_keeps_history_of_checks() {
+    use self::history::CHECK_HISTORY_LEN;
+
+    let _dir = setup_workflow_env_vars(true);
+    let build = || {
+        Updater::builder(MOCK_RELEASER_REPO_NAME)
+            .interval(Duration::seconds(0))
+            .build()
+            .expect("cannot build Updater")
+    };
+    // First run of workflow doesn't check.
+    let updater = build();
+    updater.init().expect("couldn't init worker");
+    assert!(updater.check_history().unwrap().is_empty());
+
+    {
+        let _m = setup_mock_server(200);
+        let updater = build();
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().expect("couldn't check for update"));
+    }
+    let updater = build();
+    {
+        let _m = setup_mock_server(500);
+        updater.init().expect("couldn't init worker");
+        assert!(updater.update_ready().is_err());
+    }
+
+    let history = updater.check_history().unwrap();
+    assert_eq!(2, history.len());
+    assert!(history[0].succeeded());
+    assert_eq!(
+        Some(&Version::parse(VERSION_TEST_NEW).unwrap()),
+        history[0].version()
+    );
+    assert!(!history[1].succeeded());
+    assert!(history[1].error().is_some());
+    assert!(history[0].checked_at() <= history[1].checked_at());
+
+    // Only the most recent checks are kept.
+    let p = history::history_fn().unwrap();
+    for _ in 0..CHECK_HISTORY_LEN {
+        history::append(&p, CheckRecord::new(Err(&anyhow!("offline")))).unwrap();
+    }
+    let history = updater.check_history().unwrap();
+    assert_eq!(CHECK_HISTORY_LEN, history.len());
+    assert!(history.iter().all(|check| !check.succeeded()));
+}
+
+#[test]
+fn it_picks_alfred_bundle_id() {
+    type U = Updater<GithubReleaser>;
+    assert_eq!(
+        "com.runningwithcrayons.Alfred-3",
+        U::alfred_bundle_id(Some("3.8.1"))
+    );
+    assert_eq!(
+        "com.runningwithcrayons.Alfred",
+        U::alfred_bundle_id(Some("4.0.3"))
+    );
+    assert_eq!(
+        "com.runningwithcrayons.Alfred",
+        U::alfred_bundle_id(Some("5.5"))
+    );
+    assert_eq!("com.runningwithcrayons.Alfred", U::alfred_bundle_id(None));
+}
+
+#[test]
+fn it_auto_installs_a_release_only_once() {
+    let cache_dir = setup_workflow_env_vars(true);
+    let flag = cache_dir.join("auto_install_attempt.json");
+    let version = Version::parse(VERSION_TEST_NEW).unwrap();
+    // Nothing listens on this port, any attempt to download would fail.
+    let url = Url::parse("http://127.0.0.1:9/latest.alfredworkflow").unwrap();
+
+    assert!(Updater::<GithubReleaser>::auto_install(
+        &NetworkSettings::default(),
+        &version,
+        &url,
+        None,
+        &flag
+    )
+    .is_err());
+
+    crate::Data::save_to_file(&flag, &Some(&version)).unwrap();
+    Updater::<GithubReleaser>::auto_install(
+        &NetworkSettings::default(),
+        &version,
+        &url,
+        None,
+        &flag,
+    )
+    .expect("should skip already attempted release");
+    assert!(
+        !Updater::<GithubReleaser>::release_download_fn(Some(&version))
+            .unwrap()
+            .exists()
+    );
+}
+
+#[test]
+fn it_rejects_downloads_that_are_not_workflows() {
+    let cache_dir = setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+
+    let url = Url::parse(&format!(
+        "{}/dl/error.alfredworkflow",
+        mockito::server_url()
+    ))
+    .unwrap();
+    let _m = mock("GET", "/dl/error.alfredworkflow")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<html><body>Service Unavailable</body></html>")
+        .create();
+    let download_fn = cache_dir.join("error.alfredworkflow");
+    assert!(updater
+        .download_release(&url, None, download_fn.clone())
+        .is_err());
+    assert!(!download_fn.exists());
+    assert!(!Updater::<GithubReleaser>::partial_download_fn(&download_fn, &url).exists());
+}
+
+#[test]
+fn it_rejects_downloads_not_matching_checksum() {
+    setup_workflow_env_vars(true);
+    let latest = include_str!("../../tests/latest.json").replace(
+        WORKFLOW_SHA256,
+        "sha256:0000000000000000000000000000000000000000000000000000000000000000",
+    );
+    let _m = mock("GET", "/releases/latest")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(&latest)
+        .create();
+    let _d = mock(
+        "GET",
+        Matcher::Regex(r"^/releases/download/.*$".to_string()),
+    )
+    .with_status(200)
+    .with_header("content-type", "application/octet-stream")
+    .with_body(&include_bytes!("../../tests/workflow.alfredworkflow")[..])
+    .create();
+    first_check_after_installing_workflow();
+
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater.set_interval(0);
+    updater.init().expect("couldn't init worker");
+    assert!(updater.update_ready().expect("couldn't check for update"));
+
+    let err = updater.download_latest().unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"));
+    let download_fn = Updater::<GithubReleaser>::release_download_fn(None).unwrap();
+    let url = updater.state.download_url().unwrap();
+    assert!(!download_fn.exists());
+    assert!(!Updater::<GithubReleaser>::partial_download_fn(&download_fn, &url).exists());
+}
+
+#[test]
+fn it_cleans_up_stale_downloads() {
+    let cache_dir = setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let download_fn = |v: &str| {
+        Updater::<GithubReleaser>::release_download_fn(Some(&Version::parse(v).unwrap())).unwrap()
+    };
+
+    // Current version is VERSION_TEST (0.10.5)
+    let installed = download_fn("0.10.5");
+    let older = download_fn("0.9.0");
+    let newer = download_fn("0.11.1");
+    let partial = installed.with_file_name(format!(
+        "{}.0123456789abcdef.part",
+        older.file_name().unwrap().to_str().unwrap()
+    ));
+    let unrelated = cache_dir.join("my_data.json");
+    for p in &[&installed, &older, &newer, &partial, &unrelated] {
+        std::fs::write(p, "bundle").unwrap();
+    }
+
+    let mut removed = updater
+        .cleanup(time::Duration::from_secs(3600))
+        .expect("couldn't clean up");
+    removed.sort();
+    let mut expected = vec![installed, older, partial];
+    expected.sort();
+    assert_eq!(expected, removed);
+    assert!(newer.exists());
+    assert!(unrelated.exists());
+
+    // Everything is too old now.
+    let removed = updater
+        .cleanup(time::Duration::from_secs(0))
+        .expect("couldn't clean up");
+    assert_eq!(vec![newer], removed);
+    assert!(unrelated.exists());
+}
+
+#[test]
+fn it_cleans_up_stale_downloads_with_lenient_versions() {
+    setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    let latest_fn = Updater::<GithubReleaser>::release_download_fn(None).unwrap();
+    let download_fn = |v: &str| {
+        let name = latest_fn.file_name().unwrap().to_str().unwrap();
+        let name = name.replacen("latest_release_", "release_", 1).replacen(
+            ".alfredworkflow",
+            &format!("_{}.alfredworkflow", v),
+            1,
+        );
+        latest_fn.with_file_name(name)
+    };
+
+    // Current version is VERSION_TEST (0.10.5)
+    let older = download_fn("0.9");
+    let newer = download_fn("0.11");
+    for p in &[&older, &newer] {
+        std::fs::write(p, "bundle").unwrap();
+    }
+
+    let removed = updater
+        .cleanup(time::Duration::from_secs(3600))
+        .expect("couldn't clean up");
+    assert!(removed.is_empty());
+
+    updater.lenient_versions(true);
+    let removed = updater
+        .cleanup(time::Duration::from_secs(3600))
+        .expect("couldn't clean up");
+    assert_eq!(vec![older], removed);
+    assert!(newer.exists());
+}
+
+#[test]
+fn it_falls_back_to_download_mirrors() {
+    let cache_dir = setup_workflow_env_vars(true);
+    first_check_after_installing_workflow();
+    let mut updater = Updater::gh(MOCK_RELEASER_REPO_NAME).expect("cannot build Updater");
+    updater
+        .add_download_mirror(format!("{}/blocked{{path}}", mockito::server_url()))
+        .unwrap();
+    updater
+        .add_download_mirror(format!("{}/mirror{{path}}", mockito::server_url()))
+        .unwrap();
+
+    let _cdn = mock("GET", "/cdn/w.alfredworkflow")
+        .with_status(403)
+        .create();
+    let _blocked = mock("GET", "/blocked/cdn/w.alfredworkflow")
+        .with_status(200)
+        .with_body("<html>blocked</html>")
+        .create();
+    let _mirror = mock("GET", "/mirror/cdn/w.alfredworkflow")
+        .with_status(200)
+        .with_body(&include_bytes!("../../tests/workflow.alfredworkflow")[..])
+        .create();
+
+    let url = Url::parse(&format!("{}/cdn/w.alfredworkflow", mockito::server_url())).unwrap();
+    let p = updater
+        .download_release(&url, None, cache_dir.join("mirrored.alfredworkflow"))
+        .expect("couldn't download from mirror");
+    assert!(p.exists());
+}
+
+#[test]
+fn it_builds_configured_updater() {
+    setup_workflow_env_vars(true);
+    let updater = Updater::builder(MOCK_RELEASER_REPO_NAME)
+        .version("1.2")
+        .interval(Duration::days(7))
+        .update_policy(UpdatePolicy::PatchOnly)
+        .channel("beta")
+        .build()
+        .expect("cannot build Updater");
+    assert_eq!(&Version::new(1, 2, 0), updater.current_version());
+    assert_eq!(7 * 24 * 60 * 60, updater.update_interval());
+    assert!(updater.is_update(&Version::new(1, 2, 1)));
+    assert!(!updater.is_update(&Version::new(1, 3, 0)));
+
+    // Version is saved with the very first state of the updater.
+    let state_fn = Updater::<GithubReleaser>::build_data_fn().unwrap();
+    let saved: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(state_fn).unwrap()).unwrap();
+    assert_eq!("1.2.0", saved["current_version"]);
+
+    assert!(Updater::builder(MOCK_RELEASER_REPO_NAME)
+        .proxy("ftp://proxy.local")
+        .build()
+        .is_err());
+}
+
 pub(super) fn setup_workflow_env_vars(secure_temp_dir: bool) -> PathBuf {
     // Mimic Alfred's environment variables
+    // `TempDir::keep()` replaces `into_path()` from tempfile 3.20 on, which needs Rust 1.63.
+    #[allow(deprecated)]
     let path = if secure_temp_dir {
         Builder::new()
             .prefix("alfred_workflow_test")
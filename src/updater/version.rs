@@ -0,0 +1,72 @@
+use super::Version;
+
+// Parse `version` as a semantic version, normalizing common non-strict forms when strict
+// parsing fails:
+// - missing components are zero: `1.2` -> `1.2.0`, `3` -> `3.0.0`
+// - leading zeros are dropped: `2024.05.01` -> `2024.5.1`
+// - components after the third are kept as build metadata: `1.2.3.4` -> `1.2.3+4`
+pub(super) fn parse_lenient(version: &str) -> Option<Version> {
+    let version = version.trim();
+    if let Ok(v) = Version::parse(version) {
+        return Some(v);
+    }
+
+    let (rest, build) = match version.find('+') {
+        Some(idx) => (&version[..idx], Some(&version[idx + 1..])),
+        None => (version, None),
+    };
+    let (core, pre) = match rest.find('-') {
+        Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+        None => (rest, None),
+    };
+    let numbers = core
+        .split('.')
+        .map(|n| n.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut normalized = format!(
+        "{}.{}.{}",
+        numbers[0],
+        numbers.get(1).unwrap_or(&0),
+        numbers.get(2).unwrap_or(&0)
+    );
+    if let Some(pre) = pre {
+        normalized.push('-');
+        normalized.push_str(pre);
+    }
+    let mut build_ids = numbers
+        .iter()
+        .skip(3)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    build_ids.extend(build.map(String::from));
+    if !build_ids.is_empty() {
+        normalized.push('+');
+        normalized.push_str(&build_ids.join("."));
+    }
+    debug!("  normalized version {} to {}", version, normalized);
+    Version::parse(&normalized).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_normalizes_non_strict_versions() {
+        let parse = |v| parse_lenient(v).map(|v| v.to_string());
+
+        assert_eq!(Some("1.2.3".to_string()), parse("1.2.3"));
+        assert_eq!(Some("1.2.0".to_string()), parse("1.2"));
+        assert_eq!(Some("3.0.0".to_string()), parse("3"));
+        assert_eq!(Some("2024.5.1".to_string()), parse("2024.05.01"));
+        assert_eq!(Some("1.2.3+4".to_string()), parse("1.2.3.4"));
+        assert_eq!(Some("1.2.0-beta.1".to_string()), parse("1.2-beta.1"));
+        assert_eq!(Some("1.2.3+4.abc".to_string()), parse("1.2.3.4+abc"));
+        assert!(parse_lenient("1.2.3.4").unwrap() < parse_lenient("1.2.3.5").unwrap());
+
+        assert_eq!(None, parse("latest"));
+        assert_eq!(None, parse("1..2"));
+        assert_eq!(None, parse(""));
+    }
+}
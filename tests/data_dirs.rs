@@ -0,0 +1,23 @@
+use alfred_rs::data::Data;
+
+// Runs in its own process, without Alfred's environment variables.
+#[test]
+fn it_uses_dirs_set_by_workflow() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    assert!(Data::load("settings.json").is_err());
+
+    Data::set_data_dir(data_dir.path());
+    Data::set_cache_dir(cache_dir.path());
+    assert_eq!(Some(data_dir.path().to_path_buf()), Data::data_dir());
+    assert_eq!(Some(cache_dir.path().to_path_buf()), Data::cache_dir());
+
+    let mut settings = Data::load("settings.json").unwrap();
+    settings.set("page_size", &20).unwrap();
+    assert!(data_dir.path().join("settings.json").exists());
+
+    Data::save_to_file("tags.json", &vec!["rust"]).unwrap();
+    assert!(cache_dir.path().join("tags.json").exists());
+    let tags: Vec<String> = Data::load_from_file("tags.json").unwrap();
+    assert_eq!(vec!["rust".to_string()], tags);
+}